@@ -11,16 +11,56 @@
 
 use adw::{prelude::*, subclass::prelude::*};
 use gtk::{gio, glib, glib::FromVariant, glib::WeakRef};
-use std::cell::RefCell;
+use std::{
+  cell::{Cell, RefCell},
+  collections::{HashMap, HashSet},
+  rc::Rc,
+};
 
 use crate::config;
+use crate::dbus::{Request, SearchProvider, SearchRequest, Service};
+use crate::model::feed_uri_to_url;
+use crate::model::find_duplicates;
+use crate::model::get_other_feed_titles;
+use crate::model::get_self_link;
+use crate::model::is_opml;
+use crate::model::normalize_url;
+use crate::model::open_credential_store;
+use crate::model::parse_opml;
+use crate::model::set_ca_certificates;
+use crate::model::set_pause_on_metered;
+use crate::model::Authentication;
+use crate::model::CredentialStore;
+use crate::model::Credentials;
 use crate::model::Feed;
+use crate::model::FeedItem;
+use crate::model::FeedSource;
+use crate::model::FeedState;
+use crate::model::FilterPreset;
+use crate::model::HighlightRule;
+use crate::model::ItemIndex;
+use crate::model::MuteRule;
+use crate::model::NetworkState;
+use crate::model::RuleMatcher;
 use crate::model::StoredFeed;
+use crate::model::StoredItem;
+use crate::model::ThumbnailCache;
+use crate::model::WebSubEvent;
+use crate::model::WebSubServer;
+use crate::view::PreferencesWindow;
 use crate::view::Window;
 
+// GNOME Shell only shows the first few results of each search provider. This is the
+// maximum number of items which are looked up in the ItemIndex for a search.
+const MAX_SEARCH_RESULTS: u32 = 100;
+
 // ---------------------------------------------------------------------------------------
 // The application of BingeRSS is derived from adw::Application. It does not have any
 // additional public methods; all the setup happens in the overridden activate() methods.
+// Besides the actions, the feeds can be queried and controlled via the D-Bus interface
+// io.github.schneegans.BingeRSS which is exported on the application's bus name. The
+// items of all feeds can also be found with the search of GNOME Shell. If BingeRSS is
+// asked to open feed:// URIs or files, the overridden open() method adds the feeds.
 glib::wrapper! {
   pub struct Application(ObjectSubclass<imp::Application>)
     @extends gio::Application, gtk::Application, adw::Application,
@@ -34,6 +74,7 @@ impl Application {
   pub fn new() -> Self {
     glib::Object::builder()
       .property("application-id", &Some(config::APP_ID))
+      .property("flags", gio::ApplicationFlags::HANDLES_OPEN)
       .build()
   }
 
@@ -42,6 +83,7 @@ impl Application {
   // This creates all the actions which glue together all the parts of BingeRSS. There are
   // currently these actions available:
   //   app.about():          Shows the about dialog.
+  //   app.preferences():    Shows the preferences window.
   //   app.quit():           Quits the application.
   //   app.add-feed():       Adds a new empty feed.
   //   app.remove-feed():    Removes the currently selected feed and shows a undo-toast.
@@ -49,6 +91,11 @@ impl Application {
   //   app.undo-remove(id):  Re-adds a previously deleted feed. The ID of the
   //                         to-be-re-added feed has to be given as parameter.
   //   app.refresh():        Re-downloads all feeds.
+  //   app.toggle-star(item): Stars or unstars a feed item. The item has to be given as a
+  //                         JSON-serialized StoredItem.
+  //   app.search():         Shows the global search.
+  //   app.mark-read(url):   Marks the item with the given URL and all its duplicates in
+  //                         other feeds as read.
   fn setup_actions(&self) {
     let window = self.main_window();
 
@@ -74,6 +121,20 @@ impl Application {
       self.add_action(&action);
     }
 
+    // Show the preferences window if app.preferences() is called. It can also be opened
+    // with Ctrl+Comma.
+    {
+      let action = gio::SimpleAction::new("preferences", None);
+      action.connect_activate(glib::clone!(@weak window => move |_, _| {
+        let preferences = PreferencesWindow::new();
+        preferences.set_transient_for(Some(&window));
+        preferences.present();
+      }));
+
+      self.add_action(&action);
+      self.set_accels_for_action("app.preferences", &["<Ctrl>comma"]);
+    }
+
     // Quit BingeRSS if app.quit() is called.
     {
       let action = gio::SimpleAction::new("quit", None);
@@ -87,20 +148,17 @@ impl Application {
     // Add a new empty feed if app.add-feed() is called.
     {
       let action = gio::SimpleAction::new("add-feed", None);
-      action.connect_activate(
-        glib::clone!(@weak self as this, @weak window => move |_, _| {
-
-          // First, create the new feed.
-          let feed = Feed::new(&"New Feed".into(), &"".into(), &"".into(),
-            chrono::Utc::now().timestamp());
+      action.connect_activate(glib::clone!(@weak self as this => move |_, _| {
 
-          // Then add it to the user interface.
-          window.add_feed(&feed);
+        // First, create the new feed. If the feeds are ordered manually, it will be
+        // shown at the bottom of the sidebar.
+        let feed = Feed::new(&"New Feed".into(), &"".into(), &"".into(),
+          chrono::Utc::now().timestamp());
+        feed.set_property("position", this.imp().feeds.borrow().len() as u32);
 
-          // Finally, store it in the list of all feeds.
-          this.imp().feeds.borrow_mut().push(feed);
-        }),
-      );
+        // Then add it to the user interface and store it in the list of all feeds.
+        this.add_feed(feed);
+      }));
       self.add_action(&action);
     }
 
@@ -129,13 +187,13 @@ impl Application {
             format!("Removed '{}'", this.imp().feeds.borrow()[i].get_title()).as_str(),
             "Undo",
             "app.undo-remove",
-            &id.unwrap().to_variant());
+            &id.as_ref().unwrap().to_variant());
 
           // Remove the feed from the list of all feeds and add it to the list of all
           // removed feeds instead. This will allow us to later undo the deletion of the
           // feed.
-          let feed = this.imp().feeds.borrow_mut().remove(i);
-          this.imp().removed_feeds.borrow_mut().push(feed);
+          let feed = this.remove_feed(&id.unwrap());
+          this.imp().removed_feeds.borrow_mut().extend(feed);
         }),
       );
       self.add_action(&action);
@@ -145,26 +203,24 @@ impl Application {
     // button in the toast which is shown whenever a feed is deleted.
     {
       let action = gio::SimpleAction::new("undo-remove", Some(glib::VariantTy::STRING));
-      action.connect_activate(
-        glib::clone!(@weak self as this, @weak window => move |_, id| {
-            if id.is_some() {
-
-              // First, get the index of the to-be-restored feed from the list of all
-              // previously removed feeds.
-              let i = this.imp().removed_feeds.borrow().iter().position(|f| {
-                f.get_id().eq(&String::from_variant(id.unwrap()).unwrap())
-              });
-
-              // Then remove the feed from the list of removed feeds, re-add it to the
-              // user interface and store it in the real feed list.
-              if i.is_some() {
-                let feed = this.imp().removed_feeds.borrow_mut().remove(i.unwrap());
-                window.add_feed(&feed);
-                this.imp().feeds.borrow_mut().push(feed);
-              }
-            }
-        }),
-      );
+      action.connect_activate(glib::clone!(@weak self as this => move |_, id| {
+        if id.is_some() {
+
+          // First, get the index of the to-be-restored feed from the list of all
+          // previously removed feeds.
+          let i = this.imp().removed_feeds.borrow().iter().position(|f| {
+            f.get_id().eq(&String::from_variant(id.unwrap()).unwrap())
+          });
+
+          // Then remove the feed from the list of removed feeds, re-add it to the user
+          // interface and store it in the real feed list.
+          if i.is_some() {
+            let feed = this.imp().removed_feeds.borrow_mut().remove(i.unwrap());
+            this.add_feed(feed);
+            this.update_duplicates();
+          }
+        }
+      }));
       self.add_action(&action);
     }
 
@@ -186,50 +242,139 @@ impl Application {
       }));
       self.add_action(&action);
     }
+
+    // The app.search() action shows the global search. It can also be triggered with
+    // Ctrl+F.
+    {
+      let action = gio::SimpleAction::new("search", None);
+      action.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.show_search();
+      }));
+      self.add_action(&action);
+      self.set_accels_for_action("app.search", &["<Ctrl>f"]);
+    }
+
+    // The app.toggle-star(item) action adds the given item to the list of starred items.
+    // If it is already starred, it will be removed from this list instead. Starred items
+    // are identified by their URL. Invalid items are ignored.
+    {
+      let action = gio::SimpleAction::new("toggle-star", Some(glib::VariantTy::STRING));
+      action.connect_activate(glib::clone!(@weak self as this => move |_, item| {
+        let json = match item.and_then(String::from_variant) {
+          Some(json) => json,
+          None => return,
+        };
+
+        let stored: StoredItem = match serde_json::from_str(json.as_str()) {
+          Ok(stored) => stored,
+          Err(err) => {
+            println!("Failed to parse starred item {}: {}", json, err);
+            return;
+          }
+        };
+
+        // First check whether the item is already in the list of starred items.
+        let starred = &this.imp().starred;
+        let position = (0..starred.n_items()).find(|i| {
+          let item = starred.item(*i).unwrap().downcast::<FeedItem>().unwrap();
+          *item.get_url() == stored.url
+        });
+
+        // Then add or remove it.
+        let is_starred = if let Some(position) = position {
+          starred.remove(position);
+          false
+        } else {
+          let item = FeedItem::from_stored(&stored);
+          item.set_property("starred", true);
+          starred.insert(0, &item);
+          true
+        };
+
+        // Finally update the starred-state of all items of all feeds with the same URL.
+        for feed in this.imp().feeds.borrow().iter() {
+          feed
+            .get_items()
+            .iter()
+            .filter(|i| i.get_url().eq(&stored.url))
+            .for_each(|i| i.set_property("starred", is_starred));
+        }
+
+        // Save the starred items right away so that they are not lost if the application
+        // does not exit cleanly.
+        this.save_starred_items();
+      }));
+      self.add_action(&action);
+    }
+
+    // The app.mark-read(url) action is called whenever an item is opened. It marks the
+    // item and all its duplicates in other feeds as read.
+    {
+      let action = gio::SimpleAction::new("mark-read", Some(glib::VariantTy::STRING));
+      action.connect_activate(glib::clone!(@weak self as this => move |_, url| {
+        if let Some(url) = url.and_then(String::from_variant) {
+          this.mark_read(&url);
+        }
+      }));
+      self.add_action(&action);
+    }
   }
 
   // The feeds are stored in a JSON string under the settings key
   // io.github.schneegans.bingerss.feeds. This method retrieves the JSON string, and
   // creates Feed objects accordingly. The newly created Feed objects are added to the
-  // user interface.
+  // user interface. As the credentials of the feeds are loaded asynchronously from the
+  // keyring, the feeds are added one after another once their credentials are known.
   fn load_feeds(&self) {
-    // Load the JSON string.
-    let data = self.imp().settings.string("feeds");
+    let ctx = glib::MainContext::default();
+    ctx.spawn_local(glib::clone!(@weak self as this => async move {
+      let store = this.imp().credential_store.clone();
 
-    // Parse it.
-    let stored_feeds: Vec<StoredFeed> =
-      serde_json::from_str(data.as_str()).expect("valid json");
+      for f in this.get_stored_feeds().iter() {
+        let credentials = if f.secret.is_empty() {
+          None
+        } else {
+          match store.lookup(&f.secret).await {
+            Ok(credentials) => credentials,
+            Err(err) => {
+              println!("Failed to load credentials for {}: {}", f.url, err);
+              None
+            }
+          }
+        };
 
-    // Create a Feed for each StoredFeed.
-    self.imp().feeds.replace(
-      stored_feeds
-        .iter()
-        .map(|f| Feed::new(&f.title, &f.url, &f.filter, f.viewed))
-        .collect(),
-    );
+        this.add_feed(Feed::from_stored(f, credentials));
+      }
 
-    // Add all feeds to the user interface.
-    for feed in self.imp().feeds.borrow_mut().iter_mut() {
-      self.main_window().add_feed(&feed);
-    }
+      // The feeds must not be saved before all of them have been loaded, else the
+      // remaining ones would be lost.
+      this.imp().feeds_loaded.set(true);
+      this.apply_filter_presets();
+    }));
+  }
+
+  // Retrieves the JSON string stored under the settings key
+  // io.github.schneegans.bingerss.feeds and parses it.
+  fn get_stored_feeds(&self) -> Vec<StoredFeed> {
+    let data = self.imp().settings.string("feeds");
+    serde_json::from_str(data.as_str()).expect("valid json")
   }
 
   // The feeds are stored in a JSON string under the settings key
   // io.github.schneegans.bingerss.feeds. This method converts all current Feeds to a JSON
   // string, and saves this data under the settings key.
   fn save_feeds(&self) {
+    if !self.imp().feeds_loaded.get() {
+      return;
+    }
+
     // Create a StoredFeed for each Feed.
     let stored_feeds: Vec<StoredFeed> = self
       .imp()
       .feeds
       .borrow()
       .iter()
-      .map(|f| StoredFeed {
-        title: f.get_title().clone(),
-        url: f.get_url().clone(),
-        filter: f.get_filter().clone(),
-        viewed: f.get_viewed().clone(),
-      })
+      .map(|f| f.to_stored())
       .collect();
 
     // Serialize the data to JSON.
@@ -241,6 +386,1043 @@ impl Application {
       .settings
       .set_string("feeds", &json)
       .expect("Failed to write settings!");
+
+    self.save_credentials();
+  }
+
+  // Writes the credentials of all feeds to the keyring. Credentials of feeds which have
+  // been removed or which do not require authentication anymore are deleted from the
+  // keyring. If a removed feed is restored, its credentials are written again the next
+  // time the feeds are saved.
+  fn save_credentials(&self) {
+    let store = &self.imp().credential_store;
+
+    for feed in self.imp().feeds.borrow().iter() {
+      let result = match feed.get_credentials().as_ref() {
+        Some(credentials) => {
+          let label = format!("BingeRSS credentials for {}", feed.get_title());
+          store.store(&feed.get_secret(), &label, credentials)
+        }
+        None if !feed.get_secret().is_empty() => store.clear(&feed.get_secret()),
+        None => Ok(()),
+      };
+
+      if let Err(err) = result {
+        println!("Failed to save credentials for {}: {}", feed.get_url(), err);
+      }
+    }
+
+    for feed in self.imp().removed_feeds.borrow().iter() {
+      if feed.get_secret().is_empty() {
+        continue;
+      }
+
+      if let Err(err) = store.clear(&feed.get_secret()) {
+        println!(
+          "Failed to remove credentials for {}: {}",
+          feed.get_url(),
+          err
+        );
+      }
+    }
+  }
+
+  // Asks the user for a user name and a password for the given feed. This is called if
+  // the server of the feed responds with 401 Unauthorized. The entered credentials are
+  // used for Basic authentication; custom headers of the feed are kept. Other kinds of
+  // authentication can be configured on the feed's page. There is at most one dialog
+  // per feed.
+  fn prompt_credentials(&self, feed: &Feed) {
+    if !self
+      .imp()
+      .auth_prompts
+      .borrow_mut()
+      .insert(feed.get_id().clone())
+    {
+      return;
+    }
+
+    let username = adw::EntryRow::builder().title("User Name").build();
+    let password = adw::PasswordEntryRow::builder().title("Password").build();
+
+    if let Some(Credentials {
+      auth: Authentication::Basic { username: name, .. },
+      ..
+    }) = feed.get_credentials().as_ref()
+    {
+      username.set_text(name);
+    }
+
+    let list_box = gtk::ListBox::builder()
+      .selection_mode(gtk::SelectionMode::None)
+      .build();
+    list_box.add_css_class("boxed-list");
+    list_box.append(&username);
+    list_box.append(&password);
+
+    let dialog = adw::MessageDialog::builder()
+      .transient_for(&self.main_window())
+      .modal(true)
+      .heading("Authentication Required")
+      .body(&format!(
+        "The server of {} requires a user name and a password.",
+        feed.get_title()
+      ))
+      .extra_child(&list_box)
+      .build();
+    dialog.add_response("cancel", "_Cancel");
+    dialog.add_response("login", "_Log In");
+    dialog.set_response_appearance("login", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("login"));
+    dialog.set_close_response("cancel");
+
+    dialog.connect_response(
+      None,
+      glib::clone!(@weak self as this, @weak feed => move |_, response| {
+        this.imp().auth_prompts.borrow_mut().remove(&*feed.get_id());
+
+        if response == "login" {
+          let headers = feed
+            .get_credentials()
+            .as_ref()
+            .map(|credentials| credentials.headers.clone())
+            .unwrap_or_default();
+
+          feed.set_credentials(Some(Credentials {
+            auth: Authentication::Basic {
+              username: username.text().to_string(),
+              password: password.text().to_string(),
+            },
+            headers,
+          }));
+
+          this.save_feeds();
+        }
+      }),
+    );
+
+    dialog.present();
+  }
+
+  // The starred items are stored in a JSON string under the settings key
+  // io.github.schneegans.bingerss.starred-items. This method retrieves the JSON string,
+  // creates FeedItem objects accordingly, and shows them in the user interface.
+  fn load_starred_items(&self) {
+    let data = self.imp().settings.string("starred-items");
+
+    let stored_items: Vec<StoredItem> =
+      serde_json::from_str(data.as_str()).expect("valid json");
+
+    for stored in stored_items.iter() {
+      let item = FeedItem::from_stored(stored);
+      item.set_property("starred", true);
+      self.imp().starred.append(&item);
+    }
+
+    self.main_window().set_starred_items(&self.imp().starred);
+  }
+
+  // This converts all starred items to a JSON string and saves it under the settings key
+  // io.github.schneegans.bingerss.starred-items.
+  fn save_starred_items(&self) {
+    let starred = &self.imp().starred;
+    let stored_items: Vec<StoredItem> = (0..starred.n_items())
+      .map(|i| {
+        starred
+          .item(i)
+          .unwrap()
+          .downcast::<FeedItem>()
+          .unwrap()
+          .to_stored()
+      })
+      .collect();
+
+    let json = serde_json::to_string(&stored_items).unwrap();
+
+    self
+      .imp()
+      .settings
+      .set_string("starred-items", &json)
+      .expect("Failed to write settings!");
+  }
+
+  // Loads the global mute rules from the settings and applies them to all feeds. As
+  // muted items are not shown in the "All Items" view, the duplicates are updated as
+  // well.
+  fn load_mute_rules(&self) {
+    let rules = PreferencesWindow::get_mute_rules(&self.imp().settings);
+
+    for feed in self.imp().feeds.borrow().iter() {
+      feed.set_mute_rules(&rules);
+    }
+
+    self.imp().mute_rules.replace(rules);
+    self.update_duplicates();
+  }
+
+  // Loads the additional certificate authorities configured in the preferences. They are
+  // trusted for all subsequent requests. Files which cannot be loaded are reported and
+  // skipped.
+  fn load_ca_certificates(&self) {
+    let paths = PreferencesWindow::get_ca_certificates(&self.imp().settings);
+
+    for err in set_ca_certificates(&paths) {
+      println!("{}", err);
+    }
+  }
+
+  // Shows a banner if the feeds cannot be refreshed, either because the network is
+  // unavailable or because refreshing is paused on metered connections. When the feeds
+  // can be refreshed automatically again, all feeds are refreshed right away.
+  fn update_network_state(&self) {
+    set_pause_on_metered(self.imp().settings.boolean("pause-on-metered"));
+
+    let state = NetworkState::current();
+    let previous = self.imp().network_state.replace(state);
+
+    if let Some(window) = self.imp().window.upgrade() {
+      match state {
+        NetworkState::Offline => window.show_network_banner(
+          Some("You are offline. The feeds will be refreshed once you are online again."),
+          false,
+        ),
+        NetworkState::Paused => window.show_network_banner(
+          Some("Automatic refreshing is paused on this metered connection."),
+          true,
+        ),
+        NetworkState::Online => window.show_network_banner(None, false),
+      }
+    }
+
+    if state == NetworkState::Online && previous != NetworkState::Online {
+      for feed in self.imp().feeds.borrow().iter() {
+        feed.refresh();
+      }
+    }
+  }
+
+  // Starts the listener for WebSub callbacks if a callback URL is configured and
+  // subscribes all feeds which link to a hub. The subscriptions of a previously running
+  // listener are canceled and the listener is stopped; this waits until its port is free
+  // again. If the callback URL did not change, the new listener answers the hubs'
+  // verifications of these cancellations.
+  fn start_websub_server(&self) {
+    let pending = self.imp().websub_server.take().map(WebSubServer::stop);
+
+    let callback = self.imp().settings.string("websub-callback");
+    if callback.is_empty() {
+      return;
+    }
+
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    let address = self.imp().settings.string("websub-address");
+    let port = self.imp().settings.int("websub-port") as u16;
+    let server = match WebSubServer::start(&callback, &address, port, sender) {
+      Ok(server) => server,
+      Err(err) => {
+        println!(
+          "Failed to listen for WebSub callbacks on {}:{}: {}",
+          address, port, err
+        );
+        return;
+      }
+    };
+
+    if let Some(pending) = pending {
+      server.take_over(pending);
+    }
+
+    self.imp().websub_server.replace(Some(server));
+
+    receiver.attach(
+      None,
+      glib::clone!(@weak self as this => @default-return glib::Continue(false),
+        move |event| {
+          this.handle_websub_event(event);
+          glib::Continue(true)
+        }
+      ),
+    );
+
+    for feed in self.imp().feeds.borrow().iter() {
+      self.update_websub_subscription(feed);
+    }
+  }
+
+  // Subscribes the given feed at its WebSub hub or cancels its subscription if it does
+  // not link to a hub anymore. This does nothing if the listener is not running.
+  fn update_websub_subscription(&self, feed: &Feed) {
+    if let Some(server) = self.imp().websub_server.borrow().as_ref() {
+      match feed.get_websub_links().as_ref() {
+        Some(links) => server.subscribe(&feed.get_id(), links),
+        None => server.unsubscribe(&feed.get_id()),
+      }
+    }
+  }
+
+  // Handles the callbacks of the WebSub hubs. Verified subscriptions are renewed shortly
+  // before their lease expires. Pushed content is added to the corresponding feed right
+  // away.
+  fn handle_websub_event(&self, event: WebSubEvent) {
+    match event {
+      WebSubEvent::Verified {
+        token,
+        mode,
+        lease_seconds: Some(lease_seconds),
+        ..
+      } if mode == "subscribe" => {
+        glib::timeout_add_seconds_local_once(
+          (lease_seconds - lease_seconds / 10).max(60),
+          glib::clone!(@weak self as this => move || {
+            if let Some(server) = this.imp().websub_server.borrow().as_ref() {
+              server.renew(&token);
+            }
+          }),
+        );
+      }
+      WebSubEvent::Verified { .. } => {}
+      WebSubEvent::Denied { feed_id, reason } => {
+        println!(
+          "WebSub subscription of feed {} was denied: {}",
+          feed_id, reason
+        );
+      }
+      WebSubEvent::Content { feed_id, content } => match self.get_feed(&feed_id) {
+        Ok(feed) => feed.push_content(*content),
+        Err(err) => println!("Failed to handle pushed content: {}", err),
+      },
+    }
+  }
+
+  // Copies the filter of each filter preset to all feeds using it. Feeds which use a
+  // preset which does not exist anymore keep their filter but do not use a preset
+  // anymore.
+  fn apply_filter_presets(&self) {
+    let presets = PreferencesWindow::get_filter_presets(&self.imp().settings);
+
+    for feed in self.imp().feeds.borrow().iter() {
+      let name = feed.get_preset().clone();
+
+      if name.is_empty() {
+        continue;
+      }
+
+      match FilterPreset::get_filter(&presets, &name) {
+        Some(filter) => {
+          if *feed.get_filter() != filter {
+            feed.set_property("filter", filter);
+          }
+        }
+        None => feed.set_property("preset", ""),
+      }
+    }
+  }
+
+  // Loads the global highlight rules from the settings and applies them to all feeds.
+  fn load_highlight_rules(&self) {
+    let rules = PreferencesWindow::get_highlight_rules(&self.imp().settings);
+
+    for feed in self.imp().feeds.borrow().iter() {
+      feed.set_highlight_rules(&rules);
+    }
+
+    self.imp().highlight_rules.replace(rules);
+  }
+
+  // Opens the ItemIndex which stores the history of all downloaded items and is used for
+  // the global search and the search provider. If this fails, both will not be
+  // available.
+  fn load_item_index(&self) {
+    match ItemIndex::open() {
+      Ok(index) => {
+        self.imp().index.replace(Some(Rc::new(index)));
+      }
+      Err(err) => println!("Failed to open item index: {}", err),
+    }
+  }
+
+  // Marks the item with the given URL and all its duplicates as read. This includes the
+  // starred items. The normalized URLs of all these items are stored in the ItemIndex so
+  // that they remain read after a restart.
+  fn mark_read(&self, url: &str) {
+    let url = normalize_url(url);
+    let mut urls = vec![url.clone()];
+
+    let groups = find_duplicates(&self.get_all_items());
+    let group = groups
+      .iter()
+      .find(|group| group.iter().any(|i| normalize_url(&i.get_url()) == url));
+
+    for item in group.into_iter().flatten() {
+      item.set_property("read", true);
+      urls.push(normalize_url(&item.get_url()));
+    }
+
+    let starred = &self.imp().starred;
+    for i in 0..starred.n_items() {
+      let item = starred.item(i).unwrap().downcast::<FeedItem>().unwrap();
+      if urls.contains(&normalize_url(&item.get_url())) {
+        item.set_property("read", true);
+      }
+    }
+
+    if let Some(index) = self.imp().index.borrow().as_ref() {
+      if let Err(err) = index.mark_read(&urls) {
+        println!("Failed to store read state of {}: {}", url, err);
+      }
+    }
+  }
+
+  // Detects stories which are contained in multiple feeds. The items of each story are
+  // told about the other feeds containing the story. If one of them has been read, all
+  // of them are marked as read. Finally, the list shown in the "All Items" view is
+  // updated so that it contains each story only once.
+  fn update_duplicates(&self) {
+    let groups = find_duplicates(&self.get_all_items());
+    let mut newly_read = vec![];
+
+    for group in groups.iter() {
+      let is_read = group.iter().any(|i| i.is_read());
+
+      for item in group {
+        item.set_also_in(get_other_feed_titles(group, item));
+
+        if is_read && !item.is_read() {
+          item.set_property("read", true);
+          newly_read.push(normalize_url(&item.get_url()));
+        }
+      }
+    }
+
+    if let Some(index) = self.imp().index.borrow().as_ref() {
+      if let Err(err) = index.mark_read(&newly_read) {
+        println!("Failed to store read state: {}", err);
+      }
+    }
+
+    let stories: Vec<FeedItem> = groups.iter().map(|group| group[0].clone()).collect();
+    let all_items = &self.imp().all_items;
+    all_items.splice(0, all_items.n_items(), &stories);
+  }
+
+  // Returns the items of all feeds, sorted by date with the newest item first. Muted
+  // items are not included.
+  fn get_all_items(&self) -> Vec<FeedItem> {
+    let mut items: Vec<FeedItem> = self
+      .imp()
+      .feeds
+      .borrow()
+      .iter()
+      .flat_map(|feed| feed.get_items().clone())
+      .filter(|item| !item.is_muted())
+      .collect();
+
+    items.sort_by(|a, b| b.get_date().cmp(&a.get_date()));
+    items
+  }
+
+  // Removes the feed with the given ID from the list of all feeds and returns it. The
+  // items of the removed feed are not part of the unified views anymore. This does not
+  // remove the feed from the user interface.
+  fn remove_feed(&self, id: &String) -> Option<Feed> {
+    let i = self
+      .imp()
+      .feeds
+      .borrow()
+      .iter()
+      .position(|f| f.get_id().eq(id))?;
+
+    let feed = self.imp().feeds.borrow_mut().remove(i);
+    self.imp().known_items.borrow_mut().remove(id);
+
+    if let Some(server) = self.imp().websub_server.borrow().as_ref() {
+      server.unsubscribe(id);
+    }
+
+    self.update_duplicates();
+
+    Some(feed)
+  }
+
+  // Returns the feed with the given ID. If there is no such feed, an error message is
+  // returned which can be passed to D-Bus clients.
+  fn get_feed(&self, id: &String) -> Result<Feed, String> {
+    self
+      .imp()
+      .feeds
+      .borrow()
+      .iter()
+      .find(|f| f.get_id().eq(id))
+      .cloned()
+      .ok_or_else(|| format!("There is no feed with the ID '{}'", id))
+  }
+
+  // Marks all items of the given feed as read. This also updates the time the feed was
+  // last viewed. Duplicates of the items in other feeds are marked as read as well.
+  fn mark_feed_read(&self, feed: &Feed) {
+    let urls: Vec<String> = feed
+      .get_items()
+      .iter()
+      .map(|item| {
+        item.set_property("read", true);
+        normalize_url(&item.get_url())
+      })
+      .collect();
+
+    if let Some(index) = self.imp().index.borrow().as_ref() {
+      if let Err(err) = index.mark_read(&urls) {
+        println!("Failed to store read state of {}: {}", feed.get_url(), err);
+      }
+    }
+
+    feed.set_viewed();
+    self.update_duplicates();
+  }
+
+  // Exports the D-Bus service on the application's bus name. The service is backed by
+  // the same Feed objects as the user interface. If the application is not connected to
+  // a session bus, nothing will happen.
+  fn export_dbus_service(&self) {
+    let (connection, path) = match (self.dbus_connection(), self.dbus_object_path()) {
+      (Some(connection), Some(path)) => (connection, path),
+      _ => return,
+    };
+
+    let result = Service::register(
+      &connection,
+      &path,
+      glib::clone!(@weak self as this => @default-return Err("Shutting down".into()),
+        move |request| this.handle_dbus_request(request)
+      ),
+    );
+
+    match result {
+      Ok(service) => {
+        self.imp().dbus_service.replace(Some(service));
+      }
+      Err(err) => println!("Failed to export D-Bus service: {}", err),
+    }
+  }
+
+  // Executes a method call of the D-Bus service. This returns the reply or an error
+  // message which is passed to the caller.
+  fn handle_dbus_request(
+    &self,
+    request: Request,
+  ) -> Result<Option<glib::Variant>, String> {
+    match request {
+      Request::ListFeeds => Ok(Some(Service::feed_list(&self.imp().feeds.borrow()))),
+      Request::AddFeed { url, title, filter } => {
+        if FeedSource::is_command(&url) {
+          return Err(String::from(
+            "Command feeds can only be added in the user interface",
+          ));
+        }
+
+        let title = if title.is_empty() {
+          String::from("New Feed")
+        } else {
+          title
+        };
+
+        // The URL is set after the feed has been added so that the download is noticed.
+        let feed = Feed::new(&title, &"".into(), &filter, chrono::Utc::now().timestamp());
+        feed.set_property("position", self.imp().feeds.borrow().len() as u32);
+
+        let id = feed.get_id().clone();
+        self.add_feed(feed.clone());
+        feed.set_property("url", url);
+        self.save_feeds();
+
+        Ok(Some((id,).to_variant()))
+      }
+      Request::RemoveFeed(id) => {
+        self.get_feed(&id)?;
+        self.main_window().remove_feed(&id);
+        self.remove_feed(&id);
+        self.save_feeds();
+        Ok(None)
+      }
+      Request::Refresh(id) => {
+        self.get_feed(&id)?.download();
+        Ok(None)
+      }
+      Request::GetUnreadCounts => {
+        Ok(Some(Service::unread_counts(&self.imp().feeds.borrow())))
+      }
+      Request::MarkRead(id) => {
+        self.mark_feed_read(&self.get_feed(&id)?);
+        Ok(None)
+      }
+    }
+  }
+
+  // Emits the D-Bus signals for a finished download of the given feed. The NewItems
+  // signal contains all items which were not part of the previous download. Hence, it
+  // is not emitted for the first download of a feed. Muted items and items which do not
+  // match the feed's filter are not announced.
+  fn emit_dbus_signals(&self, feed: &Feed) {
+    let service = self.imp().dbus_service.borrow();
+    let service = match service.as_ref() {
+      Some(service) => service,
+      None => return,
+    };
+
+    service.emit_feed_updated(feed);
+
+    if *feed.get_state() != FeedState::DownloadSucceeded {
+      return;
+    }
+
+    let key = |item: &FeedItem| {
+      if item.get_url().is_empty() {
+        item.get_title().clone()
+      } else {
+        item.get_url().clone()
+      }
+    };
+
+    let keys: HashSet<String> = feed.get_items().iter().map(key).collect();
+    let previous = self
+      .imp()
+      .known_items
+      .borrow_mut()
+      .insert(feed.get_id().clone(), keys);
+
+    if let Some(previous) = previous {
+      let new_items: Vec<FeedItem> = feed
+        .get_items()
+        .iter()
+        .filter(|item| !previous.contains(&key(item)))
+        .filter(|item| !item.is_muted() && feed.matches_filter(item))
+        .cloned()
+        .collect();
+
+      if !new_items.is_empty() {
+        service.emit_new_items(feed, &new_items);
+      }
+    }
+  }
+
+  // This is called for each URI or file BingeRSS is asked to open. feed:// URIs and web
+  // URLs are added as new feeds after a confirmation. Local files are imported if they
+  // contain an OPML subscription list; else they are treated like a downloaded feed.
+  fn open_file(&self, file: &gio::File) {
+    let uri = file.uri().to_string();
+
+    match file.uri_scheme().as_deref() {
+      Some("feed") => return self.confirm_feed(feed_uri_to_url(&uri)),
+      Some("http") | Some("https") => return self.confirm_feed(uri),
+      _ => {}
+    }
+
+    let bytes = match file.load_contents(gio::Cancellable::NONE) {
+      Ok((bytes, _)) => bytes,
+      Err(err) => {
+        println!("Failed to open {}: {}", uri, err);
+        return;
+      }
+    };
+
+    // A downloaded feed file usually contains the URL from which it was downloaded.
+    if is_opml(&bytes) {
+      self.import_opml(&bytes);
+    } else {
+      self.confirm_feed(get_self_link(&bytes).unwrap_or(uri));
+    }
+  }
+
+  // Shows a dialog which asks the user whether the feed with the given URL should be
+  // added. Web feeds are downloaded while the dialog is shown so that their title and
+  // latest items can be previewed; local files are only read once the user agreed. If
+  // there already is a feed with this URL, it is shown instead. Commands are rejected,
+  // as the URL does not come from the user.
+  fn confirm_feed(&self, url: String) {
+    let window = self.main_window();
+
+    if FeedSource::is_command(&url) {
+      println!("Refusing to add command feed {}", url);
+      window.show_message("Command feeds can only be added on the page of a feed.");
+      return;
+    }
+
+    if let Some(feed) = self.get_feed_by_url(&url) {
+      window.show_item(&feed.get_id(), "", "");
+      return;
+    }
+
+    let preview = url::Url::parse(&url).map_or(false, |parsed| {
+      parsed.scheme() == "http" || parsed.scheme() == "https"
+    });
+
+    let feed = Feed::new(
+      &"New Feed".into(),
+      &if preview { url.clone() } else { String::new() },
+      &"".into(),
+      chrono::Utc::now().timestamp(),
+    );
+
+    let body = if preview {
+      format!("Downloading {}…", url)
+    } else {
+      format!("Do you want to add {}?", url)
+    };
+
+    let dialog = adw::MessageDialog::builder()
+      .transient_for(&window)
+      .modal(true)
+      .heading("Add Feed?")
+      .body(&body)
+      .build();
+    dialog.add_response("cancel", "_Cancel");
+    dialog.add_response("add", "_Add");
+    dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("add"));
+    dialog.set_close_response("cancel");
+
+    // Show the title and the newest items of the feed once it has been downloaded.
+    feed.connect_notify_local(
+      Some("state"),
+      glib::clone!(@weak dialog, @strong url => move |feed, _| {
+        let state = *feed.get_state();
+
+        if state == FeedState::DownloadSucceeded {
+          let mut items = feed.get_items().clone();
+          items.sort_by_key(|item| -item.get_date());
+
+          let titles: Vec<String> = items
+            .iter()
+            .take(5)
+            .map(|item| format!("• {}", item.get_title()))
+            .collect();
+
+          let preview = if titles.is_empty() {
+            String::from("This feed contains no items.")
+          } else {
+            titles.join("\n")
+          };
+
+          dialog.set_body(&format!("{}\n{}\n\n{}", feed.get_title(), url, preview));
+        } else if state == FeedState::DownloadFailed || state == FeedState::FileMissing {
+          dialog.set_body(&format!("Failed to download {}.", url));
+        } else if state == FeedState::AuthenticationRequired {
+          dialog.set_body(&format!(
+            "{} requires authentication. You will be asked for credentials after \
+             adding the feed.",
+            url
+          ));
+        }
+      }),
+    );
+
+    dialog.connect_response(
+      None,
+      glib::clone!(@weak self as this => move |_, response| {
+        if response == "add" {
+          feed.set_property("position", this.imp().feeds.borrow().len() as u32);
+          this.add_feed(feed.clone());
+
+          // Feeds which have not been previewed are loaded now.
+          if !preview {
+            feed.set_property("url", &url);
+          }
+
+          this.save_feeds();
+
+          if *feed.get_state() == FeedState::AuthenticationRequired {
+            this.prompt_credentials(&feed);
+          }
+        }
+      }),
+    );
+
+    dialog.present();
+  }
+
+  // Adds all feeds of the given OPML document. Feeds which have been added before are
+  // skipped, and so are commands, as they would be executed without asking the user. A
+  // toast tells the user how many feeds have been imported.
+  fn import_opml(&self, bytes: &[u8]) {
+    let imported = match parse_opml(bytes) {
+      Ok(imported) => imported,
+      Err(err) => {
+        println!("Failed to import OPML file: {}", err);
+        self
+          .main_window()
+          .show_message("The file could not be imported.");
+        return;
+      }
+    };
+
+    let mut count = 0;
+    let mut rejected = 0;
+
+    for imported in imported {
+      if FeedSource::is_command(&imported.url) {
+        println!("Refusing to import command feed {}", imported.url);
+        rejected += 1;
+        continue;
+      }
+
+      if self.get_feed_by_url(&imported.url).is_some() {
+        continue;
+      }
+
+      let title = if imported.title.is_empty() {
+        String::from("New Feed")
+      } else {
+        imported.title
+      };
+
+      let feed = Feed::new(
+        &title,
+        &imported.url,
+        &"".into(),
+        chrono::Utc::now().timestamp(),
+      );
+      feed.set_property("position", self.imp().feeds.borrow().len() as u32);
+      self.add_feed(feed);
+      count += 1;
+    }
+
+    self.save_feeds();
+
+    let message = match count {
+      0 if rejected > 0 => String::from("No feeds have been imported."),
+      0 => String::from("All feeds have been added before."),
+      1 => String::from("Imported one feed."),
+      n => format!("Imported {} feeds.", n),
+    };
+
+    let message = match rejected {
+      0 => message,
+      1 => format!("{} One command feed has been skipped.", message),
+      n => format!("{} {} command feeds have been skipped.", message, n),
+    };
+
+    self.main_window().show_message(&message);
+  }
+
+  // Returns the feed with the given URL, if there is any.
+  fn get_feed_by_url(&self, url: &str) -> Option<Feed> {
+    self
+      .imp()
+      .feeds
+      .borrow()
+      .iter()
+      .find(|f| *f.get_url() == url)
+      .cloned()
+  }
+
+  // Exports the org.gnome.Shell.SearchProvider2 interface. This is called from
+  // dbus_register(), so the search provider is also available if GNOME Shell started
+  // BingeRSS as a D-Bus service without opening a window.
+  fn export_search_provider(
+    &self,
+    connection: &gio::DBusConnection,
+    object_path: &str,
+  ) -> Result<(), glib::Error> {
+    let provider = SearchProvider::register(
+      connection,
+      &format!("{}/SearchProvider", object_path),
+      glib::clone!(@weak self as this => @default-return Err("Shutting down".into()),
+        move |request| this.handle_search_request(request)
+      ),
+    )?;
+
+    self.imp().search_provider.replace(Some(provider));
+
+    Ok(())
+  }
+
+  // Executes a method call of GNOME Shell. The results are served from the ItemIndex, so
+  // this works even if no feed has been loaded because BingeRSS was started as a D-Bus
+  // service. Results are identified by the URLs of their items. Activating a result or
+  // launching a search opens the main window.
+  fn handle_search_request(
+    &self,
+    request: SearchRequest,
+  ) -> Result<Option<glib::Variant>, String> {
+    match request {
+      SearchRequest::GetInitialResultSet(terms) => {
+        Ok(Some(SearchProvider::result_set(self.search_items(&terms))))
+      }
+      SearchRequest::GetSubsearchResultSet(previous, terms) => {
+        let ids = previous
+          .into_iter()
+          .filter(|id| {
+            self
+              .get_search_result(id)
+              .map_or(false, |(_, item)| SearchProvider::matches(&item, &terms))
+          })
+          .collect();
+
+        Ok(Some(SearchProvider::result_set(ids)))
+      }
+      SearchRequest::GetResultMetas(ids) => {
+        let results: Vec<(String, FeedItem)> = ids
+          .into_iter()
+          .filter_map(|id| self.get_search_result(&id).map(|(_, item)| (id, item)))
+          .collect();
+
+        Ok(Some(SearchProvider::result_metas(&results)))
+      }
+      SearchRequest::ActivateResult(id, timestamp) => {
+        let (feed_url, item) = self
+          .get_search_result(&id)
+          .ok_or_else(|| format!("Invalid search result '{}'", id))?;
+
+        self.activate();
+        self.main_window().present_with_time(timestamp);
+
+        let feed = self
+          .get_feed_by_url(&feed_url)
+          .ok_or_else(|| format!("There is no feed with the URL '{}'", feed_url))?;
+
+        if self
+          .main_window()
+          .show_item(&feed.get_id(), &id, &item.get_title())
+        {
+          Ok(None)
+        } else {
+          Err(format!("There is no feed with the ID '{}'", feed.get_id()))
+        }
+      }
+      SearchRequest::LaunchSearch(terms, timestamp) => {
+        self.activate();
+        self.main_window().present_with_time(timestamp);
+        self.main_window().show_search_for(&terms.join(" "));
+        Ok(None)
+      }
+    }
+  }
+
+  // Returns the URLs of all indexed items whose title contains all given terms, newest
+  // first. Only items of configured feeds are returned; muted items and items which do
+  // not match their feed's filter are skipped. The feeds and the mute rules are read from
+  // the settings, as they have not been loaded if no window has been opened.
+  fn search_items(&self, terms: &[String]) -> Vec<String> {
+    let index = self.imp().index.borrow();
+    let results = match index.as_ref() {
+      Some(index) => index.search_titles(terms, MAX_SEARCH_RESULTS),
+      None => return vec![],
+    };
+
+    let results = match results {
+      Ok(results) => results,
+      Err(err) => {
+        println!("Failed to search the item index: {}", err);
+        return vec![];
+      }
+    };
+
+    // Feeds which use a filter preset use the filter of the preset.
+    let presets = PreferencesWindow::get_filter_presets(&self.imp().settings);
+    let filters: HashMap<String, String> = self
+      .get_stored_feeds()
+      .into_iter()
+      .map(|feed| {
+        let filter = match FilterPreset::get_filter(&presets, &feed.preset) {
+          Some(filter) => filter.to_string(),
+          None => feed.filter,
+        };
+        (feed.url, filter)
+      })
+      .collect();
+
+    let mute_rules: Vec<RuleMatcher> =
+      PreferencesWindow::get_mute_rules(&self.imp().settings)
+        .iter()
+        .map(MuteRule::get_matcher)
+        .collect();
+
+    results
+      .into_iter()
+      .filter(|(feed_url, item)| {
+        filters
+          .get(feed_url)
+          .map_or(false, |filter| Feed::filter_matches(filter, item))
+          && SearchProvider::matches(item, terms)
+          && !mute_rules.iter().any(|matcher| matcher.matches(item))
+      })
+      .map(|(_, item)| item.get_url().clone())
+      .collect()
+  }
+
+  // Returns the indexed item with the given URL and the URL of its feed. This returns
+  // None if the item is not in the ItemIndex.
+  fn get_search_result(&self, url: &str) -> Option<(String, FeedItem)> {
+    let index = self.imp().index.borrow();
+    let result = index.as_ref()?.get_item(url);
+
+    result.unwrap_or_else(|err| {
+      println!("Failed to look up {} in the item index: {}", url, err);
+      None
+    })
+  }
+
+  // Adds the given feed to the user interface and to the list of all feeds. Whenever the
+  // feed has been downloaded, its items are marked as starred if they are contained in
+  // the list of starred items and as read if they have been read before. Also, they are
+  // added to the ItemIndex and the duplicates across all feeds are updated. The global
+  // mute and highlight rules are applied to the items of the feed as well.
+  fn add_feed(&self, feed: Feed) {
+    feed.set_mute_rules(&self.imp().mute_rules.borrow());
+    feed.set_highlight_rules(&self.imp().highlight_rules.borrow());
+
+    feed.connect_notify_local(
+      Some("state"),
+      glib::clone!(@weak self as this => move |feed, _| {
+        if feed.get_state().eq(&FeedState::DownloadSucceeded) {
+          if let Some(index) = this.imp().index.borrow().as_ref() {
+            if let Err(err) = index.add_items(feed) {
+              println!("Failed to index items of {}: {}", feed.get_url(), err);
+            }
+
+            for item in feed.get_items().iter() {
+              let is_read = index.is_read(&normalize_url(&item.get_url()));
+              item.set_property("read", is_read.unwrap_or(false));
+            }
+          }
+
+          let starred = &this.imp().starred;
+          let urls: Vec<String> = (0..starred.n_items())
+            .map(|i| {
+              starred.item(i).unwrap().downcast::<FeedItem>().unwrap().get_url().clone()
+            })
+            .collect();
+
+          for item in feed.get_items().iter() {
+            item.set_property("starred", urls.contains(&*item.get_url()));
+          }
+
+          this.update_duplicates();
+          this.update_websub_subscription(feed);
+        }
+
+        // Ask for credentials if the server rejected the request.
+        let state = *feed.get_state();
+        if state == FeedState::AuthenticationRequired {
+          this.prompt_credentials(feed);
+        }
+
+        // Tell D-Bus clients about the finished download.
+        if state == FeedState::DownloadSucceeded
+          || state == FeedState::DownloadFailed
+          || state == FeedState::FileMissing
+          || state == FeedState::AuthenticationRequired
+        {
+          this.emit_dbus_signals(feed);
+        }
+      }),
+    );
+
+    // Feeds which are added again after being removed are downloaded already.
+    self.update_websub_subscription(&feed);
+
+    self.main_window().add_feed(&feed);
+    self.imp().feeds.borrow_mut().push(feed);
   }
 
   // Returns the current main window of the application. This will panic if called before
@@ -256,14 +1438,37 @@ mod imp {
   // -------------------------------------------------------------------------------------
   // This object holds the state of our custom application. Next to the current
   // application window and the GSettings, it contains a list of all currently configured
-  // feeds. If a feed gets removed by the user, it is removed from the 'feeds' but added
-  // to the 'removed_feeds'. This allows us to undo the deletion if required.
+  // feeds. As the feeds are loaded asynchronously, 'feeds_loaded' is set once all of them
+  // have been added. If a feed gets removed by the user, it is removed from the 'feeds'
+  // but added to the 'removed_feeds'. This allows us to undo the deletion if required.
+  // The starred items are stored independently of the feeds. The 'all_items' contain one
+  // item of each story of all feeds. The ItemIndex is used for the global search and
+  // stores which items have been read. The mute and highlight rules are applied to the
+  // items of all feeds. The D-Bus service remembers the items of each feed's last
+  // download so that it can announce new items. The credentials of the feeds are stored
+  // in the credential store, usually the system keyring. If a WebSub callback is
+  // configured, the WebSubServer receives the content pushed by the hubs of the feeds.
+  // The last known network state is stored so that the feeds can be refreshed when it
+  // becomes online.
   #[derive(Debug)]
   pub struct Application {
     pub window: WeakRef<Window>,
     pub settings: gio::Settings,
     pub feeds: RefCell<Vec<Feed>>,
+    pub feeds_loaded: Cell<bool>,
     pub removed_feeds: RefCell<Vec<Feed>>,
+    pub starred: gio::ListStore,
+    pub all_items: gio::ListStore,
+    pub index: RefCell<Option<Rc<ItemIndex>>>,
+    pub mute_rules: RefCell<Vec<MuteRule>>,
+    pub highlight_rules: RefCell<Vec<HighlightRule>>,
+    pub dbus_service: RefCell<Option<Service>>,
+    pub search_provider: RefCell<Option<SearchProvider>>,
+    pub known_items: RefCell<HashMap<String, HashSet<String>>>,
+    pub credential_store: Rc<dyn CredentialStore>,
+    pub auth_prompts: RefCell<HashSet<String>>,
+    pub websub_server: RefCell<Option<WebSubServer>>,
+    pub network_state: Cell<NetworkState>,
   }
 
   impl Default for Application {
@@ -272,7 +1477,20 @@ mod imp {
         window: Default::default(),
         settings: gio::Settings::new(config::APP_ID),
         feeds: RefCell::new(vec![]),
+        feeds_loaded: Cell::new(false),
         removed_feeds: RefCell::new(vec![]),
+        starred: gio::ListStore::new(FeedItem::static_type()),
+        all_items: gio::ListStore::new(FeedItem::static_type()),
+        index: RefCell::new(None),
+        mute_rules: RefCell::new(vec![]),
+        highlight_rules: RefCell::new(vec![]),
+        dbus_service: RefCell::new(None),
+        search_provider: RefCell::new(None),
+        known_items: RefCell::new(HashMap::new()),
+        credential_store: open_credential_store(),
+        auth_prompts: RefCell::new(HashSet::new()),
+        websub_server: RefCell::new(None),
+        network_state: Cell::new(NetworkState::Online),
       }
     }
   }
@@ -287,6 +1505,46 @@ mod imp {
   impl ObjectImpl for Application {}
 
   impl ApplicationImpl for Application {
+    // This is called once when the primary instance starts. This also happens if GNOME
+    // Shell starts BingeRSS as a D-Bus service to query the search provider; in this
+    // case, activate() is not called. Hence, the ItemIndex is opened here.
+    fn startup(&self) {
+      self.parent_startup();
+      self.obj().load_item_index();
+    }
+
+    // This is called when the application registers its bus name. We use this to export
+    // the search provider for GNOME Shell.
+    fn dbus_register(
+      &self,
+      connection: &gio::DBusConnection,
+      object_path: &str,
+    ) -> Result<(), glib::Error> {
+      self.parent_dbus_register(connection, object_path)?;
+      self.obj().export_search_provider(connection, object_path)
+    }
+
+    // This is called instead of activate() if BingeRSS is started with URIs or files as
+    // command line arguments. If BingeRSS is already running, this is called on the
+    // primary instance.
+    fn open(&self, files: &[gio::File], _hint: &str) {
+      // Make sure that the window exists and that the feeds have been loaded.
+      self.obj().activate();
+
+      for file in files {
+        self.obj().open_file(file);
+      }
+    }
+
+    // The search provider has to be removed from the bus when the application shuts down.
+    fn dbus_unregister(&self, connection: &gio::DBusConnection, object_path: &str) {
+      if let Some(provider) = self.search_provider.take() {
+        provider.unregister();
+      }
+
+      self.parent_dbus_unregister(connection, object_path);
+    }
+
     // This is called when the application is started and for each subsequent attempt of
     // the user to start another instance of the application. In the latter cases, no new
     // application instance is opened, instead this method is called on the primary
@@ -315,6 +1573,7 @@ mod imp {
       window.connect_close_request(
         glib::clone!(@weak self as this => @default-return gtk::Inhibit(false), move |_| {
           this.obj().save_feeds();
+          this.obj().save_starred_items();
           gtk::Inhibit(false)
         }),
       );
@@ -322,8 +1581,97 @@ mod imp {
       // Setup the actions which glue to together the functionality of BingeRSS.
       self.obj().setup_actions();
 
-      // Load all configured feeds from the settings.
+      // Allow other applications and scripts to access the feeds.
+      self.obj().export_dbus_service();
+
+      // Web feeds are not downloaded while offline or while automatic refreshes are
+      // paused. Instead, a banner is shown and the feeds are refreshed once this changes.
+      set_pause_on_metered(self.settings.boolean("pause-on-metered"));
+      self.network_state.set(NetworkState::current());
+      self.obj().update_network_state();
+
+      let monitor = gio::NetworkMonitor::default();
+
+      monitor.connect_network_changed(glib::clone!(@weak self as this => move |_, _| {
+        this.obj().update_network_state();
+      }));
+
+      monitor.connect_network_metered_notify(
+        glib::clone!(@weak self as this => move |_| {
+          this.obj().update_network_state();
+        }),
+      );
+
+      self.settings.connect_changed(
+        Some("pause-on-metered"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_network_state();
+        }),
+      );
+
+      // The item index has been opened in startup(). It is used by the global search.
+      if let Some(index) = self.index.borrow().as_ref() {
+        window.set_item_index(index.clone());
+      }
+
+      // Load all configured feeds and starred items from the settings.
+      self.obj().main_window().set_all_items(&self.all_items);
+      self.obj().load_starred_items();
+      self.obj().load_mute_rules();
+      self.obj().load_highlight_rules();
+      self.obj().load_ca_certificates();
       self.obj().load_feeds();
+      self.obj().start_websub_server();
+
+      // Remove old thumbnails from the cache.
+      ThumbnailCache::prune();
+
+      // Re-apply the mute and highlight rules to all feeds whenever they are changed in
+      // the preferences.
+      self.settings.connect_changed(
+        Some("mute-rules"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().load_mute_rules();
+        }),
+      );
+
+      self.settings.connect_changed(
+        Some("highlight-rules"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().load_highlight_rules();
+        }),
+      );
+
+      // Update the filters of all feeds whenever the filter presets change.
+      self.settings.connect_changed(
+        Some("filter-presets"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().apply_filter_presets();
+        }),
+      );
+
+      // Restart the WebSub listener whenever its settings change.
+      for key in ["websub-callback", "websub-address", "websub-port"] {
+        self.settings.connect_changed(
+          Some(key),
+          glib::clone!(@weak self as this => move |_, _| {
+            this.obj().start_websub_server();
+          }),
+        );
+      }
+
+      // Download all feeds again if the trusted certificate authorities change, as this
+      // may fix or break the connections to their servers.
+      self.settings.connect_changed(
+        Some("ca-certificates"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().load_ca_certificates();
+
+          for feed in this.feeds.borrow().iter() {
+            feed.refresh();
+          }
+        }),
+      );
 
       // Finally, show the window.
       self.obj().main_window().present();
@@ -0,0 +1,21 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+// This module contains the D-Bus interfaces which BingeRSS exports on its bus name. They
+// allow other applications and scripts to query and control BingeRSS.
+
+mod search_provider;
+mod service;
+
+pub use self::search_provider::SearchProvider;
+pub use self::search_provider::SearchRequest;
+pub use self::service::Request;
+pub use self::service::Service;
@@ -0,0 +1,212 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{gio, glib, prelude::*};
+use std::collections::HashMap;
+
+use crate::model::FeedItem;
+
+// The interface which GNOME Shell uses to query search providers.
+const INTERFACE_NAME: &str = "org.gnome.Shell.SearchProvider2";
+
+// Errors are reported to GNOME Shell with this D-Bus error name.
+const ERROR_NAME: &str = "io.github.schneegans.BingeRSS.Error.Failed";
+
+// The introspection data of the org.gnome.Shell.SearchProvider2 interface.
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="results" type="as" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg name="previous_results" type="as" direction="in"/>
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="results" type="as" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg name="identifiers" type="as" direction="in"/>
+      <arg name="metas" type="aa{sv}" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg name="identifier" type="s" direction="in"/>
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg name="terms" type="as" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+// ---------------------------------------------------------------------------------------
+// Each method call of GNOME Shell is turned into one of these requests. Results are
+// identified by the URLs of their items, so they remain valid across restarts.
+#[derive(Debug)]
+pub enum SearchRequest {
+  // Returns the IDs of all items matching the given terms.
+  GetInitialResultSet(Vec<String>),
+
+  // Returns those of the given previous results which match the given, refined terms.
+  GetSubsearchResultSet(Vec<String>, Vec<String>),
+
+  // Returns the title and feed name of each of the given results.
+  GetResultMetas(Vec<String>),
+
+  // Shows the given result in the main window.
+  ActivateResult(String, u32),
+
+  // Shows the global search for the given terms in the main window.
+  LaunchSearch(Vec<String>, u32),
+}
+
+// ---------------------------------------------------------------------------------------
+// The SearchProvider exports the org.gnome.Shell.SearchProvider2 interface. Like the
+// Service, it only implements the protocol: method calls are passed as SearchRequests
+// to a handler, which returns either the reply or an error message. The associated
+// functions help with creating the replies.
+#[derive(Debug)]
+pub struct SearchProvider {
+  connection: gio::DBusConnection,
+  registration_id: gio::RegistrationId,
+}
+
+impl SearchProvider {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Registers the interface at the given object path. This path has to match the
+  // ObjectPath in the search-provider.ini file. The given handler is called for each
+  // incoming method call.
+  pub fn register<F>(
+    connection: &gio::DBusConnection,
+    object_path: &str,
+    handler: F,
+  ) -> Result<Self, glib::Error>
+  where
+    F: Fn(SearchRequest) -> Result<Option<glib::Variant>, String> + 'static,
+  {
+    let info = gio::DBusNodeInfo::for_xml(INTERFACE_XML)?
+      .lookup_interface(INTERFACE_NAME)
+      .expect("interface is defined");
+
+    let registration_id = connection.register_object(
+      object_path,
+      &info,
+      move |_, _, _, _, method, parameters, invocation| {
+        let result = Self::parse_request(method, &parameters)
+          .ok_or_else(|| format!("Invalid call of {}", method))
+          .and_then(&handler);
+
+        match result {
+          Ok(reply) => invocation.return_value(reply.as_ref()),
+          Err(message) => invocation.return_dbus_error(ERROR_NAME, &message),
+        }
+      },
+      |_, _, _, _, _| unreachable!("the interface has no properties"),
+      |_, _, _, _, _, _| unreachable!("the interface has no properties"),
+    )?;
+
+    Ok(Self {
+      connection: connection.clone(),
+      registration_id,
+    })
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Removes the interface from the connection again. This has to be called when the
+  // application is unregistered from the bus.
+  pub fn unregister(self) {
+    if let Err(err) = self.connection.unregister_object(self.registration_id) {
+      println!("Failed to unregister search provider: {}", err);
+    }
+  }
+
+  // Returns true if the title of the given item contains all of the given terms. The
+  // comparison is case-insensitive. If there are no terms, nothing matches.
+  pub fn matches(item: &FeedItem, terms: &[String]) -> bool {
+    let title = item.get_title().to_lowercase();
+    !terms.is_empty() && terms.iter().all(|t| title.contains(&t.to_lowercase()))
+  }
+
+  // Returns the reply of the GetInitialResultSet and GetSubsearchResultSet methods.
+  pub fn result_set(ids: Vec<String>) -> glib::Variant {
+    (ids,).to_variant()
+  }
+
+  // Returns the reply of the GetResultMetas method. Each result is shown with the title
+  // of the item and the title of its feed.
+  pub fn result_metas(results: &[(String, FeedItem)]) -> glib::Variant {
+    let metas: Vec<HashMap<String, glib::Variant>> = results
+      .iter()
+      .map(|(id, item)| {
+        HashMap::from([
+          (String::from("id"), id.to_variant()),
+          (String::from("name"), item.get_title().to_variant()),
+          (
+            String::from("description"),
+            item.get_feed_title().to_variant(),
+          ),
+        ])
+      })
+      .collect();
+
+    (metas,).to_variant()
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Converts a method call to a SearchRequest. The types of the parameters have already
+  // been checked by GIO, so this only returns None for unknown methods.
+  fn parse_request(method: &str, parameters: &glib::Variant) -> Option<SearchRequest> {
+    match method {
+      "GetInitialResultSet" => {
+        let (terms,) = parameters.get::<(Vec<String>,)>()?;
+        Some(SearchRequest::GetInitialResultSet(terms))
+      }
+      "GetSubsearchResultSet" => {
+        let (previous, terms) = parameters.get::<(Vec<String>, Vec<String>)>()?;
+        Some(SearchRequest::GetSubsearchResultSet(previous, terms))
+      }
+      "GetResultMetas" => {
+        let (ids,) = parameters.get::<(Vec<String>,)>()?;
+        Some(SearchRequest::GetResultMetas(ids))
+      }
+      "ActivateResult" => {
+        let (id, _, timestamp) = parameters.get::<(String, Vec<String>, u32)>()?;
+        Some(SearchRequest::ActivateResult(id, timestamp))
+      }
+      "LaunchSearch" => {
+        let (terms, timestamp) = parameters.get::<(Vec<String>, u32)>()?;
+        Some(SearchRequest::LaunchSearch(terms, timestamp))
+      }
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches() {
+    let item = FeedItem::new(&"GTK 4.10 Released".into(), &"".into(), 0);
+    let terms = |terms: &[&str]| terms.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+    assert!(SearchProvider::matches(&item, &terms(&["gtk", "RELEASED"])));
+    assert!(!SearchProvider::matches(&item, &terms(&["gtk", "rust"])));
+    assert!(!SearchProvider::matches(&item, &terms(&[])));
+  }
+}
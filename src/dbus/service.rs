@@ -0,0 +1,221 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{gio, glib, prelude::*};
+use std::collections::HashMap;
+
+use crate::model::{Feed, FeedItem};
+
+// The name of the exported interface. It does not depend on the application ID, so
+// scripts work with development builds as well.
+const INTERFACE_NAME: &str = "io.github.schneegans.BingeRSS";
+
+// Errors are reported to the caller with this D-Bus error name.
+const ERROR_NAME: &str = "io.github.schneegans.BingeRSS.Error.Failed";
+
+// The introspection data of the exported interface. Feeds are identified by the same IDs
+// which are used internally; these IDs are only valid until BingeRSS is restarted.
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="io.github.schneegans.BingeRSS">
+    <method name="ListFeeds">
+      <arg name="feeds" type="a(ssss)" direction="out"/>
+    </method>
+    <method name="AddFeed">
+      <arg name="url" type="s" direction="in"/>
+      <arg name="title" type="s" direction="in"/>
+      <arg name="filter" type="s" direction="in"/>
+      <arg name="id" type="s" direction="out"/>
+    </method>
+    <method name="RemoveFeed">
+      <arg name="id" type="s" direction="in"/>
+    </method>
+    <method name="Refresh">
+      <arg name="id" type="s" direction="in"/>
+    </method>
+    <method name="GetUnreadCounts">
+      <arg name="counts" type="a{si}" direction="out"/>
+    </method>
+    <method name="MarkRead">
+      <arg name="id" type="s" direction="in"/>
+    </method>
+    <signal name="FeedUpdated">
+      <arg name="id" type="s"/>
+      <arg name="unread" type="i"/>
+    </signal>
+    <signal name="NewItems">
+      <arg name="id" type="s"/>
+      <arg name="items" type="a(ss)"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+// ---------------------------------------------------------------------------------------
+// Each method call on the D-Bus interface is turned into one of these requests.
+#[derive(Debug)]
+pub enum Request {
+  // Returns the ID, title, URL, and filter of each feed.
+  ListFeeds,
+
+  // Adds a new feed and returns its ID. If the title is empty, the title of the
+  // downloaded feed is used. Command feeds cannot be added this way.
+  AddFeed {
+    url: String,
+    title: String,
+    filter: String,
+  },
+
+  // Removes the feed with the given ID.
+  RemoveFeed(String),
+
+  // Downloads the feed with the given ID again.
+  Refresh(String),
+
+  // Returns the number of unread items of each feed.
+  GetUnreadCounts,
+
+  // Marks all items of the feed with the given ID as read.
+  MarkRead(String),
+}
+
+// ---------------------------------------------------------------------------------------
+// The Service exports the io.github.schneegans.BingeRSS interface on the given
+// connection. It only implements the protocol: incoming method calls are passed as
+// Requests to a handler, which returns either the reply or an error message. The
+// signals of the interface can be emitted with the emit_*() methods.
+#[derive(Debug)]
+pub struct Service {
+  connection: gio::DBusConnection,
+  object_path: String,
+}
+
+impl Service {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Registers the interface at the given object path. The given handler is called for
+  // each incoming method call.
+  pub fn register<F>(
+    connection: &gio::DBusConnection,
+    object_path: &str,
+    handler: F,
+  ) -> Result<Self, glib::Error>
+  where
+    F: Fn(Request) -> Result<Option<glib::Variant>, String> + 'static,
+  {
+    let info = gio::DBusNodeInfo::for_xml(INTERFACE_XML)?
+      .lookup_interface(INTERFACE_NAME)
+      .expect("interface is defined");
+
+    connection.register_object(
+      object_path,
+      &info,
+      move |_, _, _, _, method, parameters, invocation| {
+        let result = Self::parse_request(method, &parameters)
+          .ok_or_else(|| format!("Invalid call of {}", method))
+          .and_then(&handler);
+
+        match result {
+          Ok(reply) => invocation.return_value(reply.as_ref()),
+          Err(message) => invocation.return_dbus_error(ERROR_NAME, &message),
+        }
+      },
+      |_, _, _, _, _| unreachable!("the interface has no properties"),
+      |_, _, _, _, _, _| unreachable!("the interface has no properties"),
+    )?;
+
+    Ok(Self {
+      connection: connection.clone(),
+      object_path: object_path.to_string(),
+    })
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns the reply of the ListFeeds method for the given feeds.
+  pub fn feed_list(feeds: &[Feed]) -> glib::Variant {
+    let feeds: Vec<(String, String, String, String)> = feeds
+      .iter()
+      .map(|feed| {
+        (
+          feed.get_id().clone(),
+          feed.get_title().clone(),
+          feed.get_url().clone(),
+          feed.get_filter().clone(),
+        )
+      })
+      .collect();
+
+    (feeds,).to_variant()
+  }
+
+  // Returns the reply of the GetUnreadCounts method for the given feeds.
+  pub fn unread_counts(feeds: &[Feed]) -> glib::Variant {
+    let counts: HashMap<String, i32> = feeds
+      .iter()
+      .map(|feed| (feed.get_id().clone(), feed.get_unread()))
+      .collect();
+
+    (counts,).to_variant()
+  }
+
+  // Emits the FeedUpdated signal. This should be called whenever a download of the given
+  // feed finished.
+  pub fn emit_feed_updated(&self, feed: &Feed) {
+    let parameters = (feed.get_id().clone(), feed.get_unread()).to_variant();
+    self.emit("FeedUpdated", &parameters);
+  }
+
+  // Emits the NewItems signal with the titles and URLs of the given items.
+  pub fn emit_new_items(&self, feed: &Feed, items: &[FeedItem]) {
+    let items: Vec<(String, String)> = items
+      .iter()
+      .map(|item| (item.get_title().clone(), item.get_url().clone()))
+      .collect();
+
+    let parameters = (feed.get_id().clone(), items).to_variant();
+    self.emit("NewItems", &parameters);
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Emits the given signal of the interface to all listeners.
+  fn emit(&self, signal: &str, parameters: &glib::Variant) {
+    let result = self.connection.emit_signal(
+      None,
+      &self.object_path,
+      INTERFACE_NAME,
+      signal,
+      Some(parameters),
+    );
+
+    if let Err(err) = result {
+      println!("Failed to emit D-Bus signal {}: {}", signal, err);
+    }
+  }
+
+  // Converts a method call to a Request. The types of the parameters have already been
+  // checked by GIO, so this only returns None for unknown methods.
+  fn parse_request(method: &str, parameters: &glib::Variant) -> Option<Request> {
+    match method {
+      "ListFeeds" => Some(Request::ListFeeds),
+      "AddFeed" => {
+        let (url, title, filter) = parameters.get::<(String, String, String)>()?;
+        Some(Request::AddFeed { url, title, filter })
+      }
+      "RemoveFeed" => Some(Request::RemoveFeed(parameters.get::<(String,)>()?.0)),
+      "Refresh" => Some(Request::Refresh(parameters.get::<(String,)>()?.0)),
+      "GetUnreadCounts" => Some(Request::GetUnreadCounts),
+      "MarkRead" => Some(Request::MarkRead(parameters.get::<(String,)>()?.0)),
+      _ => None,
+    }
+  }
+}
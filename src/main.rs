@@ -11,6 +11,7 @@
 
 mod application;
 mod config;
+mod dbus;
 mod model;
 mod view;
 
@@ -0,0 +1,311 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use futures::future::LocalBoxFuture;
+use gtk::gio;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+// If this environment variable is set to "memory", credentials are not written to the
+// keyring. This is used for testing, similar to GSETTINGS_BACKEND=memory.
+const BACKEND_VARIABLE: &str = "BINGE_RSS_CREDENTIAL_BACKEND";
+
+// The name of the libsecret schema. The items in the keyring are identified by the
+// "id" attribute.
+const SCHEMA_NAME: &str = "io.github.schneegans.BingeRSS.Credentials";
+
+// ---------------------------------------------------------------------------------------
+// A feed can either be downloaded anonymously, with a user name and a password, or with
+// a bearer token.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Authentication {
+  None,
+  Basic { username: String, password: String },
+  Bearer { token: String },
+}
+
+impl Default for Authentication {
+  fn default() -> Self {
+    Authentication::None
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Credentials are sent with each request for a feed. Next to the authentication, they can
+// contain custom headers, for instance API tokens which are expected in a special header.
+// They are serialized to JSON and stored in the keyring, never in the settings.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+  #[serde(default)]
+  pub auth: Authentication,
+
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub headers: Vec<(String, String)>,
+}
+
+impl Credentials {
+  // ---------------------------------------------------------------------- public methods
+
+  // Adds the authentication and the custom headers to the given request.
+  pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let request = match &self.auth {
+      Authentication::None => request,
+      Authentication::Basic { username, password } => {
+        request.basic_auth(username, Some(password))
+      }
+      Authentication::Bearer { token } => request.bearer_auth(token),
+    };
+
+    self.headers.iter().fold(request, |request, (name, value)| {
+      request.header(name, value)
+    })
+  }
+
+  // Parses custom headers which are given as one "Name: Value" pair per line. Empty
+  // lines are ignored. An error message is returned if any of the lines is not a valid
+  // header.
+  pub fn parse_headers(text: &str) -> Result<Vec<(String, String)>, String> {
+    text
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .map(|line| {
+        let (name, value) = line
+          .split_once(':')
+          .ok_or_else(|| format!("'{}' is not of the form 'Name: Value'.", line))?;
+        let (name, value) = (name.trim(), value.trim());
+
+        if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+          return Err(format!("'{}' is not a valid header name.", name));
+        }
+
+        if reqwest::header::HeaderValue::from_str(value).is_err() {
+          return Err(format!("The value of '{}' is not valid.", name));
+        }
+
+        Ok((name.to_string(), value.to_string()))
+      })
+      .collect()
+  }
+
+  // Returns the custom headers in the format accepted by parse_headers().
+  pub fn format_headers(&self) -> String {
+    self
+      .headers
+      .iter()
+      .map(|(name, value)| format!("{}: {}", name, value))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// A CredentialStore saves the Credentials of feeds under a unique ID. Usually, this is
+// the system keyring. All errors are returned as messages which can be printed.
+pub trait CredentialStore: std::fmt::Debug {
+  // Returns the Credentials stored under the given ID or None if there are none. As the
+  // keyring may have to be unlocked first, this is done asynchronously.
+  fn lookup(
+    &self,
+    id: &str,
+  ) -> LocalBoxFuture<'static, Result<Option<Credentials>, String>>;
+
+  // Stores the given Credentials under the given ID. Existing Credentials are replaced.
+  // The label is shown in keyring managers like Seahorse.
+  fn store(&self, id: &str, label: &str, credentials: &Credentials)
+    -> Result<(), String>;
+
+  // Removes the Credentials stored under the given ID.
+  fn clear(&self, id: &str) -> Result<(), String>;
+}
+
+// Returns the keyring or, if BINGE_RSS_CREDENTIAL_BACKEND is set to "memory", a store
+// which forgets all Credentials when the application is closed.
+pub fn open_credential_store() -> Rc<dyn CredentialStore> {
+  if std::env::var(BACKEND_VARIABLE).map_or(false, |backend| backend == "memory") {
+    Rc::new(MemoryStore::default())
+  } else {
+    Rc::new(SecretStore::new())
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// The SecretStore uses libsecret to save the Credentials in the system keyring.
+#[derive(Debug)]
+pub struct SecretStore {
+  schema: libsecret::Schema,
+}
+
+impl SecretStore {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    let attributes = HashMap::from([("id", libsecret::SchemaAttributeType::String)]);
+    let schema =
+      libsecret::Schema::new(SCHEMA_NAME, libsecret::SchemaFlags::NONE, attributes);
+
+    Self { schema }
+  }
+}
+
+impl CredentialStore for SecretStore {
+  fn lookup(
+    &self,
+    id: &str,
+  ) -> LocalBoxFuture<'static, Result<Option<Credentials>, String>> {
+    let schema = self.schema.clone();
+    let id = id.to_string();
+
+    Box::pin(async move {
+      let attributes = HashMap::from([("id", id.as_str())]);
+      let secret = libsecret::password_lookup_future(Some(&schema), attributes)
+        .await
+        .map_err(|err| err.to_string())?;
+
+      match secret {
+        Some(secret) => serde_json::from_str(&secret)
+          .map(Some)
+          .map_err(|err| err.to_string()),
+        None => Ok(None),
+      }
+    })
+  }
+
+  fn store(
+    &self,
+    id: &str,
+    label: &str,
+    credentials: &Credentials,
+  ) -> Result<(), String> {
+    let attributes = HashMap::from([("id", id)]);
+    let secret = serde_json::to_string(credentials).map_err(|err| err.to_string())?;
+
+    libsecret::password_store_sync(
+      Some(&self.schema),
+      attributes,
+      Some(libsecret::COLLECTION_DEFAULT),
+      label,
+      &secret,
+      gio::Cancellable::NONE,
+    )
+    .map_err(|err| err.to_string())
+  }
+
+  fn clear(&self, id: &str) -> Result<(), String> {
+    let attributes = HashMap::from([("id", id)]);
+    libsecret::password_clear_sync(Some(&self.schema), attributes, gio::Cancellable::NONE)
+      .map_err(|err| err.to_string())
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// The MemoryStore keeps the Credentials in memory. Like the keyring, it stores them as
+// JSON strings so that the serialization is covered by tests as well.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  secrets: RefCell<HashMap<String, String>>,
+}
+
+impl CredentialStore for MemoryStore {
+  fn lookup(
+    &self,
+    id: &str,
+  ) -> LocalBoxFuture<'static, Result<Option<Credentials>, String>> {
+    let result = match self.secrets.borrow().get(id) {
+      Some(secret) => serde_json::from_str(secret)
+        .map(Some)
+        .map_err(|err| err.to_string()),
+      None => Ok(None),
+    };
+
+    Box::pin(async move { result })
+  }
+
+  fn store(
+    &self,
+    id: &str,
+    _label: &str,
+    credentials: &Credentials,
+  ) -> Result<(), String> {
+    let secret = serde_json::to_string(credentials).map_err(|err| err.to_string())?;
+    self.secrets.borrow_mut().insert(id.to_string(), secret);
+    Ok(())
+  }
+
+  fn clear(&self, id: &str) -> Result<(), String> {
+    self.secrets.borrow_mut().remove(id);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lookup(store: &MemoryStore, id: &str) -> Result<Option<Credentials>, String> {
+    crate::RUNTIME.block_on(store.lookup(id))
+  }
+
+  #[test]
+  fn memory_store() {
+    let store = MemoryStore::default();
+    let credentials = Credentials {
+      auth: Authentication::Basic {
+        username: "jenkins".into(),
+        password: "secret".into(),
+      },
+      headers: vec![("X-Api-Key".into(), "1234".into())],
+    };
+
+    assert_eq!(lookup(&store, "a"), Ok(None));
+
+    store.store("a", "Feed", &credentials).unwrap();
+    assert_eq!(lookup(&store, "a"), Ok(Some(credentials)));
+    assert_eq!(lookup(&store, "b"), Ok(None));
+
+    store.clear("a").unwrap();
+    assert_eq!(lookup(&store, "a"), Ok(None));
+  }
+
+  #[test]
+  fn apply() {
+    let credentials = Credentials {
+      auth: Authentication::Bearer {
+        token: "abc".into(),
+      },
+      headers: Credentials::parse_headers("PRIVATE-TOKEN: 42\n\n Accept : text/xml ")
+        .unwrap(),
+    };
+
+    assert_eq!(
+      credentials.format_headers(),
+      "PRIVATE-TOKEN: 42\nAccept: text/xml"
+    );
+
+    let request = credentials
+      .apply(reqwest::Client::new().get("https://example.com/feed"))
+      .build()
+      .unwrap();
+
+    let headers = request.headers();
+    assert_eq!(headers["Authorization"], "Bearer abc");
+    assert_eq!(headers["PRIVATE-TOKEN"], "42");
+    assert_eq!(headers["Accept"], "text/xml");
+  }
+
+  #[test]
+  fn invalid_headers() {
+    assert!(Credentials::parse_headers("no colon").is_err());
+    assert!(Credentials::parse_headers("bad name: value").is_err());
+    assert!(Credentials::parse_headers("").unwrap().is_empty());
+  }
+}
@@ -0,0 +1,301 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{glib, prelude::*, subclass::prelude::*};
+use once_cell::sync::Lazy;
+use std::{
+  cell::{Cell, Ref, RefCell},
+  error::Error,
+  path::{Path, PathBuf},
+};
+use tokio::io::AsyncWriteExt;
+
+// ---------------------------------------------------------------------------------------
+// Each Download can be in one of these states.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "DownloadState")]
+pub enum DownloadState {
+  Running,
+  Finished,
+  Failed,
+}
+
+impl Default for DownloadState {
+  fn default() -> Self {
+    DownloadState::Running
+  }
+}
+
+// These messages are sent from the download task to the main context.
+enum Message {
+  Progress(u64, Option<u64>),
+  Finished(Result<(), String>),
+}
+
+// ---------------------------------------------------------------------------------------
+// A Download is a GObject which downloads the file at a given URL to a given path. This
+// is used for downloading the enclosures of feed items, for instance podcast episodes.
+// The progress property is a value between zero and one, or negative if the size of the
+// file is unknown. The state property changes to Finished or Failed once the download is
+// done.
+glib::wrapper! {
+  pub struct Download(ObjectSubclass<imp::Download>);
+}
+
+impl Download {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Creates a new Download which stores the file at the given URL in the given folder.
+  // The file name is derived from the URL. If a file with this name already exists, a
+  // number is appended. The download starts immediately.
+  pub fn new(url: &String, folder: &Path) -> Self {
+    let path = Self::unique_path(folder, &Self::file_name(url));
+
+    let download: Self = glib::Object::builder()
+      .property("url", url)
+      .property("path", path.to_string_lossy().to_string())
+      .build();
+
+    download.start();
+    download
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Get the URL of the downloaded file.
+  pub fn get_url(&self) -> Ref<String> {
+    self.imp().url.borrow()
+  }
+
+  // Get the path at which the downloaded file is stored.
+  pub fn get_path(&self) -> Ref<String> {
+    self.imp().path.borrow()
+  }
+
+  // Get the current progress in the range [0, 1]. If the size of the file is unknown,
+  // this is negative.
+  pub fn get_progress(&self) -> f64 {
+    self.imp().progress.get()
+  }
+
+  // Get the current state of the download.
+  pub fn get_state(&self) -> DownloadState {
+    self.imp().state.get()
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Starts a task on the tokio runtime which downloads the file chunk by chunk. The
+  // progress is reported to the main context, where the properties of the Download are
+  // updated accordingly. The data is written to a .part file next to the target path,
+  // which is renamed once the download is complete. If the download fails, the .part
+  // file is removed, so that no truncated file is left behind.
+  fn start(&self) {
+    let url = self.get_url().clone();
+    let path = PathBuf::from(&*self.get_path());
+
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    crate::RUNTIME.spawn(async move {
+      let part_path = Self::part_path(&path);
+      let progress_sender = sender.clone();
+      let mut created = false;
+      let result = async {
+        let client = crate::model::http_client()?;
+        let mut response = client.get(&url).send().await?.error_for_status()?;
+        let total = response.content_length();
+        let mut file = tokio::fs::OpenOptions::new()
+          .write(true)
+          .create_new(true)
+          .open(&part_path)
+          .await?;
+        created = true;
+        let mut received = 0;
+
+        while let Some(chunk) = response.chunk().await? {
+          file.write_all(&chunk).await?;
+          received += chunk.len() as u64;
+          progress_sender
+            .send(Message::Progress(received, total))
+            .ok();
+        }
+
+        file.flush().await?;
+        drop(file);
+        tokio::fs::rename(&part_path, &path).await?;
+
+        Ok::<(), Box<dyn Error + Send + Sync>>(())
+      }
+      .await;
+
+      if result.is_err() && created {
+        tokio::fs::remove_file(&part_path).await.ok();
+      }
+
+      sender
+        .send(Message::Finished(result.map_err(|err| err.to_string())))
+        .ok();
+    });
+
+    receiver.attach(
+      None,
+      glib::clone!(@weak self as this => @default-return glib::Continue(false),
+        move |message| {
+          match message {
+            Message::Progress(received, Some(total)) if total > 0 => {
+              this.set_property("progress", received as f64 / total as f64);
+            }
+            Message::Progress(_, _) => {
+              this.set_property("progress", -1.0);
+            }
+            Message::Finished(Ok(())) => {
+              this.set_property("progress", 1.0);
+              this.set_property("state", DownloadState::Finished);
+              return glib::Continue(false);
+            }
+            Message::Finished(Err(err)) => {
+              println!("Failed to download {}: {}", this.get_url(), err);
+              this.set_property("state", DownloadState::Failed);
+              return glib::Continue(false);
+            }
+          }
+          glib::Continue(true)
+        }
+      ),
+    );
+  }
+
+  // Returns the last segment of the path of the given URL. If there is none, "download"
+  // is returned.
+  fn file_name(url: &str) -> String {
+    url::Url::parse(url)
+      .ok()
+      .and_then(|url| url.path_segments().and_then(|s| s.last().map(String::from)))
+      .filter(|name| !name.is_empty())
+      .unwrap_or_else(|| String::from("download"))
+  }
+
+  // Returns the path of the file to which the data is written while downloading the
+  // file at the given path.
+  fn part_path(path: &Path) -> PathBuf {
+    let mut part_path = path.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+  }
+
+  // Returns a path in the given folder with the given file name. If such a file or a
+  // .part file of a running download already exists, a number is appended to the file
+  // stem until the path is unique.
+  fn unique_path(folder: &Path, name: &str) -> PathBuf {
+    let mut path = folder.join(name);
+    let stem = path
+      .file_stem()
+      .map(|s| s.to_string_lossy().to_string())
+      .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+
+    let mut i = 1;
+    while path.exists() || Self::part_path(&path).exists() {
+      let name = match &extension {
+        Some(extension) => format!("{} ({}).{}", stem, i, extension),
+        None => format!("{} ({})", stem, i),
+      };
+      path = folder.join(name);
+      i += 1;
+    }
+
+    path
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+
+  // Object holding the internal state of a Download.
+  #[derive(Debug, Default)]
+  pub struct Download {
+    pub url: RefCell<String>,
+    pub path: RefCell<String>,
+    pub progress: Cell<f64>,
+    pub state: Cell<DownloadState>,
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for Download {
+    const NAME: &'static str = "Download";
+    type Type = super::Download;
+  }
+
+  impl ObjectImpl for Download {
+    fn properties() -> &'static [glib::ParamSpec] {
+      static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+        vec![
+          glib::ParamSpecString::builder("url").build(),
+          glib::ParamSpecString::builder("path").build(),
+          glib::ParamSpecDouble::builder("progress")
+            .minimum(-1.0)
+            .maximum(1.0)
+            .build(),
+          glib::ParamSpecEnum::builder::<DownloadState>(
+            "state",
+            DownloadState::default(),
+          )
+          .build(),
+        ]
+      });
+      PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+      match pspec.name() {
+        "url" => {
+          self.url.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "path" => {
+          self.path.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "progress" => {
+          self
+            .progress
+            .set(value.get().expect("The value needs to be of type `f64`."));
+        }
+        "state" => {
+          self.state.set(
+            value
+              .get()
+              .expect("The value needs to be of type `DownloadState`."),
+          );
+        }
+        _ => unimplemented!(),
+      }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+      match pspec.name() {
+        "url" => self.url.borrow().clone().to_value(),
+        "path" => self.path.borrow().clone().to_value(),
+        "progress" => self.progress.get().to_value(),
+        "state" => self.state.get().to_value(),
+        _ => unimplemented!(),
+      }
+    }
+  }
+}
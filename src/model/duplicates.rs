@@ -0,0 +1,396 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+// This module contains the functions which detect the same story published in multiple
+// feeds. Two items of different feeds are considered duplicates if their normalized URLs
+// are equal or if their titles are nearly identical. Items of the same feed are never
+// duplicates of each other.
+
+use std::collections::HashMap;
+
+use crate::model::FeedItem;
+
+// Query parameters with these names are only used for tracking and are removed from URLs
+// before they are compared. Parameters starting with "utm_" are removed as well.
+const TRACKING_PARAMETERS: [&str; 15] = [
+  "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "mc_cid", "mc_eid",
+  "igshid", "yclid", "twclid", "ref", "ref_src", "ref_url", "cmpid",
+];
+
+// Titles with fewer words than this are too generic to detect duplicates by title, for
+// instance "Weekly Thread" or "Release Notes".
+const MIN_TITLE_WORDS: usize = 4;
+
+// Titles whose normalized forms have at least this similarity are considered to be
+// nearly identical.
+const MIN_TITLE_SIMILARITY: f64 = 0.9;
+
+// ---------------------------------------------------------------------------------------
+// Splits the given items into groups of duplicates. Each item is contained in exactly one
+// group and the order of the given items is preserved, both within the groups and for
+// the first items of all groups. Hence, if the given items are sorted, the first item of
+// each group can be used as representative of the group. Each group contains at most one
+// item of each feed. URLs which only differ in their fragment are considered equal if
+// one of them has no fragment, as in "story" and "story#comments", but not if both have
+// different fragments, as in "changes#v1.2" and "changes#v1.3".
+pub fn find_duplicates(items: &[FeedItem]) -> Vec<Vec<FeedItem>> {
+  let mut groups: Vec<Vec<FeedItem>> = vec![];
+
+  // Normalized URLs without fragment and normalized titles of all groups. The fragments
+  // are stored next to the groups. The titles are bucketed by their first words so that
+  // we do not have to compare each pair of items.
+  let mut by_url: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+  let mut by_prefix: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+
+  for item in items {
+    let url = normalize_url(&item.get_url());
+    let (url, fragment) = match url.split_once('#') {
+      Some((url, fragment)) => (url.to_string(), fragment.to_string()),
+      None => (url, String::new()),
+    };
+    let title = normalize_title(&item.get_title());
+    let prefix = title_prefix(&title);
+
+    // Items are only added to groups which do not contain an item of the same feed yet.
+    let feed_title = item.get_feed_title().clone();
+    let is_other_feed = |group: &usize| {
+      groups[*group]
+        .iter()
+        .all(|i| *i.get_feed_title() != feed_title)
+    };
+
+    let by_same_url =
+      by_url
+        .get(&url)
+        .filter(|_| !url.is_empty())
+        .and_then(|candidates| {
+          candidates
+            .iter()
+            .filter(|(other, _)| {
+              fragment.is_empty() || other.is_empty() || *other == fragment
+            })
+            .map(|(_, group)| *group)
+            .find(is_other_feed)
+        });
+
+    let by_title = prefix.as_ref().and_then(|prefix| {
+      by_prefix
+        .get(prefix)?
+        .iter()
+        .filter(|(other, _)| similarity(&title, other) >= MIN_TITLE_SIMILARITY)
+        .map(|(_, group)| *group)
+        .find(is_other_feed)
+    });
+
+    let group = match by_same_url.or(by_title) {
+      Some(group) => {
+        groups[group].push(item.clone());
+        group
+      }
+      None => {
+        groups.push(vec![item.clone()]);
+        groups.len() - 1
+      }
+    };
+
+    if !url.is_empty() {
+      by_url.entry(url).or_default().push((fragment, group));
+    }
+
+    if let Some(prefix) = prefix {
+      by_prefix.entry(prefix).or_default().push((title, group));
+    }
+  }
+
+  groups
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the titles of all feeds which contain an item of the given group of duplicates,
+// except for the feed of the given item. Each title is contained only once.
+pub fn get_other_feed_titles(group: &[FeedItem], item: &FeedItem) -> Vec<String> {
+  let mut titles: Vec<String> = vec![];
+
+  for other in group {
+    let title = other.get_feed_title().clone();
+    if title != *item.get_feed_title() && !titles.contains(&title) {
+      titles.push(title);
+    }
+  }
+
+  titles
+}
+
+// ---------------------------------------------------------------------------------------
+// Normalizes the given URL so that different variants of the same URL become equal. The
+// scheme is set to https, the host is lower-cased and "www." or "amp." prefixes are
+// removed. Tracking parameters, AMP suffixes of the path, and trailing slashes are
+// removed as well. The fragment is kept, as some feeds link to different sections of
+// the same page. Strings which are not valid URLs are returned trimmed.
+pub fn normalize_url(url: &str) -> String {
+  let mut parsed = match url::Url::parse(url.trim()) {
+    Ok(parsed) => parsed,
+    Err(_) => return url.trim().to_string(),
+  };
+
+  if parsed.scheme() == "http" {
+    parsed.set_scheme("https").ok();
+  }
+
+  if let Some(host) = parsed.host_str() {
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let host = host.strip_prefix("amp.").unwrap_or(host).to_string();
+    parsed.set_host(Some(&host)).ok();
+  }
+
+  // Remove all tracking parameters and parameters requesting an AMP page.
+  let query: Vec<(String, String)> = parsed
+    .query_pairs()
+    .filter(|(key, value)| {
+      let key = key.to_lowercase();
+      let is_amp = key == "amp" || (key == "outputtype" && value == "amp");
+      !is_amp && !key.starts_with("utm_") && !TRACKING_PARAMETERS.contains(&key.as_str())
+    })
+    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+    .collect();
+
+  if query.is_empty() {
+    parsed.set_query(None);
+  } else {
+    parsed.query_pairs_mut().clear().extend_pairs(query);
+  }
+
+  // Remove AMP suffixes like "/amp" or ".amp" and trailing slashes from the path.
+  let mut path = parsed.path().trim_end_matches('/').to_string();
+  for suffix in ["/amp", ".amp"] {
+    if let Some(stripped) = path.strip_suffix(suffix) {
+      path = stripped.trim_end_matches('/').to_string();
+    }
+  }
+  parsed.set_path(&path);
+
+  let fragment = parsed.fragment().map(String::from);
+  parsed.set_fragment(None);
+
+  let mut result: String = parsed.into();
+  if result.ends_with('/') {
+    result.pop();
+  }
+
+  match fragment {
+    Some(fragment) if !fragment.is_empty() => format!("{}#{}", result, fragment),
+    _ => result,
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Normalizes the given title so that minor differences in punctuation, whitespace, or
+// capitalization do not matter. Site names which are appended like in "Title - Site" or
+// "Title | Site" are removed as well.
+pub fn normalize_title(title: &str) -> String {
+  let mut title = title.trim();
+
+  for separator in [" | ", " - ", " – ", " — "] {
+    if let Some((head, tail)) = title.rsplit_once(separator) {
+      let is_site_name = tail.split_whitespace().count() <= 3;
+      let is_long_enough = head.split_whitespace().count() >= MIN_TITLE_WORDS;
+      if is_site_name && is_long_enough {
+        title = head;
+        break;
+      }
+    }
+  }
+
+  title
+    .to_lowercase()
+    .chars()
+    .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+    .collect::<String>()
+    .split_whitespace()
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the first two words of the given normalized title. Only titles with the same
+// prefix are compared. If the title is too short for detecting duplicates, None is
+// returned.
+fn title_prefix(title: &str) -> Option<String> {
+  let words: Vec<&str> = title.split_whitespace().collect();
+
+  if words.len() < MIN_TITLE_WORDS {
+    return None;
+  }
+
+  Some(words[..2].join(" "))
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the similarity of the two given strings in the range [0, 1]. This is based on
+// the Levenshtein distance of the two strings, whereby 1 means that they are equal.
+fn similarity(a: &str, b: &str) -> f64 {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+  let max = a.len().max(b.len());
+
+  if max == 0 {
+    return 1.0;
+  }
+
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+  let mut current = vec![0; b.len() + 1];
+
+  for i in 1..=a.len() {
+    current[0] = i;
+    for j in 1..=b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+      current[j] = (previous[j] + 1)
+        .min(current[j - 1] + 1)
+        .min(previous[j - 1] + cost);
+    }
+    std::mem::swap(&mut previous, &mut current);
+  }
+
+  1.0 - previous[b.len()] as f64 / max as f64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn url_tracking_parameters() {
+    assert_eq!(
+      normalize_url("https://example.com/story?utm_source=rss&id=42&fbclid=abc"),
+      "https://example.com/story?id=42"
+    );
+  }
+
+  #[test]
+  fn url_variants() {
+    let expected = "https://example.com/2022/11/story";
+
+    assert_eq!(
+      normalize_url("http://www.example.com/2022/11/story/"),
+      expected
+    );
+    assert_eq!(
+      normalize_url("https://example.com/2022/11/story/#comments"),
+      "https://example.com/2022/11/story#comments"
+    );
+    assert_eq!(
+      normalize_url("https://amp.example.com/2022/11/story"),
+      expected
+    );
+    assert_eq!(
+      normalize_url("https://example.com/2022/11/story/amp/"),
+      expected
+    );
+    assert_eq!(
+      normalize_url("https://example.com/2022/11/story?outputType=amp"),
+      expected
+    );
+  }
+
+  #[test]
+  fn title_with_site_name() {
+    assert_eq!(
+      normalize_title("Apple Announces New MacBook Pro Models - The Verge"),
+      "apple announces new macbook pro models"
+    );
+    assert_eq!(
+      normalize_title("Rust 1.65 - Released"),
+      "rust 1 65 released"
+    );
+  }
+
+  // Creates an item with the given title and URL which was published in the given feed.
+  fn item(feed_title: &str, title: &str, url: &str) -> FeedItem {
+    let item = FeedItem::new(&title.into(), &url.into(), 0);
+    item.set_property("feed-title", feed_title);
+    item
+  }
+
+  #[test]
+  fn nearly_identical_titles() {
+    let a = item(
+      "The Verge",
+      "Apple announces new MacBook Pro models",
+      "https://www.theverge.com/macbook",
+    );
+    let b = item(
+      "Ars Technica",
+      "Apple Announces New MacBook Pro Models!",
+      "https://arstechnica.com/macbook",
+    );
+    let c = item(
+      "Ars Technica",
+      "Apple announces new iPad models",
+      "https://arstechnica.com/ipad",
+    );
+
+    let groups = find_duplicates(&[a, b, c]);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].len(), 2);
+  }
+
+  #[test]
+  fn same_feed_is_never_grouped() {
+    let a = item(
+      "Advisories",
+      "Security advisory for libexample CVE-2022-1234",
+      "https://example.com/advisories/1234",
+    );
+    let b = item(
+      "Advisories",
+      "Security advisory for libexample CVE-2022-1235",
+      "https://example.com/advisories/1235",
+    );
+    let c = item(
+      "Mirror",
+      "Security advisory for libexample CVE-2022-1234",
+      "https://example.com/advisories/1234",
+    );
+
+    let groups = find_duplicates(&[a, b, c]);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].len(), 2);
+    assert_eq!(*groups[0][1].get_feed_title(), "Mirror");
+    assert_eq!(groups[1].len(), 1);
+  }
+
+  #[test]
+  fn fragments() {
+    let a = item(
+      "Changelog",
+      "Version 1.2",
+      "https://example.com/changes#v1.2",
+    );
+    let b = item(
+      "Changelog",
+      "Version 1.3",
+      "https://example.com/changes#v1.3",
+    );
+    let c = item("News", "Version 1.3", "https://example.com/changes#v1.3");
+
+    let groups = find_duplicates(&[a, b, c]);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[1].len(), 2);
+
+    let a = item("News", "Story", "https://example.com/story#comments");
+    let b = item("Other News", "Story", "https://example.com/story");
+    let groups = find_duplicates(&[a, b]);
+    assert_eq!(groups.len(), 1);
+  }
+}
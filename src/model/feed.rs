@@ -13,12 +13,15 @@ use gtk::{gdk, gio, glib, prelude::*, subclass::prelude::*};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{
-  cell::{Ref, RefCell},
-  error::Error,
+  cell::{Cell, Ref, RefCell},
+  collections::{HashMap, HashSet},
   sync::atomic::{AtomicUsize, Ordering},
 };
 
-use crate::model::FeedItem;
+use crate::model::{
+  parser, Credentials, DateSource, FeedItem, FeedSource, FetchedContent, HighlightRule,
+  MuteRule, NetworkState, RuleMatcher, ScrapeConfig, TlsConfig, WebSubLinks,
+};
 
 // ---------------------------------------------------------------------------------------
 // The StoredFeed is used for storing the currently configured feeds in the settings.
@@ -39,14 +42,161 @@ pub struct StoredFeed {
   // The currently configured filter for this feed.
   #[serde(default, skip_serializing_if = "String::is_empty")]
   pub filter: String,
+
+  // The name of the filter preset used by this feed. If this is empty, the filter has
+  // been entered manually.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub preset: String,
+
+  // The order in which the feed items are shown.
+  #[serde(default)]
+  pub order: ItemOrder,
+
+  // The position of the feed in the sidebar if the feeds are ordered manually.
+  #[serde(default)]
+  pub position: u32,
+
+  // Whether thumbnail images should be shown next to the feed items.
+  #[serde(default)]
+  pub show_thumbnails: bool,
+
+  // For feed items without any date, this stores the unix timestamps at which they have
+  // been seen for the first time. The items are identified by their URL or, if they have
+  // none, by their title.
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub first_seen: HashMap<String, i64>,
+
+  // If this is set, the URL points to a web page without a feed. The items are scraped
+  // from the HTML of the page using the CSS selectors of this config.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub scrape: Option<ScrapeConfig>,
+
+  // The ID under which the credentials of this feed are stored in the keyring. The
+  // credentials themselves are never stored in the settings. This is empty if the feed
+  // is downloaded anonymously.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub secret: String,
+
+  // The client certificate and the certificate exceptions for the server of this feed.
+  #[serde(default, skip_serializing_if = "TlsConfig::is_default")]
+  pub tls: TlsConfig,
+}
+
+// ---------------------------------------------------------------------------------------
+// The feeds in the sidebar can be shown in either of these orders. The order is stored
+// under the GSettings key /io/github/schneegans/BingeRSS/feed-order using the strings
+// returned by FeedOrder::nick().
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FeedOrder {
+  Alphabetical,
+  Unread,
+  RecentItem,
+  LastError,
+  Manual,
+}
+
+impl FeedOrder {
+  // Returns the FeedOrder for the given string as stored in the settings. Unknown
+  // strings result in FeedOrder::Alphabetical.
+  pub fn from_nick(nick: &str) -> Self {
+    match nick {
+      "unread" => FeedOrder::Unread,
+      "recent-item" => FeedOrder::RecentItem,
+      "last-error" => FeedOrder::LastError,
+      "manual" => FeedOrder::Manual,
+      _ => FeedOrder::Alphabetical,
+    }
+  }
+
+  // Compares the two given Feeds according to this order. Feeds which are equal according
+  // to the primary criterion are sorted alphabetically by their case-insensitive title.
+  pub fn compare(&self, a: &Feed, b: &Feed) -> std::cmp::Ordering {
+    let by_title = a
+      .get_title()
+      .to_lowercase()
+      .cmp(&b.get_title().to_lowercase());
+
+    match self {
+      FeedOrder::Alphabetical => by_title,
+      FeedOrder::Unread => b.get_unread().cmp(&a.get_unread()).then(by_title),
+      FeedOrder::RecentItem => {
+        b.get_newest_date().cmp(&a.get_newest_date()).then(by_title)
+      }
+      FeedOrder::LastError => b.get_error_time().cmp(&a.get_error_time()).then(by_title),
+      FeedOrder::Manual => a.get_position().cmp(&b.get_position()).then(by_title),
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// The items of each Feed can be shown in either of these orders. The order is a property
+// of the Feed, so it is stored together with the other settings of the feed. As the order
+// is also exposed via a gio::PropertyAction, the nicks of the variants are used as
+// targets in menus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Deserialize, Serialize)]
+#[enum_type(name = "ItemOrder")]
+#[serde(rename_all = "kebab-case")]
+pub enum ItemOrder {
+  #[enum_value(name = "NewestFirst", nick = "newest-first")]
+  NewestFirst,
+  #[enum_value(name = "OldestFirst", nick = "oldest-first")]
+  OldestFirst,
+  #[enum_value(name = "Title", nick = "title")]
+  Title,
+  #[enum_value(name = "Author", nick = "author")]
+  Author,
+  #[enum_value(name = "UnreadFirst", nick = "unread-first")]
+  UnreadFirst,
+}
+
+impl Default for ItemOrder {
+  fn default() -> Self {
+    ItemOrder::NewestFirst
+  }
+}
+
+impl ItemOrder {
+  // Compares the two given FeedItems according to this order. The given timestamp is the
+  // last time the feed was viewed; it is used to decide whether the items are unread.
+  // Items which are equal according to the primary criterion are sorted by date with the
+  // newest items first.
+  pub fn compare(&self, a: &FeedItem, b: &FeedItem, viewed: i64) -> std::cmp::Ordering {
+    let by_date = b.get_date().cmp(&a.get_date());
+
+    match self {
+      ItemOrder::NewestFirst => by_date,
+      ItemOrder::OldestFirst => by_date.reverse(),
+      ItemOrder::Title => {
+        let a = a.get_title().to_lowercase();
+        let b = b.get_title().to_lowercase();
+        a.cmp(&b).then(by_date)
+      }
+      ItemOrder::Author => {
+        let a = a.get_author().to_lowercase();
+        let b = b.get_author().to_lowercase();
+        a.cmp(&b).then(by_date)
+      }
+      ItemOrder::UnreadFirst => {
+        let a = a.is_newer(viewed) && !a.is_read();
+        let b = b.is_newer(viewed) && !b.is_read();
+        b.cmp(&a).then(by_date)
+      }
+    }
+  }
 }
 
 // ---------------------------------------------------------------------------------------
 // Each Feed is in either of these states. Initially, the URL is empty. If the URL is set
 // to any value, the state will change to DownloadPending. Then, if the download() method
-// of the Feed is called, the state will change to DownloadStarted. If the download fails
-// or succeeds, the state will change to either DownloadFailed or DownloadSucceeded. Since
-// the state is a property of the Feed, you can get notified whenever it changes.
+// of the Feed is called, the state will change to DownloadStarted, unless the feed has to
+// be downloaded from the internet and the network is unavailable. The same happens for
+// the refresh() method, which additionally waits while automatic refreshes are paused on
+// a metered connection. If the download fails or succeeds, the state will change to
+// either DownloadFailed or DownloadSucceeded. If the URL points to a local file which
+// does not exist, the state will change to FileMissing instead of DownloadFailed.
+// Similarly, if the server requires credentials or rejects the configured ones, the
+// state will change to AuthenticationRequired. Since the state is a property of the
+// Feed, you can get notified whenever it changes.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum)]
 #[enum_type(name = "FeedState")]
 pub enum FeedState {
@@ -54,6 +204,8 @@ pub enum FeedState {
   DownloadPending,
   DownloadStarted,
   DownloadFailed,
+  FileMissing,
+  AuthenticationRequired,
   DownloadSucceeded,
 }
 
@@ -65,12 +217,24 @@ impl Default for FeedState {
 
 // ---------------------------------------------------------------------------------------
 // Feed objects store the information on single feeds, like its name, url, or any applied
-// filters. In addition, they allow to download the actual feed content from the internet.
-// You can then access the individual feed items and an icon for the feed via its
-// get_items() and get_icon() methods. There is also an interface for getting the number
-// of unread items. This is done by comparing the publication timestamps of the feed items
-// to the last time feed.set_viewed() was called. This may not work in all cases but it
-// makes it unnecessary to store all feeds locally.
+// filters. In addition, they allow to download the actual feed content from the internet
+// or to read it from a local file. Local files are watched, so that changes are loaded
+// immediately. Web pages without a feed can be used as well if a ScrapeConfig is set; the
+// items are then extracted from the HTML of the page. For feeds which require
+// authentication, credentials can be set; they are sent with each request. Similarly, a
+// TlsConfig can be set for servers which require client certificates. If a feed links to
+// a WebSub hub, content pushed by the hub can be added with push_content(). You can then
+// access the individual feed items and an icon for the feed via its get_items() and
+// get_icon() methods. There is also an interface for getting the number of unread items.
+// This is done by comparing the publication timestamps of the feed items to the last time
+// feed.set_viewed() was called. This may not work in all cases but it makes it
+// unnecessary to store all feeds locally. Items which have been read explicitly are never
+// counted as unread. The unread property only counts items which match the feed's filter,
+// the total-unread property counts all unread items. Items matching any of the global
+// mute rules are marked as muted; they are not counted as unread either and the muted
+// property stores how many of them there are. Similarly, the global highlight rules are
+// applied to all items. The important property stores how many unread items match an
+// important highlight rule.
 glib::wrapper! {
   pub struct Feed(ObjectSubclass<imp::Feed>);
 }
@@ -87,14 +251,222 @@ impl Feed {
       .build()
   }
 
+  // Creates a Feed from the information stored in the settings. The credentials have to
+  // be looked up in the keyring by the caller.
+  pub fn from_stored(feed: &StoredFeed, credentials: Option<Credentials>) -> Self {
+    let this: Self = glib::Object::builder()
+      .property("title", &feed.title)
+      .property("order", feed.order)
+      .property("position", feed.position)
+      .property("show-thumbnails", feed.show_thumbnails)
+      .property("filter", &feed.filter)
+      .property("preset", &feed.preset)
+      .property("viewed", feed.viewed)
+      .build();
+
+    // The first-seen timestamps, the scrape config, the credentials, and the TLS config
+    // have to be restored before the URL is set, as this triggers the download of the
+    // feed.
+    this.imp().first_seen.replace(feed.first_seen.clone());
+    this.imp().scrape_config.replace(feed.scrape.clone());
+    this.imp().secret.replace(feed.secret.clone());
+    this.imp().credentials.replace(credentials);
+    this.imp().tls_config.replace(feed.tls.clone());
+
+    // Restoring the feeds at startup is an automatic refresh.
+    this.imp().url.replace(feed.url.clone());
+    this.load_url(true);
+    this.notify("url");
+
+    this
+  }
+
   // ---------------------------------------------------------------------- public methods
 
-  // This method downloads the feed information from the configured URL. This happens in a
-  // separate thread. If there is a download operation currently ongoing, it will be
-  // canceled. As soon as the download starts, succeeds, or fails, the state property will
-  // change accordingly. If the state changes to DownloadSucceeded, you can check the
-  // items and icon of the Feed with the get_items() and get_icon() methods.
+  // Returns a StoredFeed containing all information required to restore this Feed later.
+  pub fn to_stored(&self) -> StoredFeed {
+    StoredFeed {
+      title: self.get_title().clone(),
+      url: self.get_url().clone(),
+      filter: self.get_filter().clone(),
+      preset: self.get_preset().clone(),
+      viewed: *self.get_viewed(),
+      order: *self.get_order(),
+      position: self.get_position(),
+      show_thumbnails: self.get_show_thumbnails(),
+      first_seen: self.imp().first_seen.borrow().clone(),
+      scrape: self.get_scrape_config().clone(),
+      secret: match self.get_credentials().as_ref() {
+        Some(_) => self.get_secret().clone(),
+        None => String::new(),
+      },
+      tls: self.get_tls_config().clone(),
+    }
+  }
+
+  // This method downloads the feed information from the configured URL or reads it from
+  // the configured file. This happens in a separate thread. If there is a download
+  // operation currently ongoing, it will be canceled. As soon as the download starts,
+  // succeeds, or fails, the state property will change accordingly. If the state changes
+  // to DownloadSucceeded, you can check the items and icon of the Feed with the
+  // get_items() and get_icon() methods. This should be used if the user asked for the
+  // download; use refresh() for automatic downloads.
   pub fn download(&self) {
+    self.fetch(false);
+  }
+
+  // This is like download(), but web feeds are not downloaded while automatic refreshes
+  // are paused on a metered connection. This should be used for all downloads which
+  // were not requested by the user.
+  pub fn refresh(&self) {
+    self.fetch(true);
+  }
+
+  // Adds the items of content which was pushed by a WebSub hub. Hubs often only push the
+  // new entries, so the current items are kept and only unknown items are added. If the
+  // feed has not been downloaded successfully before, it is refreshed instead.
+  pub fn push_content(&self, content: feed_rs::model::Feed) {
+    if *self.get_state() != FeedState::DownloadSucceeded {
+      self.refresh();
+      return;
+    }
+
+    let feed_title = self.get_title().clone();
+    let base = url::Url::parse(&self.get_url()).ok();
+    let known: HashSet<String> =
+      self.get_items().iter().map(Self::get_item_key).collect();
+
+    let new_items: Vec<FeedItem> = content
+      .entries
+      .iter()
+      .map(|entry| parser::entry_to_item(entry, &feed_title, base.as_ref()))
+      .filter(|item| !known.contains(&Self::get_item_key(item)))
+      .collect();
+
+    if new_items.is_empty() {
+      return;
+    }
+
+    self
+      .imp()
+      .items
+      .borrow_mut()
+      .splice(0..0, new_items.iter().cloned());
+
+    self.assign_first_seen_dates();
+    self.apply_mute_rules();
+    self.apply_highlight_rules();
+    self.watch_read_state(&new_items);
+
+    // This makes the user interface and the application pick up the new items as if
+    // the feed had been downloaded.
+    self.set_property("state", FeedState::DownloadSucceeded);
+
+    self.notify("unread");
+    self.notify("total-unread");
+    self.notify("important");
+  }
+
+  // Get the CSS selectors which are used to scrape the items from a web page. This is
+  // None for normal feeds.
+  pub fn get_scrape_config(&self) -> Ref<Option<ScrapeConfig>> {
+    self.imp().scrape_config.borrow()
+  }
+
+  // Sets the CSS selectors which are used to scrape the items from the web page at the
+  // configured URL. If this is None, the URL has to point to a feed. If the config
+  // changes, the feed is downloaded again.
+  pub fn set_scrape_config(&self, config: Option<ScrapeConfig>) {
+    if *self.get_scrape_config() != config {
+      self.imp().scrape_config.replace(config);
+      self.download();
+    }
+  }
+
+  // Get the credentials which are sent with each request for the feed. This is None if
+  // the feed is downloaded anonymously.
+  pub fn get_credentials(&self) -> Ref<Option<Credentials>> {
+    self.imp().credentials.borrow()
+  }
+
+  // Sets the credentials which are sent with each request for the feed. If they change,
+  // the feed is downloaded again. The credentials are not stored in the keyring by this
+  // method; this happens when the feeds are saved. A new secret ID is created for feeds
+  // which did not have credentials before.
+  pub fn set_credentials(&self, credentials: Option<Credentials>) {
+    if *self.get_credentials() == credentials {
+      return;
+    }
+
+    if credentials.is_some() && self.get_secret().is_empty() {
+      self
+        .imp()
+        .secret
+        .replace(glib::uuid_string_random().to_string());
+    }
+
+    self.imp().credentials.replace(credentials);
+    self.notify("authenticated");
+    self.download();
+  }
+
+  // Get the ID under which the credentials are stored in the keyring. This may be
+  // non-empty even if there are no credentials anymore; in this case, the credentials
+  // should be removed from the keyring.
+  pub fn get_secret(&self) -> Ref<String> {
+    self.imp().secret.borrow()
+  }
+
+  // Get the client certificate and the certificate exceptions which are used for the
+  // connection to the server of the feed.
+  pub fn get_tls_config(&self) -> Ref<TlsConfig> {
+    self.imp().tls_config.borrow()
+  }
+
+  // Sets the TLS config of the feed. If it changes, the feed is downloaded again.
+  pub fn set_tls_config(&self, config: TlsConfig) {
+    if *self.get_tls_config() != config {
+      self.imp().tls_config.replace(config);
+      self.download();
+    }
+  }
+
+  // Get the WebSub hub and topic of the feed. This is None if the last downloaded feed
+  // did not link to a hub.
+  pub fn get_websub_links(&self) -> Ref<Option<WebSubLinks>> {
+    self.imp().websub_links.borrow()
+  }
+
+  // Get the HTML of the web page which was fetched by the last successful download of a
+  // feed with a scrape config. This can be used to preview other selectors.
+  pub fn get_page(&self) -> Ref<String> {
+    self.imp().page.borrow()
+  }
+
+  // Sets the global mute rules. All items matching any of these rules are marked as
+  // muted. The rules are also applied to all items of subsequent downloads.
+  pub fn set_mute_rules(&self, rules: &[MuteRule]) {
+    let matchers = rules.iter().map(MuteRule::get_matcher).collect();
+    self.imp().mute_rules.replace(matchers);
+    self.apply_mute_rules();
+  }
+
+  // Sets the global highlight rules. They are applied to all current items and to all
+  // items of subsequent downloads.
+  pub fn set_highlight_rules(&self, rules: &[HighlightRule]) {
+    let rules = rules
+      .iter()
+      .map(|rule| (rule.clone(), rule.get_matcher()))
+      .collect();
+    self.imp().highlight_rules.replace(rules);
+    self.apply_highlight_rules();
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Downloads or reads the feed. This implements download() and refresh(); the latter
+  // passes true for automatic.
+  fn fetch(&self, automatic: bool) {
     // First cancel any ongoing download operation. This will not abort the actual
     // download thread, but we will ignore its result.
     if self.imp().download_source_id.borrow().is_some() {
@@ -107,94 +479,298 @@ impl Feed {
       return;
     }
 
+    // Web URLs of feeds with a scrape config point to HTML pages instead of feeds.
+    let source = match FeedSource::from_url(&self.get_url()) {
+      FeedSource::Web(url) if self.get_scrape_config().is_some() => FeedSource::Page(url),
+      source => source,
+    };
+
+    // While offline, web feeds are not downloaded at all, as this would only fail. The
+    // same applies to automatic refreshes while they are paused. Downloaded feeds keep
+    // their items, all others stay pending until they are refreshed once the network is
+    // available again.
+    if source.is_remote() && !NetworkState::current().allows_download(automatic) {
+      if *self.get_state() != FeedState::DownloadSucceeded {
+        self.set_property("state", FeedState::DownloadPending);
+      }
+      return;
+    }
+
     // Notify about the started download operation.
     self.set_property("state", FeedState::DownloadStarted);
 
-    let url_copy = self.imp().url.borrow().clone();
+    let credentials = self.get_credentials().clone();
+    let tls = self.get_tls_config().clone();
 
-    // Spawn a thread for downloading the feed data.
-    let handle = crate::RUNTIME.spawn(async move {
-      // Download from the URL and parse the feed information.
-      let bytes = reqwest::get(&url_copy).await?.bytes().await?;
-      let content = feed_rs::parser::parse(&bytes[..])?;
+    // Now spawn an asynchronous future on the main context. This will fetch the feed data
+    // in the background and then store the feed items and the icon in our private
+    // members. We will store the returned download_source_id so that we are able to
+    // cancel this if download() is called again.
+    let ctx = glib::MainContext::default();
+    self.imp().download_source_id.replace(Some(ctx.spawn_local(
+      glib::clone!(@weak self as this => async move {
 
-      // Try to download the favicon from the feed.
-      let url = url::Url::parse(&content.links[0].href);
-      let icon_url = url.as_ref().unwrap().scheme().to_string()
-        + &String::from("://")
-        + &url.as_ref().unwrap().host().unwrap().to_string()
-        + &String::from("/favicon.ico");
+        // Asynchronously wait for the download to finish. If everything succeeds, the
+        // result will contain the feed content as well as the data for the icon.
+        let result = source.fetch(credentials, tls).await;
 
-      let bytes = reqwest::get(icon_url).await?.bytes().await?;
-      let image = Some(glib::Bytes::from(&bytes.to_vec()));
+        // Reset the download_source_id.
+        this.imp().download_source_id.replace(None);
 
-      // If everything succeed, return the feed's content and the bytes for the icon.
-      Ok::<(feed_rs::model::Feed, Option<glib::Bytes>), Box<dyn Error + Send + Sync>>((
-        content, image,
-      ))
-    });
+        // Create the items from the fetched content. If the items cannot be scraped from
+        // a web page, this is treated like a failed download.
+        let result = result.and_then(|(content, image)| {
+          this.load_content(content)?;
+          Ok(image)
+        });
 
-    // Now spawn an asynchronous future on the main context. This will await the above
-    // thread to finish and then store the feed items and the icon in our private members.
-    // We will store the returned download_source_id so that we are able to cancel this if
-    // download() is called again.
-    let ctx = glib::MainContext::default();
-    self.imp().download_source_id.replace(
-      Some(ctx.spawn_local(glib::clone!(@weak self as this => async move {
+        // Return early if the download failed. The error message is stored so that it can
+        // be shown to the user.
+        if let Err(err) = &result {
+          this.imp().error.replace(err.to_string());
+          this.notify("error");
+
+          let state = if FeedSource::is_missing_file(err.as_ref()) {
+            FeedState::FileMissing
+          } else if FeedSource::is_unauthorized(err.as_ref()) {
+            FeedState::AuthenticationRequired
+          } else {
+            FeedState::DownloadFailed
+          };
+
+          this.set_property("error-time", chrono::Utc::now().timestamp());
+          this.set_property("state", state);
+          return;
+        }
+
+        this.set_property("error-time", 0i64);
+        this.imp().error.replace(String::new());
+        this.notify("error");
+
+        let image = result.unwrap();
+
+        // Items without any date get the time at which we saw them for the first time.
+        this.assign_first_seen_dates();
+
+        // Hide all items matching the global mute rules and emphasize all items matching
+        // the global highlight rules.
+        this.apply_mute_rules();
+        this.apply_highlight_rules();
+
+        // Update the number of unread items whenever an item is marked as read.
+        this.watch_read_state(&this.get_items());
+
+        // Convert the image data to a gdk::Paintable.
+        if image.is_some() {
+          let stream = gio::MemoryInputStream::from_bytes(&image.unwrap());
+          let pixbuf =
+            gdk::gdk_pixbuf::Pixbuf::from_stream(&stream, gio::Cancellable::NONE);
+
+          if pixbuf.is_ok() {
+            let image = gtk::Image::from_pixbuf(Some(&pixbuf.unwrap()));
+            this.imp().icon.replace(Some(image.paintable().unwrap()));
+          }
+        }
+
+        this.set_property("state", FeedState::DownloadSucceeded);
+
+        // The number of unread items may have changed.
+        this.notify("unread");
+        this.notify("total-unread");
+        this.notify("important");
+      }),
+    )));
+  }
+
+  // Resets the items and the icon after the URL has been changed and loads the feed
+  // from the new URL. If the URL points to a local file, it is watched for changes.
+  fn load_url(&self, automatic: bool) {
+    self.imp().icon.replace(None);
+    self.imp().items.replace(vec![]);
+    self.update_file_monitor();
+
+    if self.get_url().is_empty() {
+      self.set_property("state", FeedState::EmptyURL);
+    } else {
+      self.set_property("state", FeedState::DownloadPending);
+      self.fetch(automatic);
+    }
+  }
+
+  // Creates the FeedItems from the given content. Items of feeds are created from their
+  // entries, items of web pages are scraped from the HTML using the scrape config. The
+  // HTML is kept so that it can be shown in the preview. If the title of this Feed is
+  // still "New Feed", it is replaced with the title of the feed or web page.
+  fn load_content(&self, content: FetchedContent) -> Result<(), String> {
+    let title = match &content {
+      FetchedContent::Feed(content) => content.title.as_ref().map(|t| t.content.clone()),
+      FetchedContent::Page(html) => ScrapeConfig::get_page_title(html),
+    };
+
+    if title.is_some() && self.get_title().eq("New Feed") {
+      self.set_property("title", title.unwrap());
+    }
 
-      // Asynchronously wait for the above thread to finish. Once the thread finished, the
-      // result will contain the feed content as well as the data for the icon.
-      let result = handle.await.unwrap();
+    let feed_title = self.get_title().clone();
+    let base = url::Url::parse(&self.get_url()).ok();
 
-      // Reset the download_source_id.
-      this.imp().download_source_id.replace(None);
+    let links = match &content {
+      FetchedContent::Feed(content) => WebSubLinks::from_feed(content, &self.get_url()),
+      FetchedContent::Page(_) => None,
+    };
+    self.imp().websub_links.replace(links);
 
-      // Return early if the download failed.
-      if result.is_err() {
-        this.set_property("state", FeedState::DownloadFailed);
-        return;
+    let items = match content {
+      FetchedContent::Feed(content) => content
+        .entries
+        .iter()
+        .map(|entry| parser::entry_to_item(entry, &feed_title, base.as_ref()))
+        .collect(),
+      FetchedContent::Page(html) => {
+        let config = self.get_scrape_config().clone().unwrap_or_default();
+        let items = config.scrape(&html, base.as_ref());
+        self.imp().page.replace(html);
+        items?
+          .iter()
+          .map(|item| item.to_item(&feed_title))
+          .collect()
       }
+    };
 
-      let (content, image) = result.unwrap();
+    self.imp().items.replace(items);
+
+    Ok(())
+  }
 
-      // Replace our title if it's still "New Feed".
-      if content.title.is_some() && this.get_title().eq("New Feed") {
-        this.set_property("title", content.title.unwrap().content.clone());
+  // If the URL points to a local file, the file is watched for changes. Whenever it is
+  // written, created, or deleted, the feed is loaded again. A file which does not exist
+  // yet can be watched as well.
+  fn update_file_monitor(&self) {
+    let source = FeedSource::from_url(&self.get_url());
+    let monitor = source.get_path().and_then(|path| {
+      gio::File::for_path(path)
+        .monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE)
+        .map_err(|err| println!("Failed to watch {}: {}", path.display(), err))
+        .ok()
+    });
+
+    if let Some(monitor) = &monitor {
+      monitor.connect_changed(glib::clone!(@weak self as this => move |_, _, _, event| {
+        if event == gio::FileMonitorEvent::ChangesDoneHint
+          || event == gio::FileMonitorEvent::Created
+          || event == gio::FileMonitorEvent::Deleted
+        {
+          this.download();
+        }
+      }));
+    }
+
+    if let Some(old) = self.imp().file_monitor.replace(monitor) {
+      old.cancel();
+    }
+  }
+
+  // Assigns the time at which they have been seen for the first time to all items which
+  // have no date. These timestamps are stored so that they remain the same across
+  // refreshes and restarts of the application. Timestamps of items which are not part of
+  // the feed anymore are forgotten.
+  fn assign_first_seen_dates(&self) {
+    let now = chrono::Utc::now().timestamp();
+    let mut first_seen = self.imp().first_seen.borrow_mut();
+    let mut seen_now = HashMap::new();
+
+    for item in self.get_items().iter() {
+      if item.get_date_source() != DateSource::FirstSeen {
+        continue;
       }
 
-      // Create FeedItems accordingly.
-      this.imp().items.replace(content.entries
+      let key = Self::get_item_key(item);
+      let date = *first_seen.get(&key).unwrap_or(&now);
+      item.set_property("date", date);
+      seen_now.insert(key, date);
+    }
+
+    *first_seen = seen_now;
+  }
+
+  // Items are identified by their URL or, if they have none, by their title.
+  fn get_item_key(item: &FeedItem) -> String {
+    if item.get_url().is_empty() {
+      item.get_title().clone()
+    } else {
+      item.get_url().clone()
+    }
+  }
+
+  // Read items are not counted as unread, so the number of unread items changes whenever
+  // one of the given items is marked as read.
+  fn watch_read_state(&self, items: &[FeedItem]) {
+    for item in items {
+      item.connect_notify_local(
+        Some("read"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.notify("unread");
+          this.notify("total-unread");
+          this.notify("important");
+        }),
+      );
+    }
+  }
+
+  // Returns true if the given item has been published after the last call to
+  // set_viewed() and has neither been read nor muted. The feed's filter is not
+  // considered.
+  fn is_unread(&self, item: &FeedItem) -> bool {
+    item.is_newer(*self.get_viewed()) && !item.is_read() && !item.is_muted()
+  }
+
+  // Marks all items which match any of the configured mute rules as muted and all other
+  // items as not muted. As this changes the number of unread and muted items, the
+  // corresponding properties are notified.
+  fn apply_mute_rules(&self) {
+    for item in self.get_items().iter() {
+      let muted = self
+        .imp()
+        .mute_rules
+        .borrow()
         .iter()
-        .map(|item| {
-          let title = if item.title.is_some() {
-            item.title.as_ref().unwrap().content.clone()
-          } else {
-            String::from("Unnamed Item")
-          };
+        .any(|matcher| matcher.matches(item));
 
-          let url = item.links[0].href.clone();
-          let date = if item.published.is_some() {item.published.unwrap().timestamp()} else {0};
+      if muted != item.is_muted() {
+        item.set_property("muted", muted);
+      }
+    }
 
-          FeedItem::new(&title, &url, date)
-        })
-        .collect());
+    self.notify("muted");
+    self.notify("unread");
+    self.notify("total-unread");
+    self.notify("important");
+  }
 
-      // Convert the image data to a gdk::Paintable.
-      if image.is_some() {
-        let stream = gio::MemoryInputStream::from_bytes(&image.unwrap());
-        let pixbuf = gdk::gdk_pixbuf::Pixbuf::from_stream(&stream, gio::Cancellable::NONE);
+  // Assigns the styles of all matching highlight rules to each item. Afterwards, the
+  // highlighted and important properties are notified so that the user interface can
+  // be updated.
+  fn apply_highlight_rules(&self) {
+    for item in self.get_items().iter() {
+      let rules = self.imp().highlight_rules.borrow();
+      let matching: Vec<&HighlightRule> = rules
+        .iter()
+        .filter(|(_, matcher)| matcher.matches(item))
+        .map(|(rule, _)| rule)
+        .collect();
 
-        if pixbuf.is_ok() {
-          let image = gtk::Image::from_pixbuf(Some(&pixbuf.unwrap()));
-          this.imp().icon.replace(Some(image.paintable().unwrap()));
+      let mut styles = vec![];
+      for rule in matching.iter() {
+        if !styles.contains(&rule.style) {
+          styles.push(rule.style);
         }
       }
 
-      this.set_property("state", FeedState::DownloadSucceeded);
+      item.set_highlights(styles, matching.iter().any(|rule| rule.important));
+    }
 
-      // The number of unread items may have changed.
-      this.notify("unread");
-    }))));
+    self.notify("highlighted");
+    self.notify("important");
   }
 
   // Return the name of the feed. If this is set to "New Feed", it will be overridden with
@@ -213,6 +789,50 @@ impl Feed {
     self.imp().filter.borrow()
   }
 
+  // Get the name of the filter preset used by this feed. This is empty if the filter has
+  // been entered manually.
+  pub fn get_preset(&self) -> Ref<String> {
+    self.imp().preset.borrow()
+  }
+
+  // Get the order in which the feed items should be shown.
+  pub fn get_order(&self) -> Ref<ItemOrder> {
+    self.imp().order.borrow()
+  }
+
+  // Get the position of the feed in the sidebar if the feeds are ordered manually.
+  pub fn get_position(&self) -> u32 {
+    self.imp().position.get()
+  }
+
+  // Returns true if thumbnail images should be shown next to the feed items.
+  pub fn get_show_thumbnails(&self) -> bool {
+    self.imp().show_thumbnails.get()
+  }
+
+  // Get a description of the reason why the last download failed. If the last download
+  // succeeded, this will be empty.
+  pub fn get_error(&self) -> Ref<String> {
+    self.imp().error.borrow()
+  }
+
+  // Get the unix timestamp at which the last download failed. If the last download
+  // succeeded, this will be zero.
+  pub fn get_error_time(&self) -> i64 {
+    self.imp().error_time.get()
+  }
+
+  // Get the publication date of the newest feed item. If there are no items, this will be
+  // zero.
+  pub fn get_newest_date(&self) -> i64 {
+    self
+      .get_items()
+      .iter()
+      .map(|i| i.get_date())
+      .max()
+      .unwrap_or(0)
+  }
+
   // Get the automatically assigned unique ID for this feed. All constructed feeds will
   // have a different ID, however if the application is restarted, a feed may get a
   // different ID than last time.
@@ -246,13 +866,53 @@ impl Feed {
   pub fn set_viewed(&self) {
     self.set_property("viewed", chrono::Utc::now().timestamp());
     self.notify("unread");
+    self.notify("total-unread");
+    self.notify("important");
+  }
+
+  // Returns true if the title of the given item contains the feed's filter. The filter
+  // may consist of multiple terms separated by " OR "; in this case, the title has to
+  // contain any of them. The comparison is case-insensitive. If the filter is empty, all
+  // items match.
+  pub fn matches_filter(&self, item: &FeedItem) -> bool {
+    Self::filter_matches(&self.get_filter(), item)
+  }
+
+  // Returns true if the title of the given item matches the given filter. This is used
+  // by matches_filter() and can be used for items without a Feed object.
+  pub fn filter_matches(filter: &str, item: &FeedItem) -> bool {
+    let title = item.get_title().to_lowercase();
+
+    let mut terms = filter
+      .split(" OR ")
+      .map(|term| term.trim().to_lowercase())
+      .filter(|term| !term.is_empty())
+      .peekable();
+
+    terms.peek().is_none() || terms.any(|term| title.contains(&term))
   }
 
   // Returns the number of feed items which have been published after the last call to
-  // set_viewed().
+  // set_viewed(), which have neither been read nor muted yet, and which match the feed's
+  // filter.
   pub fn get_unread(&self) -> i32 {
     self.property("unread")
   }
+
+  // Returns the number of unread feed items regardless of the feed's filter.
+  pub fn get_total_unread(&self) -> i32 {
+    self.property("total-unread")
+  }
+
+  // Returns the number of feed items which are hidden by the global mute rules.
+  pub fn get_muted(&self) -> i32 {
+    self.property("muted")
+  }
+
+  // Returns the number of unread feed items which match an important highlight rule.
+  pub fn get_important(&self) -> i32 {
+    self.property("important")
+  }
 }
 
 mod imp {
@@ -270,13 +930,29 @@ mod imp {
     pub title: RefCell<String>,
     pub url: RefCell<String>,
     pub filter: RefCell<String>,
+    pub preset: RefCell<String>,
     pub viewed: RefCell<i64>,
+    pub order: RefCell<ItemOrder>,
+    pub position: Cell<u32>,
+    pub show_thumbnails: Cell<bool>,
     pub state: RefCell<FeedState>,
+    pub error_time: Cell<i64>,
+    pub error: RefCell<String>,
 
     // These are set by the download() method.
     pub items: RefCell<Vec<FeedItem>>,
+    pub first_seen: RefCell<HashMap<String, i64>>,
+    pub mute_rules: RefCell<Vec<RuleMatcher>>,
+    pub highlight_rules: RefCell<Vec<(HighlightRule, RuleMatcher)>>,
     pub icon: RefCell<Option<gdk::Paintable>>,
     pub download_source_id: RefCell<Option<glib::SourceId>>,
+    pub file_monitor: RefCell<Option<gio::FileMonitor>>,
+    pub scrape_config: RefCell<Option<ScrapeConfig>>,
+    pub page: RefCell<String>,
+    pub credentials: RefCell<Option<Credentials>>,
+    pub secret: RefCell<String>,
+    pub tls_config: RefCell<TlsConfig>,
+    pub websub_links: RefCell<Option<WebSubLinks>>,
   }
 
   #[glib::object_subclass]
@@ -302,8 +978,26 @@ mod imp {
           glib::ParamSpecString::builder("title").build(),
           glib::ParamSpecString::builder("url").build(),
           glib::ParamSpecString::builder("filter").build(),
+          glib::ParamSpecString::builder("preset").build(),
           glib::ParamSpecInt64::builder("viewed").build(),
           glib::ParamSpecInt::builder("unread").read_only().build(),
+          glib::ParamSpecInt::builder("total-unread")
+            .read_only()
+            .build(),
+          glib::ParamSpecInt::builder("muted").read_only().build(),
+          glib::ParamSpecInt::builder("highlighted")
+            .read_only()
+            .build(),
+          glib::ParamSpecInt::builder("important").read_only().build(),
+          glib::ParamSpecEnum::builder::<ItemOrder>("order", ItemOrder::default())
+            .build(),
+          glib::ParamSpecUInt::builder("position").build(),
+          glib::ParamSpecBoolean::builder("show-thumbnails").build(),
+          glib::ParamSpecInt64::builder("error-time").build(),
+          glib::ParamSpecString::builder("error").read_only().build(),
+          glib::ParamSpecBoolean::builder("authenticated")
+            .read_only()
+            .build(),
           glib::ParamSpecEnum::builder::<FeedState>("state", FeedState::default())
             .build(),
         ]
@@ -327,15 +1021,8 @@ mod imp {
               .expect("The value needs to be of type `String`."),
           );
 
-          self.obj().imp().icon.replace(None);
-          self.obj().imp().items.replace(vec![]);
-
-          if self.url.borrow().is_empty() {
-            self.obj().set_property("state", FeedState::EmptyURL);
-          } else {
-            self.obj().set_property("state", FeedState::DownloadPending);
-            self.obj().download();
-          }
+          // Changing the URL is considered a request of the user.
+          self.obj().load_url(false);
         }
         "filter" => {
           self.filter.replace(
@@ -343,12 +1030,45 @@ mod imp {
               .get()
               .expect("The value needs to be of type `String`."),
           );
+
+          // Only items matching the filter are counted as unread or important.
+          self.obj().notify("unread");
+          self.obj().notify("important");
+        }
+        "preset" => {
+          self.preset.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
         }
         "viewed" => {
           self
             .viewed
             .replace(value.get().expect("The value needs to be of type `i64`."));
         }
+        "order" => {
+          self.order.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `ItemOrder`."),
+          );
+        }
+        "position" => {
+          self
+            .position
+            .set(value.get().expect("The value needs to be of type `u32`."));
+        }
+        "show-thumbnails" => {
+          self
+            .show_thumbnails
+            .set(value.get().expect("The value needs to be of type `bool`."));
+        }
+        "error-time" => {
+          self
+            .error_time
+            .set(value.get().expect("The value needs to be of type `i64`."));
+        }
         "state" => {
           self.state.replace(
             value
@@ -365,7 +1085,14 @@ mod imp {
         "title" => self.title.borrow().clone().to_value(),
         "url" => self.url.borrow().clone().to_value(),
         "filter" => self.filter.borrow().clone().to_value(),
+        "preset" => self.preset.borrow().clone().to_value(),
         "viewed" => self.viewed.borrow().clone().to_value(),
+        "order" => self.order.borrow().clone().to_value(),
+        "position" => self.position.get().to_value(),
+        "show-thumbnails" => self.show_thumbnails.get().to_value(),
+        "error-time" => self.error_time.get().to_value(),
+        "error" => self.error.borrow().clone().to_value(),
+        "authenticated" => self.credentials.borrow().is_some().to_value(),
         "state" => self.state.borrow().clone().to_value(),
         "unread" => (self
           .obj()
@@ -373,7 +1100,45 @@ mod imp {
           .items
           .borrow()
           .iter()
-          .filter(|i| i.is_newer(*self.obj().get_viewed()))
+          .filter(|i| self.obj().is_unread(i) && self.obj().matches_filter(i))
+          .count() as i32)
+          .to_value(),
+        "total-unread" => (self
+          .obj()
+          .imp()
+          .items
+          .borrow()
+          .iter()
+          .filter(|i| self.obj().is_unread(i))
+          .count() as i32)
+          .to_value(),
+        "muted" => (self
+          .obj()
+          .imp()
+          .items
+          .borrow()
+          .iter()
+          .filter(|i| i.is_muted())
+          .count() as i32)
+          .to_value(),
+        "highlighted" => (self
+          .obj()
+          .imp()
+          .items
+          .borrow()
+          .iter()
+          .filter(|i| !i.get_highlights().is_empty())
+          .count() as i32)
+          .to_value(),
+        "important" => (self
+          .obj()
+          .imp()
+          .items
+          .borrow()
+          .iter()
+          .filter(|i| {
+            i.is_important() && self.obj().is_unread(i) && self.obj().matches_filter(i)
+          })
           .count() as i32)
           .to_value(),
         _ => unimplemented!(),
@@ -381,3 +1146,133 @@ mod imp {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{HighlightStyle, RuleKind};
+
+  #[test]
+  fn unread_respects_filter() {
+    let feed = Feed::new(&"Feed".into(), &"".into(), &"rust".into(), 0);
+    feed.imp().items.replace(vec![
+      FeedItem::new(
+        &"Rust 1.65 Released".into(),
+        &"https://example.com/1".into(),
+        10,
+      ),
+      FeedItem::new(
+        &"GTK 4.8 Released".into(),
+        &"https://example.com/2".into(),
+        10,
+      ),
+    ]);
+
+    assert_eq!(feed.get_unread(), 1);
+    assert_eq!(feed.get_total_unread(), 2);
+
+    feed.set_property("filter", "");
+    assert_eq!(feed.get_unread(), 2);
+  }
+
+  #[test]
+  fn important_respects_filter() {
+    let feed = Feed::new(&"Feed".into(), &"".into(), &"rust".into(), 0);
+    feed.imp().items.replace(vec![
+      FeedItem::new(
+        &"Rust 1.65 Released".into(),
+        &"https://example.com/1".into(),
+        10,
+      ),
+      FeedItem::new(
+        &"GTK 4.8 Released".into(),
+        &"https://example.com/2".into(),
+        10,
+      ),
+    ]);
+
+    let rule = HighlightRule {
+      kind: RuleKind::Keyword,
+      pattern: "released".into(),
+      style: HighlightStyle::Bold,
+      important: true,
+    };
+    feed.set_highlight_rules(&[rule]);
+    assert_eq!(feed.get_important(), 1);
+
+    let notified = std::rc::Rc::new(Cell::new(false));
+    feed.connect_notify_local(
+      Some("important"),
+      glib::clone!(@strong notified => move |_, _| notified.set(true)),
+    );
+
+    feed.set_property("filter", "");
+    assert!(notified.get());
+    assert_eq!(feed.get_important(), 2);
+  }
+
+  #[test]
+  fn first_seen_dates() {
+    let path = format!("{}/tests/fixtures/rss20.xml", env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(path).unwrap();
+
+    let load = |feed: &Feed| {
+      let content = parser::parse_feed(&bytes).unwrap();
+      feed.load_content(FetchedContent::Feed(content)).unwrap();
+      feed.assign_first_seen_dates();
+      feed
+        .get_items()
+        .iter()
+        .map(|item| (item.get_url().clone(), item.get_date()))
+        .collect::<Vec<_>>()
+    };
+
+    // Only the undated item gets the current time.
+    let feed = Feed::new(&"Feed".into(), &"".into(), &"".into(), 0);
+    let dates = load(&feed);
+    let first_seen = dates[1].1;
+    assert!(first_seen > 0);
+    assert_eq!(
+      feed.to_stored().first_seen,
+      HashMap::from([(dates[1].0.clone(), first_seen)])
+    );
+
+    // Restore the feed with an older timestamp and a stale key. The timestamp is kept
+    // across refreshes, the stale key is dropped.
+    let mut stored = feed.to_stored();
+    stored.first_seen = HashMap::from([
+      ("https://example.com/rss/undated".into(), 1000),
+      ("https://example.com/rss/removed".into(), 2000),
+    ]);
+
+    let feed = Feed::from_stored(&stored, None);
+    let expected = vec![
+      dates[0].clone(),
+      (dates[1].0.clone(), 1000),
+      dates[2].clone(),
+    ];
+    assert_eq!(load(&feed), expected);
+    assert_eq!(load(&feed), expected);
+
+    let stored = feed.to_stored();
+    assert_eq!(
+      stored.first_seen,
+      HashMap::from([("https://example.com/rss/undated".into(), 1000)])
+    );
+
+    // The timestamps survive the conversion to JSON which is stored in the settings.
+    let json = serde_json::to_string(&stored).unwrap();
+    let feed = Feed::from_stored(&serde_json::from_str(&json).unwrap(), None);
+    assert_eq!(load(&feed)[1].1, 1000);
+  }
+
+  #[test]
+  fn filter_with_alternatives() {
+    let feed = Feed::new(&"Feed".into(), &"".into(), &"rust OR gtk".into(), 0);
+    let item = |title: &str| FeedItem::new(&title.into(), &"".into(), 0);
+
+    assert!(feed.matches_filter(&item("Rust 1.65 Released")));
+    assert!(feed.matches_filter(&item("GTK 4.8 Released")));
+    assert!(!feed.matches_filter(&item("Wayland 1.21 Released")));
+  }
+}
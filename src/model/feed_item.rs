@@ -11,13 +11,107 @@
 
 use gtk::{glib, prelude::*, subclass::prelude::*};
 use once_cell::sync::Lazy;
-use std::cell::{Ref, RefCell};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, Ref, RefCell};
+
+use crate::model::HighlightStyle;
+
+// ---------------------------------------------------------------------------------------
+// The StoredItem is used for storing starred feed items in the settings. Starred items
+// are kept independently of their source feed, so they contain all information required
+// to show them in the user interface. An array of such structs is converted from and to
+// JSON using serde and stored under the GSettings key
+// /io/github/schneegans/BingeRSS/starred-items.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StoredItem {
+  // The title of the item.
+  pub title: String,
+
+  // The URL which is opened when the item is activated.
+  pub url: String,
+
+  // The unix timestamp in seconds at which the item was published.
+  pub date: i64,
+
+  // Where the publication date came from. Items stored by older versions lack this and
+  // are considered to have a real publication date.
+  #[serde(default)]
+  pub date_source: DateSource,
+
+  // The title of the feed this item was published in.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub feed_title: String,
+
+  // The summary, the content, and the author of the item. These may be empty.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub summary: String,
+
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub content: String,
+
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub author: String,
+
+  // The enclosure of the item, for instance the audio file of a podcast episode. The URL
+  // is empty if the item has no enclosure. Unknown sizes and durations are zero.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub enclosure_url: String,
+
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub enclosure_type: String,
+
+  #[serde(default)]
+  pub enclosure_length: u64,
+
+  #[serde(default)]
+  pub enclosure_duration: u64,
+
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub enclosure_thumbnail: String,
+
+  // The URL of an image representing the item.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub thumbnail: String,
+
+  // The categories of the item.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub categories: Vec<String>,
+}
 
 // ---------------------------------------------------------------------------------------
-// A FeedItem is a very simple GObject with two string properties (a title and an URL) and
-// an int64 property. The latter stores a unix timestamp for the time at which the feed
-// was last viewed in the user interface. This object is used to populate the feed item
-// lists in the user interface.
+// The publication date of a FeedItem can come from different sources. Usually, feeds
+// provide a publication date for each item. If this is missing, the date at which the
+// item was last updated is used. If this is missing as well, the time at which BingeRSS
+// saw the item for the first time is used. Such dates are only estimates and should be
+// marked as such in the user interface.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[serde(rename_all = "kebab-case")]
+#[enum_type(name = "DateSource")]
+pub enum DateSource {
+  Published,
+  Updated,
+  FirstSeen,
+}
+
+impl Default for DateSource {
+  fn default() -> Self {
+    DateSource::Published
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// A FeedItem is a very simple GObject with some string properties (a title, an URL, the
+// title of the feed it belongs to, and the summary, content, and author of the item),
+// boolean properties storing whether the item has been starred, read, or muted, and an
+// int64 property. The latter stores the unix timestamp at which the item was published.
+// The date-source property stores where this timestamp came from. Podcast episodes and
+// other media items additionally have an enclosure: the enclosure-* properties store its
+// URL, MIME type, size in bytes, duration in seconds, and the URL of a thumbnail image.
+// The thumbnail property stores the URL of an image which represents the item. If the
+// same story is published in other feeds as well, the titles of these feeds can be
+// retrieved with get_also_in(). The categories of the item and the styles of all
+// matching highlight rules are available via get_categories() and get_highlights(). This
+// object is used to populate the feed item lists in the user interface.
 glib::wrapper! {
   pub struct FeedItem(ObjectSubclass<imp::FeedItem>);
 }
@@ -33,8 +127,53 @@ impl FeedItem {
       .build()
   }
 
+  // Creates a FeedItem from the information stored in the settings.
+  pub fn from_stored(item: &StoredItem) -> Self {
+    let this: Self = glib::Object::builder()
+      .property("title", &item.title)
+      .property("url", &item.url)
+      .property("date", item.date)
+      .property("date-source", item.date_source)
+      .property("feed-title", &item.feed_title)
+      .property("summary", &item.summary)
+      .property("content", &item.content)
+      .property("author", &item.author)
+      .property("enclosure-url", &item.enclosure_url)
+      .property("enclosure-type", &item.enclosure_type)
+      .property("enclosure-length", item.enclosure_length)
+      .property("enclosure-duration", item.enclosure_duration)
+      .property("enclosure-thumbnail", &item.enclosure_thumbnail)
+      .property("thumbnail", &item.thumbnail)
+      .build();
+
+    this.set_categories(item.categories.clone());
+    this
+  }
+
   // ---------------------------------------------------------------------- public methods
 
+  // Returns a StoredItem containing all information required to restore this FeedItem
+  // later.
+  pub fn to_stored(&self) -> StoredItem {
+    StoredItem {
+      title: self.get_title().clone(),
+      url: self.get_url().clone(),
+      date: self.get_date(),
+      date_source: self.get_date_source(),
+      feed_title: self.get_feed_title().clone(),
+      summary: self.get_summary().clone(),
+      content: self.get_content().clone(),
+      author: self.get_author().clone(),
+      enclosure_url: self.get_enclosure_url().clone(),
+      enclosure_type: self.get_enclosure_type().clone(),
+      enclosure_length: self.get_enclosure_length(),
+      enclosure_duration: self.get_enclosure_duration(),
+      enclosure_thumbnail: self.get_enclosure_thumbnail().clone(),
+      thumbnail: self.get_thumbnail().clone(),
+      categories: self.get_categories().clone(),
+    }
+  }
+
   // Get the title of the FeedItem. This should be shown to the user.
   pub fn get_title(&self) -> Ref<String> {
     self.imp().title.borrow()
@@ -45,6 +184,130 @@ impl FeedItem {
     self.imp().url.borrow()
   }
 
+  // Get the title of the feed this item was published in.
+  pub fn get_feed_title(&self) -> Ref<String> {
+    self.imp().feed_title.borrow()
+  }
+
+  // Get the summary of the FeedItem. This may be empty.
+  pub fn get_summary(&self) -> Ref<String> {
+    self.imp().summary.borrow()
+  }
+
+  // Get the full content of the FeedItem. This may be empty and may contain HTML.
+  pub fn get_content(&self) -> Ref<String> {
+    self.imp().content.borrow()
+  }
+
+  // Get the name of the author(s) of the FeedItem. This may be empty.
+  pub fn get_author(&self) -> Ref<String> {
+    self.imp().author.borrow()
+  }
+
+  // Get the unix timestamp at which the FeedItem was published.
+  pub fn get_date(&self) -> i64 {
+    *self.imp().date.borrow()
+  }
+
+  // Get the source of the publication date of the FeedItem.
+  pub fn get_date_source(&self) -> DateSource {
+    *self.imp().date_source.borrow()
+  }
+
+  // Get the URL of the item's enclosure. This is empty if the item has no enclosure.
+  pub fn get_enclosure_url(&self) -> Ref<String> {
+    self.imp().enclosure_url.borrow()
+  }
+
+  // Get the MIME type of the item's enclosure, for instance "audio/mpeg". This may be
+  // empty.
+  pub fn get_enclosure_type(&self) -> Ref<String> {
+    self.imp().enclosure_type.borrow()
+  }
+
+  // Get the size of the item's enclosure in bytes. This is zero if unknown.
+  pub fn get_enclosure_length(&self) -> u64 {
+    self.imp().enclosure_length.get()
+  }
+
+  // Get the duration of the item's enclosure in seconds. This is zero if unknown.
+  pub fn get_enclosure_duration(&self) -> u64 {
+    self.imp().enclosure_duration.get()
+  }
+
+  // Get the URL of a thumbnail image of the item's enclosure. This may be empty.
+  pub fn get_enclosure_thumbnail(&self) -> Ref<String> {
+    self.imp().enclosure_thumbnail.borrow()
+  }
+
+  // Returns true if the item has an enclosure, for instance a podcast episode.
+  pub fn has_enclosure(&self) -> bool {
+    !self.imp().enclosure_url.borrow().is_empty()
+  }
+
+  // Get the URL of an image representing the item. This may be empty.
+  pub fn get_thumbnail(&self) -> Ref<String> {
+    self.imp().thumbnail.borrow()
+  }
+
+  // Returns true if the user starred this item.
+  pub fn is_starred(&self) -> bool {
+    self.imp().starred.get()
+  }
+
+  // Returns true if the user has read this item or any of its duplicates.
+  pub fn is_read(&self) -> bool {
+    self.imp().read.get()
+  }
+
+  // Returns true if the item matches any of the global mute rules. Muted items are hidden
+  // in all views and not counted as unread.
+  pub fn is_muted(&self) -> bool {
+    self.imp().muted.get()
+  }
+
+  // Get the categories of the item. This may be empty.
+  pub fn get_categories(&self) -> Ref<Vec<String>> {
+    self.imp().categories.borrow()
+  }
+
+  // Sets the categories of the item.
+  pub fn set_categories(&self, categories: Vec<String>) {
+    self.imp().categories.replace(categories);
+  }
+
+  // Get the styles of all highlight rules matching this item. This may be empty.
+  pub fn get_highlights(&self) -> Ref<Vec<HighlightStyle>> {
+    self.imp().highlights.borrow()
+  }
+
+  // Returns true if the item matches a highlight rule which pins items to the top.
+  pub fn is_pinned(&self) -> bool {
+    self.get_highlights().contains(&HighlightStyle::Pinned)
+  }
+
+  // Returns true if the item matches a highlight rule which marks items as important.
+  pub fn is_important(&self) -> bool {
+    self.imp().important.get()
+  }
+
+  // Sets the styles of all highlight rules matching this item and whether any of them
+  // marks the item as important.
+  pub fn set_highlights(&self, styles: Vec<HighlightStyle>, important: bool) {
+    self.imp().highlights.replace(styles);
+    self.imp().important.set(important);
+  }
+
+  // Get the titles of other feeds which contain a duplicate of this item.
+  pub fn get_also_in(&self) -> Ref<Vec<String>> {
+    self.imp().also_in.borrow()
+  }
+
+  // Sets the titles of other feeds which contain a duplicate of this item.
+  pub fn set_also_in(&self, feed_titles: Vec<String>) {
+    self.imp().also_in.replace(feed_titles);
+  }
+
   // Returns true if the feed item item was published after the given date.
   pub fn is_newer(&self, date: i64) -> bool {
     self.imp().date.borrow().gt(&date)
@@ -62,6 +325,24 @@ mod imp {
     pub title: RefCell<String>,
     pub url: RefCell<String>,
     pub date: RefCell<i64>,
+    pub date_source: RefCell<DateSource>,
+    pub feed_title: RefCell<String>,
+    pub summary: RefCell<String>,
+    pub content: RefCell<String>,
+    pub author: RefCell<String>,
+    pub enclosure_url: RefCell<String>,
+    pub enclosure_type: RefCell<String>,
+    pub enclosure_length: Cell<u64>,
+    pub enclosure_duration: Cell<u64>,
+    pub enclosure_thumbnail: RefCell<String>,
+    pub thumbnail: RefCell<String>,
+    pub starred: Cell<bool>,
+    pub read: Cell<bool>,
+    pub muted: Cell<bool>,
+    pub also_in: RefCell<Vec<String>>,
+    pub categories: RefCell<Vec<String>>,
+    pub highlights: RefCell<Vec<HighlightStyle>>,
+    pub important: Cell<bool>,
   }
 
   #[glib::object_subclass]
@@ -77,6 +358,24 @@ mod imp {
           glib::ParamSpecString::builder("title").build(),
           glib::ParamSpecString::builder("url").build(),
           glib::ParamSpecInt64::builder("date").build(),
+          glib::ParamSpecEnum::builder::<DateSource>(
+            "date-source",
+            DateSource::default(),
+          )
+          .build(),
+          glib::ParamSpecString::builder("feed-title").build(),
+          glib::ParamSpecString::builder("summary").build(),
+          glib::ParamSpecString::builder("content").build(),
+          glib::ParamSpecString::builder("author").build(),
+          glib::ParamSpecString::builder("enclosure-url").build(),
+          glib::ParamSpecString::builder("enclosure-type").build(),
+          glib::ParamSpecUInt64::builder("enclosure-length").build(),
+          glib::ParamSpecUInt64::builder("enclosure-duration").build(),
+          glib::ParamSpecString::builder("enclosure-thumbnail").build(),
+          glib::ParamSpecString::builder("thumbnail").build(),
+          glib::ParamSpecBoolean::builder("starred").build(),
+          glib::ParamSpecBoolean::builder("read").build(),
+          glib::ParamSpecBoolean::builder("muted").build(),
         ]
       });
       PROPERTIES.as_ref()
@@ -103,6 +402,94 @@ mod imp {
             .date
             .replace(value.get().expect("The value needs to be of type `i64`."));
         }
+        "date-source" => {
+          self.date_source.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `DateSource`."),
+          );
+        }
+        "feed-title" => {
+          self.feed_title.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "summary" => {
+          self.summary.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "content" => {
+          self.content.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "author" => {
+          self.author.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "enclosure-url" => {
+          self.enclosure_url.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "enclosure-type" => {
+          self.enclosure_type.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "enclosure-thumbnail" => {
+          self.enclosure_thumbnail.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "thumbnail" => {
+          self.thumbnail.replace(
+            value
+              .get()
+              .expect("The value needs to be of type `String`."),
+          );
+        }
+        "enclosure-length" => {
+          self
+            .enclosure_length
+            .set(value.get().expect("The value needs to be of type `u64`."));
+        }
+        "enclosure-duration" => {
+          self
+            .enclosure_duration
+            .set(value.get().expect("The value needs to be of type `u64`."));
+        }
+        "starred" => {
+          self
+            .starred
+            .set(value.get().expect("The value needs to be of type `bool`."));
+        }
+        "read" => {
+          self
+            .read
+            .set(value.get().expect("The value needs to be of type `bool`."));
+        }
+        "muted" => {
+          self
+            .muted
+            .set(value.get().expect("The value needs to be of type `bool`."));
+        }
         _ => unimplemented!(),
       }
     }
@@ -112,8 +499,64 @@ mod imp {
         "title" => self.title.borrow().clone().to_value(),
         "url" => self.url.borrow().clone().to_value(),
         "date" => self.date.borrow().clone().to_value(),
+        "date-source" => self.date_source.borrow().clone().to_value(),
+        "feed-title" => self.feed_title.borrow().clone().to_value(),
+        "summary" => self.summary.borrow().clone().to_value(),
+        "content" => self.content.borrow().clone().to_value(),
+        "author" => self.author.borrow().clone().to_value(),
+        "enclosure-url" => self.enclosure_url.borrow().clone().to_value(),
+        "enclosure-type" => self.enclosure_type.borrow().clone().to_value(),
+        "enclosure-length" => self.enclosure_length.get().to_value(),
+        "enclosure-duration" => self.enclosure_duration.get().to_value(),
+        "enclosure-thumbnail" => self.enclosure_thumbnail.borrow().clone().to_value(),
+        "thumbnail" => self.thumbnail.borrow().clone().to_value(),
+        "starred" => self.starred.get().to_value(),
+        "read" => self.read.get().to_value(),
+        "muted" => self.muted.get().to_value(),
         _ => unimplemented!(),
       }
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stored_item() {
+    let item: FeedItem = glib::Object::builder()
+      .property("title", "Episode 42")
+      .property("url", "https://example.com/42")
+      .property("date", 1668600000i64)
+      .property("date-source", DateSource::FirstSeen)
+      .property("feed-title", "Podcast")
+      .property("author", "Jane")
+      .property("enclosure-url", "https://example.com/42.mp3")
+      .property("enclosure-type", "audio/mpeg")
+      .property("enclosure-length", 1024u64)
+      .property("enclosure-duration", 3723u64)
+      .build();
+    item.set_categories(vec!["Science".into()]);
+
+    let json = serde_json::to_string(&item.to_stored()).unwrap();
+    let restored = FeedItem::from_stored(&serde_json::from_str(&json).unwrap());
+
+    assert_eq!(*restored.get_title(), "Episode 42");
+    assert_eq!(restored.get_date_source(), DateSource::FirstSeen);
+    assert_eq!(*restored.get_author(), "Jane");
+    assert_eq!(*restored.get_enclosure_url(), "https://example.com/42.mp3");
+    assert_eq!(*restored.get_enclosure_type(), "audio/mpeg");
+    assert_eq!(restored.get_enclosure_length(), 1024);
+    assert_eq!(restored.get_enclosure_duration(), 3723);
+    assert_eq!(*restored.get_categories(), vec![String::from("Science")]);
+
+    // Items stored by older versions only contain a few fields.
+    let old: StoredItem =
+      serde_json::from_str(r#"{"title":"Old","url":"https://example.com","date":1}"#)
+        .unwrap();
+    let restored = FeedItem::from_stored(&old);
+    assert_eq!(restored.get_date_source(), DateSource::Published);
+    assert!(!restored.has_enclosure());
+  }
+}
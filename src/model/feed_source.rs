@@ -0,0 +1,350 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use futures::future::Either;
+use gtk::{gio, glib, prelude::*};
+use std::{error::Error, ffi::OsStr, path::PathBuf};
+
+use crate::model::{http_client, parser, Credentials, TlsConfig};
+
+// Commands which do not finish within this number of seconds are killed.
+const COMMAND_TIMEOUT: u32 = 30;
+
+// The content of a feed and the data of its icon, if any.
+pub type FetchResult =
+  Result<(FetchedContent, Option<glib::Bytes>), Box<dyn Error + Send + Sync>>;
+
+// ---------------------------------------------------------------------------------------
+// This error is returned by FeedSource::fetch() if the server responds with 401
+// Unauthorized. Use FeedSource::is_unauthorized() to check for it.
+#[derive(Debug)]
+struct Unauthorized;
+
+impl std::fmt::Display for Unauthorized {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "The server requires authentication.")
+  }
+}
+
+impl Error for Unauthorized {}
+
+// ---------------------------------------------------------------------------------------
+// Feeds are parsed right away. Web pages are returned as HTML text, the items are
+// extracted from them later using the ScrapeConfig of the Feed.
+#[derive(Debug)]
+pub enum FetchedContent {
+  Feed(feed_rs::model::Feed),
+  Page(String),
+}
+
+// ---------------------------------------------------------------------------------------
+// The URL of a Feed determines where its content is loaded from. Web URLs are downloaded
+// with reqwest, file:// URLs and absolute paths are read from the local file system. If
+// the URL starts with exec:, the rest of it is executed as a command line and the feed
+// is read from the command's standard output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+  // The feed is downloaded from the given URL.
+  Web(String),
+
+  // The feed is read from the given local file.
+  File(PathBuf),
+
+  // The feed is printed by the given command line.
+  Command(String),
+
+  // The HTML page at the given URL is downloaded. This is never returned by from_url(),
+  // it is used for Feeds with a ScrapeConfig.
+  Page(String),
+}
+
+impl FeedSource {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Determines the source of a feed from the URL entered by the user.
+  pub fn from_url(url: &str) -> Self {
+    let url = url.trim();
+
+    if let Some(command) = url.strip_prefix("exec:") {
+      return FeedSource::Command(command.trim().to_string());
+    }
+
+    if url.starts_with('/') {
+      return FeedSource::File(PathBuf::from(url));
+    }
+
+    match url::Url::parse(url) {
+      Ok(parsed) if parsed.scheme() == "file" => match parsed.to_file_path() {
+        Ok(path) => FeedSource::File(path),
+        Err(_) => FeedSource::Web(url.to_string()),
+      },
+      _ => FeedSource::Web(url.to_string()),
+    }
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns true if the given URL would be executed as a command. Such URLs are only
+  // accepted if the user typed them into the URL entry of a feed. They must not be taken
+  // from feed:// URIs, opened files, OPML subscription lists, or D-Bus clients, as this
+  // would allow others to run arbitrary commands.
+  pub fn is_command(url: &str) -> bool {
+    matches!(Self::from_url(url), FeedSource::Command(_))
+  }
+
+  // Returns the path of the feed file if this is a local source.
+  pub fn get_path(&self) -> Option<&PathBuf> {
+    match self {
+      FeedSource::File(path) => Some(path),
+      _ => None,
+    }
+  }
+
+  // Returns true if the feed has to be downloaded from the internet.
+  pub fn is_remote(&self) -> bool {
+    matches!(self, FeedSource::Web(_) | FeedSource::Page(_))
+  }
+
+  // Loads and parses the feed. For web feeds, the favicon of the website is downloaded as
+  // well. If credentials are given, they are sent with the request for the feed. The
+  // TlsConfig is used for the connection to the server of the feed. This has to be
+  // awaited on the glib main context: downloads and files are handled on the tokio
+  // runtime, commands are executed with a gio::Subprocess.
+  pub async fn fetch(
+    self,
+    credentials: Option<Credentials>,
+    tls: TlsConfig,
+  ) -> FetchResult {
+    match self {
+      FeedSource::Command(command) => Self::run_command(&command).await,
+      source => {
+        let future = source.fetch_in_background(credentials, tls);
+        crate::RUNTIME.spawn(future).await?
+      }
+    }
+  }
+
+  // Returns true if the given error of fetch() was caused by a missing feed file.
+  pub fn is_missing_file(err: &(dyn Error + Send + Sync)) -> bool {
+    err
+      .downcast_ref::<std::io::Error>()
+      .map_or(false, |err| err.kind() == std::io::ErrorKind::NotFound)
+  }
+
+  // Returns true if the given error of fetch() was caused by a server which requires
+  // authentication. This is also the case if the sent credentials were rejected.
+  pub fn is_unauthorized(err: &(dyn Error + Send + Sync)) -> bool {
+    err.downcast_ref::<Unauthorized>().is_some()
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Downloads or reads the feed. This is executed on the tokio runtime.
+  async fn fetch_in_background(
+    self,
+    credentials: Option<Credentials>,
+    tls: TlsConfig,
+  ) -> FetchResult {
+    match self {
+      FeedSource::Web(url) => {
+        // Download from the URL and parse the feed information.
+        let bytes = Self::get(&url, &credentials, &tls).await?.bytes().await?;
+        let content = parser::parse_feed(&bytes[..])?;
+
+        // Try to download the favicon from the feed.
+        let url = url::Url::parse(&content.links[0].href);
+        let icon_url = url.as_ref().unwrap().scheme().to_string()
+          + &String::from("://")
+          + &url.as_ref().unwrap().host().unwrap().to_string()
+          + &String::from("/favicon.ico");
+
+        let bytes = http_client()?.get(icon_url).send().await?.bytes().await?;
+        let image = Some(glib::Bytes::from(&bytes.to_vec()));
+
+        Ok((FetchedContent::Feed(content), image))
+      }
+      FeedSource::File(path) => {
+        // Local files have no icon.
+        let bytes = std::fs::read(&path)?;
+        Ok((FetchedContent::Feed(parser::parse_feed(&bytes[..])?), None))
+      }
+      FeedSource::Page(url) => {
+        let response = Self::get(&url, &credentials, &tls).await?;
+        let html = response.error_for_status()?.text().await?;
+
+        // Web pages are not required to have a favicon, so the page is usable without.
+        let image = match url::Url::parse(&url)?.join("/favicon.ico") {
+          Ok(icon_url) => match http_client()?.get(icon_url).send().await {
+            Ok(response) => response.bytes().await.ok(),
+            Err(_) => None,
+          },
+          Err(_) => None,
+        };
+        let image = image.map(|bytes| glib::Bytes::from(&bytes.to_vec()));
+
+        Ok((FetchedContent::Page(html), image))
+      }
+      FeedSource::Command(_) => Err("Commands have to be run on the main context".into()),
+    }
+  }
+
+  // Sends a GET request with the given credentials and TlsConfig. The favicons are
+  // requested without both, as they are usually public. If the server responds with 401
+  // Unauthorized, an Unauthorized error is returned. reqwest removes the Authorization
+  // header on redirects to other hosts, but not custom headers; hence, such redirects
+  // are refused if there are any.
+  async fn get(
+    url: &str,
+    credentials: &Option<Credentials>,
+    tls: &TlsConfig,
+  ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+    let same_host_only = credentials
+      .as_ref()
+      .map_or(false, |credentials| !credentials.headers.is_empty());
+
+    let request = tls.build_client(url, same_host_only)?.get(url);
+    let request = match credentials {
+      Some(credentials) => credentials.apply(request),
+      None => request,
+    };
+
+    let response = request.send().await?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+      return Err(Box::new(Unauthorized));
+    }
+
+    Ok(response)
+  }
+
+  // Executes the given command line and parses its standard output. The command line is
+  // split into arguments like a shell would do it, but no shell is involved. If the
+  // command fails, the returned error contains its exit status and its standard error
+  // output. If it does not finish in time, it is killed.
+  async fn run_command(command: &str) -> FetchResult {
+    let args = glib::shell_parse_argv(command)?;
+    let args: Vec<&OsStr> = args.iter().map(|arg| arg.as_os_str()).collect();
+
+    let process = gio::Subprocess::newv(
+      &args,
+      gio::SubprocessFlags::STDOUT_PIPE | gio::SubprocessFlags::STDERR_PIPE,
+    )?;
+
+    let output = process.communicate_future(None);
+    let timeout = glib::timeout_future_seconds(COMMAND_TIMEOUT);
+
+    let (stdout, stderr) = match futures::future::select(output, timeout).await {
+      Either::Left((output, _)) => output?,
+      Either::Right(_) => {
+        process.force_exit();
+        return Err(
+          format!(
+            "The command did not finish within {} seconds.",
+            COMMAND_TIMEOUT
+          )
+          .into(),
+        );
+      }
+    };
+
+    if !process.is_successful() {
+      let stderr = stderr.map_or(String::new(), |bytes| {
+        String::from_utf8_lossy(&bytes).trim().to_string()
+      });
+
+      let reason = if process.has_exited() {
+        format!("The command exited with status {}.", process.exit_status())
+      } else {
+        String::from("The command was terminated.")
+      };
+
+      return Err(format!("{}\n{}", reason, stderr).trim().to_string().into());
+    }
+
+    let stdout = stdout.unwrap_or_else(|| glib::Bytes::from_static(b""));
+
+    Ok((FetchedContent::Feed(parser::parse_feed(&stdout)?), None))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Runs the given future to completion on a new glib main context.
+  fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let ctx = glib::MainContext::new();
+    ctx
+      .with_thread_default(|| ctx.block_on(future))
+      .expect("context can be acquired")
+  }
+
+  #[test]
+  fn from_url() {
+    assert_eq!(
+      FeedSource::from_url("file:///tmp/release%20notes.xml"),
+      FeedSource::File(PathBuf::from("/tmp/release notes.xml"))
+    );
+    assert_eq!(
+      FeedSource::from_url("/tmp/feed.xml"),
+      FeedSource::File(PathBuf::from("/tmp/feed.xml"))
+    );
+    assert_eq!(
+      FeedSource::from_url("https://example.com/feed.xml"),
+      FeedSource::Web("https://example.com/feed.xml".into())
+    );
+    assert_eq!(
+      FeedSource::from_url("exec: cat 'release notes.xml'"),
+      FeedSource::Command("cat 'release notes.xml'".into())
+    );
+
+    assert!(FeedSource::is_command(" exec:cat feed.xml"));
+    assert!(!FeedSource::is_command("https://example.com/exec:cat"));
+
+    assert!(FeedSource::from_url("https://example.com/feed.xml").is_remote());
+    assert!(!FeedSource::from_url("/tmp/feed.xml").is_remote());
+  }
+
+  #[test]
+  fn fetch_file() {
+    let path = format!("{}/tests/fixtures/atom.xml", env!("CARGO_MANIFEST_DIR"));
+    let result = block_on(FeedSource::from_url(&path).fetch(None, TlsConfig::default()));
+    let (content, image) = result.expect("fixture can be loaded");
+
+    match content {
+      FetchedContent::Feed(content) => assert!(!content.entries.is_empty()),
+      FetchedContent::Page(_) => panic!("fixture is a feed"),
+    }
+    assert!(image.is_none());
+
+    let path = format!("{}/tests/fixtures/missing.xml", env!("CARGO_MANIFEST_DIR"));
+    let result = block_on(FeedSource::from_url(&path).fetch(None, TlsConfig::default()));
+    assert!(FeedSource::is_missing_file(result.unwrap_err().as_ref()));
+  }
+
+  #[test]
+  fn fetch_command() {
+    let fixtures = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+
+    let command = format!("exec:cat {}/rss20.xml", fixtures);
+    let result =
+      block_on(FeedSource::from_url(&command).fetch(None, TlsConfig::default()));
+    assert!(matches!(
+      result.expect("command succeeds").0,
+      FetchedContent::Feed(content) if !content.entries.is_empty()
+    ));
+
+    let command = "exec:sh -c 'echo broken >&2; exit 3'";
+    let err = block_on(FeedSource::from_url(command).fetch(None, TlsConfig::default()))
+      .unwrap_err();
+    assert_eq!(err.to_string(), "The command exited with status 3.\nbroken");
+  }
+}
@@ -0,0 +1,68 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use serde::{Deserialize, Serialize};
+
+// ---------------------------------------------------------------------------------------
+// FilterPresets are named filters which can be used by multiple feeds. Feeds reference a
+// preset by its name; whenever the filter of a preset changes in the preferences, the
+// filter of all feeds referencing it is changed as well. If the filter of a single feed
+// is edited, the feed stops using the preset. An array of such structs is converted from
+// and to JSON using serde and stored under the GSettings key
+// /io/github/schneegans/BingeRSS/filter-presets.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FilterPreset {
+  // The user-defined name of the preset. This is unique amongst all presets.
+  pub name: String,
+
+  // The filter which is applied to all feeds using this preset.
+  pub filter: String,
+}
+
+impl FilterPreset {
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns the filter of the preset with the given name. None is returned if the name is
+  // empty or if there is no such preset; in this case, a feed uses its own filter.
+  pub fn get_filter<'a>(presets: &'a [FilterPreset], name: &str) -> Option<&'a str> {
+    if name.is_empty() {
+      return None;
+    }
+
+    presets
+      .iter()
+      .find(|preset| preset.name == name)
+      .map(|preset| preset.filter.as_str())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn presets() -> Vec<FilterPreset> {
+    serde_json::from_str(
+      r#"[{"name":"Releases","filter":"release"},{"name":"","filter":"empty"}]"#,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn get_filter() {
+    let presets = presets();
+    assert_eq!(
+      FilterPreset::get_filter(&presets, "Releases"),
+      Some("release")
+    );
+    assert_eq!(FilterPreset::get_filter(&presets, "Other"), None);
+    assert_eq!(FilterPreset::get_filter(&presets, ""), None);
+  }
+}
@@ -0,0 +1,114 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{RuleKind, RuleMatcher};
+
+// ---------------------------------------------------------------------------------------
+// Matching items are emphasized in either of these styles.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HighlightStyle {
+  // The title of the item is drawn in the accent color.
+  Accent,
+
+  // The title of the item is drawn in bold.
+  Bold,
+
+  // The item is shown above all other items of the feed.
+  Pinned,
+}
+
+impl HighlightStyle {
+  // All styles in the order in which they are shown in the user interface.
+  pub const ALL: [HighlightStyle; 3] = [
+    HighlightStyle::Accent,
+    HighlightStyle::Bold,
+    HighlightStyle::Pinned,
+  ];
+
+  // Returns a human-readable name of the style which can be shown in the user interface.
+  pub fn get_label(&self) -> &'static str {
+    match self {
+      HighlightStyle::Accent => "Accent Color",
+      HighlightStyle::Bold => "Bold",
+      HighlightStyle::Pinned => "Pinned to Top",
+    }
+  }
+
+  // Returns the CSS class which is added to the rows of matching items.
+  pub fn get_css_class(&self) -> &'static str {
+    match self {
+      HighlightStyle::Accent => "item-accent",
+      HighlightStyle::Bold => "item-bold",
+      HighlightStyle::Pinned => "item-pinned",
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// HighlightRules are used to emphasize items in all feeds. An array of such structs is
+// converted from and to JSON using serde and stored under the GSettings key
+// /io/github/schneegans/BingeRSS/highlight-rules. All comparisons are case-insensitive.
+// Unread items matching an important rule are counted separately for each feed.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HighlightRule {
+  // The part of the item the pattern is matched against.
+  pub kind: RuleKind,
+
+  // The keyword, regular expression, author, or category.
+  pub pattern: String,
+
+  // The way matching items are emphasized.
+  pub style: HighlightStyle,
+
+  // Whether matching items should be counted as important.
+  #[serde(default)]
+  pub important: bool,
+}
+
+impl HighlightRule {
+  // The kinds of highlight rules in the order in which they are shown in the user
+  // interface.
+  pub const KINDS: [RuleKind; 4] = [
+    RuleKind::Keyword,
+    RuleKind::Regex,
+    RuleKind::Author,
+    RuleKind::Category,
+  ];
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns a RuleMatcher which decides whether an item should be highlighted because of
+  // this rule. If the rule's pattern is a regular expression, it is compiled only once.
+  pub fn get_matcher(&self) -> RuleMatcher {
+    RuleMatcher::new(self.kind, &self.pattern)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::FeedItem;
+
+  #[test]
+  fn stored_rule() {
+    let rule: HighlightRule =
+      serde_json::from_str(r#"{"kind":"regex","pattern":"gtk \\d","style":"bold"}"#)
+        .unwrap();
+    assert_eq!(rule.kind, RuleKind::Regex);
+    assert!(!rule.important);
+
+    let item = FeedItem::new(&"GTK 4 Released".into(), &"".into(), 0);
+    assert!(rule.get_matcher().matches(&item));
+  }
+}
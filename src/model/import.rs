@@ -0,0 +1,170 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+// This module contains the functions which are used when BingeRSS is asked to open
+// feed:// URIs, feed files, or OPML subscription lists. They do not depend on the network
+// or on a display, so they can be tested in isolation.
+
+use quick_xml::events::Event;
+
+use crate::model::parser;
+
+// ---------------------------------------------------------------------------------------
+// An ImportedFeed describes a feed which was found in an OPML file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFeed {
+  // The title of the feed. This may be empty if the OPML file does not specify one.
+  pub title: String,
+
+  // The URL from which the feed can be downloaded.
+  pub url: String,
+}
+
+// ---------------------------------------------------------------------------------------
+// Converts a feed:// URI to the URL of the feed. Both the feed://example.com/rss and the
+// feed:https://example.com/rss forms are supported. In the former case, HTTPS is used.
+// Other URIs are returned unchanged.
+pub fn feed_uri_to_url(uri: &str) -> String {
+  if let Some(url) = uri.strip_prefix("feed://") {
+    format!("https://{}", url)
+  } else if let Some(url) = uri.strip_prefix("feed:") {
+    url.to_string()
+  } else {
+    uri.to_string()
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns true if the given bytes look like an OPML document. This is used to decide
+// whether an opened file should be imported or added as a single feed.
+pub fn is_opml(bytes: &[u8]) -> bool {
+  let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_lowercase();
+  head.contains("<opml")
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the URL given in the rel="self" link of the given feed document. This is the
+// URL from which a downloaded feed file was originally retrieved. None is returned if
+// the document cannot be parsed or does not contain such a link.
+pub fn get_self_link(bytes: &[u8]) -> Option<String> {
+  let feed = parser::parse_feed(bytes).ok()?;
+  feed
+    .links
+    .into_iter()
+    .find(|link| link.rel.as_deref() == Some("self"))
+    .map(|link| link.href)
+}
+
+// ---------------------------------------------------------------------------------------
+// Parses the given OPML document and returns all feeds it contains. Outlines can be
+// nested into folders; the folders themselves are ignored. The title of each feed is
+// taken from the title attribute or, if that is missing, from the text attribute.
+pub fn parse_opml(bytes: &[u8]) -> Result<Vec<ImportedFeed>, quick_xml::Error> {
+  let text = String::from_utf8_lossy(bytes);
+  let mut reader = quick_xml::Reader::from_str(&text);
+  reader.trim_text(true);
+
+  let mut feeds = vec![];
+
+  loop {
+    match reader.read_event()? {
+      Event::Start(element) | Event::Empty(element)
+        if element.name().as_ref().eq_ignore_ascii_case(b"outline") =>
+      {
+        let mut title = String::new();
+        let mut text = String::new();
+        let mut url = String::new();
+
+        for attribute in element.attributes() {
+          let attribute = attribute?;
+          let value = attribute.unescape_value()?.trim().to_string();
+
+          match attribute.key.as_ref().to_ascii_lowercase().as_slice() {
+            b"title" => title = value,
+            b"text" => text = value,
+            b"xmlurl" => url = value,
+            _ => {}
+          }
+        }
+
+        if !url.is_empty() {
+          let title = if title.is_empty() { text } else { title };
+          feeds.push(ImportedFeed { title, url });
+        }
+      }
+      Event::Eof => break,
+      _ => {}
+    }
+  }
+
+  Ok(feeds)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn load(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(path).expect("fixture exists")
+  }
+
+  #[test]
+  fn feed_uris() {
+    assert_eq!(
+      feed_uri_to_url("feed://example.com/rss"),
+      "https://example.com/rss"
+    );
+    assert_eq!(
+      feed_uri_to_url("feed:http://example.com/rss"),
+      "http://example.com/rss"
+    );
+    assert_eq!(
+      feed_uri_to_url("https://example.com/rss"),
+      "https://example.com/rss"
+    );
+  }
+
+  #[test]
+  fn opml() {
+    let bytes = load("subscriptions.opml");
+    assert!(is_opml(&bytes));
+
+    let feeds = parse_opml(&bytes).expect("fixture can be parsed");
+    assert_eq!(
+      feeds,
+      vec![
+        ImportedFeed {
+          title: "Example News".into(),
+          url: "https://example.com/news.xml".into(),
+        },
+        ImportedFeed {
+          title: "Rust & GTK".into(),
+          url: "https://example.org/rust.atom".into(),
+        },
+        ImportedFeed {
+          title: "".into(),
+          url: "https://example.net/feed".into(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn self_link() {
+    let bytes = load("atom.xml");
+    assert!(!is_opml(&bytes));
+    assert_eq!(
+      get_self_link(&bytes),
+      Some("https://example.com/atom.xml".into())
+    );
+  }
+}
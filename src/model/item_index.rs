@@ -0,0 +1,424 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{glib, prelude::*};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::model::{Feed, FeedItem};
+
+// ---------------------------------------------------------------------------------------
+// The ItemIndex stores all feed items which have ever been downloaded in an SQLite
+// database in the user's data directory. This allows searching through the titles,
+// summaries, contents, and authors of all items, even if they have been dropped from
+// their feed in the meantime. The search is backed by an FTS5 full-text index so that it
+// stays fast even with tens of thousands of items. In addition, the normalized URLs of
+// all items which have been read by the user are stored.
+#[derive(Debug)]
+pub struct ItemIndex {
+  connection: Connection,
+}
+
+impl ItemIndex {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Opens the database in the user's data directory. It will be created if it does not
+  // exist yet.
+  pub fn open() -> Result<Self, rusqlite::Error> {
+    let mut path = glib::user_data_dir();
+    path.push("binge-rss");
+
+    if let Err(err) = std::fs::create_dir_all(&path) {
+      println!(
+        "Failed to create data directory {}: {}",
+        path.display(),
+        err
+      );
+    }
+
+    path.push("items.db");
+
+    Self::from_connection(Connection::open(path)?)
+  }
+
+  // Initializes the tables of the given database connection. The items table contains
+  // the actual data, the items_fts table is an external-content FTS5 index of it which is
+  // kept in sync using triggers. The read_items table contains the normalized URLs of
+  // all read items.
+  fn from_connection(connection: Connection) -> Result<Self, rusqlite::Error> {
+    connection.execute_batch(
+      "CREATE TABLE IF NOT EXISTS items (
+         url TEXT PRIMARY KEY,
+         feed_url TEXT NOT NULL,
+         feed_title TEXT NOT NULL,
+         title TEXT NOT NULL,
+         summary TEXT NOT NULL,
+         content TEXT NOT NULL,
+         author TEXT NOT NULL,
+         date INTEGER NOT NULL
+       );
+
+       CREATE VIRTUAL TABLE IF NOT EXISTS items_fts USING fts5(
+         title, summary, content, author,
+         content='items', content_rowid='rowid'
+       );
+
+       CREATE TRIGGER IF NOT EXISTS items_insert AFTER INSERT ON items BEGIN
+         INSERT INTO items_fts(rowid, title, summary, content, author)
+         VALUES (new.rowid, new.title, new.summary, new.content, new.author);
+       END;
+
+       CREATE TRIGGER IF NOT EXISTS items_delete AFTER DELETE ON items BEGIN
+         INSERT INTO items_fts(items_fts, rowid, title, summary, content, author)
+         VALUES ('delete', old.rowid, old.title, old.summary, old.content, old.author);
+       END;
+
+       CREATE TRIGGER IF NOT EXISTS items_update AFTER UPDATE ON items BEGIN
+         INSERT INTO items_fts(items_fts, rowid, title, summary, content, author)
+         VALUES ('delete', old.rowid, old.title, old.summary, old.content, old.author);
+         INSERT INTO items_fts(rowid, title, summary, content, author)
+         VALUES (new.rowid, new.title, new.summary, new.content, new.author);
+       END;
+
+       CREATE TABLE IF NOT EXISTS read_items (
+         url TEXT PRIMARY KEY,
+         time INTEGER NOT NULL
+       );",
+    )?;
+
+    Ok(Self { connection })
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Adds all items of the given feed to the index. Items which are already in the index
+  // are updated. Items are identified by their URL.
+  pub fn add_items(&self, feed: &Feed) -> Result<(), rusqlite::Error> {
+    self.insert_items(&feed.get_url(), &feed.get_title(), &feed.get_items())
+  }
+
+  // Stores that the items with the given normalized URLs have been read.
+  pub fn mark_read(&self, urls: &[String]) -> Result<(), rusqlite::Error> {
+    let transaction = self.connection.unchecked_transaction()?;
+
+    {
+      let mut statement = transaction
+        .prepare_cached("INSERT OR IGNORE INTO read_items (url, time) VALUES (?1, ?2)")?;
+
+      let now = chrono::Utc::now().timestamp();
+      for url in urls.iter().filter(|url| !url.is_empty()) {
+        statement.execute(params![url, now])?;
+      }
+    }
+
+    transaction.commit()
+  }
+
+  // Returns true if the item with the given normalized URL has been read.
+  pub fn is_read(&self, url: &str) -> Result<bool, rusqlite::Error> {
+    let mut statement = self
+      .connection
+      .prepare_cached("SELECT EXISTS(SELECT 1 FROM read_items WHERE url = ?1)")?;
+
+    statement.query_row(params![url], |row| row.get(0))
+  }
+
+  // Searches the index for items matching all words of the given query. The words are
+  // matched against the beginning of the words in the titles, summaries, contents, and
+  // authors of the items. The results are ranked by relevance, whereby matches in the
+  // title and the author are weighted higher than matches in the summary or content. At
+  // most 'limit' results are returned.
+  pub fn search(
+    &self,
+    query: &str,
+    limit: u32,
+  ) -> Result<Vec<FeedItem>, rusqlite::Error> {
+    let query = Self::to_fts_query(query);
+
+    if query.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let mut statement = self.connection.prepare_cached(
+      "SELECT items.title, items.url, items.date, items.feed_title, items.summary,
+              items.content, items.author
+       FROM items_fts JOIN items ON items.rowid = items_fts.rowid
+       WHERE items_fts MATCH ?1
+       ORDER BY bm25(items_fts, 10.0, 2.0, 1.0, 5.0)
+       LIMIT ?2",
+    )?;
+
+    let rows =
+      statement.query_map(params![query, limit], |row| Self::item_from_row(row, 0))?;
+
+    rows.collect()
+  }
+
+  // Searches the titles of all items for the given terms. Like for search(), the terms
+  // are matched against the beginning of the words in the titles. The results are sorted
+  // by date with the newest item first. Next to each item, the URL of its feed is
+  // returned. At most 'limit' results are returned.
+  pub fn search_titles(
+    &self,
+    terms: &[String],
+    limit: u32,
+  ) -> Result<Vec<(String, FeedItem)>, rusqlite::Error> {
+    let query = Self::to_fts_query(&terms.join(" "));
+
+    if query.is_empty() {
+      return Ok(vec![]);
+    }
+
+    let mut statement = self.connection.prepare_cached(
+      "SELECT items.feed_url, items.title, items.url, items.date, items.feed_title,
+              items.summary, items.content, items.author
+       FROM items_fts JOIN items ON items.rowid = items_fts.rowid
+       WHERE items_fts MATCH ?1
+       ORDER BY items.date DESC
+       LIMIT ?2",
+    )?;
+
+    let query = format!("title : ({})", query);
+    let rows = statement.query_map(params![query, limit], |row| {
+      Ok((row.get(0)?, Self::item_from_row(row, 1)?))
+    })?;
+
+    rows.collect()
+  }
+
+  // Returns the item with the given URL and the URL of its feed. None is returned if
+  // there is no such item in the index.
+  pub fn get_item(
+    &self,
+    url: &str,
+  ) -> Result<Option<(String, FeedItem)>, rusqlite::Error> {
+    let mut statement = self.connection.prepare_cached(
+      "SELECT feed_url, title, url, date, feed_title, summary, content, author
+       FROM items WHERE url = ?1",
+    )?;
+
+    statement
+      .query_row(params![url], |row| {
+        Ok((row.get(0)?, Self::item_from_row(row, 1)?))
+      })
+      .optional()
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Adds the given items of the feed with the given URL and title to the index. Items
+  // without a URL are skipped.
+  fn insert_items(
+    &self,
+    feed_url: &str,
+    feed_title: &str,
+    items: &[FeedItem],
+  ) -> Result<(), rusqlite::Error> {
+    let transaction = self.connection.unchecked_transaction()?;
+
+    {
+      let mut statement = transaction.prepare_cached(
+        "INSERT INTO items
+           (url, feed_url, feed_title, title, summary, content, author, date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(url) DO UPDATE SET
+           feed_url = excluded.feed_url, feed_title = excluded.feed_title,
+           title = excluded.title, summary = excluded.summary,
+           content = excluded.content, author = excluded.author, date = excluded.date",
+      )?;
+
+      for item in items.iter() {
+        if item.get_url().is_empty() {
+          continue;
+        }
+
+        statement.execute(params![
+          *item.get_url(),
+          feed_url,
+          feed_title,
+          *item.get_title(),
+          *item.get_summary(),
+          *item.get_content(),
+          *item.get_author(),
+          item.get_date(),
+        ])?;
+      }
+    }
+
+    transaction.commit()
+  }
+
+  // Creates a FeedItem from the columns of the given row. Starting at the given column,
+  // the row has to contain the title, the URL, the date, the feed title, the summary,
+  // the content, and the author of the item.
+  fn item_from_row(
+    row: &rusqlite::Row,
+    first: usize,
+  ) -> Result<FeedItem, rusqlite::Error> {
+    let item = FeedItem::new(&row.get(first)?, &row.get(first + 1)?, row.get(first + 2)?);
+    item.set_property("feed-title", row.get::<_, String>(first + 3)?);
+    item.set_property("summary", row.get::<_, String>(first + 4)?);
+    item.set_property("content", row.get::<_, String>(first + 5)?);
+    item.set_property("author", row.get::<_, String>(first + 6)?);
+    Ok(item)
+  }
+
+  // User input may contain characters which have a special meaning in the FTS5 query
+  // syntax. Hence, each word is quoted and turned into a prefix query. All words are
+  // implicitly combined with AND.
+  fn to_fts_query(query: &str) -> String {
+    query
+      .split_whitespace()
+      .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+      .collect::<Vec<String>>()
+      .join(" ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item(title: &str, url: &str, date: i64, content: &str) -> FeedItem {
+    let item = FeedItem::new(&title.into(), &url.into(), date);
+    item.set_property("content", content);
+    item
+  }
+
+  fn titles(items: &[FeedItem]) -> Vec<String> {
+    items.iter().map(|item| item.get_title().clone()).collect()
+  }
+
+  fn create_index() -> ItemIndex {
+    let index =
+      ItemIndex::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+    index
+      .insert_items(
+        "https://example.com/feed.xml",
+        "Example",
+        &[
+          item(
+            "GTK 4.8 Released",
+            "https://example.com/gtk",
+            20,
+            "Rust bindings",
+          ),
+          item("Rust 1.65 Released", "https://example.com/rust", 10, ""),
+          item("Say \"hi\" or bye", "https://example.com/quote", 0, ""),
+          item("No URL", "", 0, ""),
+        ],
+      )
+      .unwrap();
+    index
+  }
+
+  #[test]
+  fn upsert() {
+    let index = create_index();
+    assert_eq!(
+      titles(&index.search("no url", 10).unwrap()),
+      Vec::<String>::new()
+    );
+
+    // Items with the same URL are updated, both in the table and in the FTS index.
+    index
+      .insert_items(
+        "https://example.com/feed.xml",
+        "Example",
+        &[item(
+          "Rust 1.66 Released",
+          "https://example.com/rust",
+          30,
+          "",
+        )],
+      )
+      .unwrap();
+
+    assert!(index.search("1.65", 10).unwrap().is_empty());
+    assert_eq!(
+      titles(&index.search("1.66", 10).unwrap()),
+      ["Rust 1.66 Released"]
+    );
+
+    let (feed_url, item) = index.get_item("https://example.com/rust").unwrap().unwrap();
+    assert_eq!(feed_url, "https://example.com/feed.xml");
+    assert_eq!(*item.get_feed_title(), "Example");
+    assert_eq!(item.get_date(), 30);
+    assert!(index
+      .get_item("https://example.com/other")
+      .unwrap()
+      .is_none());
+  }
+
+  #[test]
+  fn ranking() {
+    let index = create_index();
+
+    // Matches in the title are ranked higher than matches in the content.
+    assert_eq!(
+      titles(&index.search("rust", 10).unwrap()),
+      ["Rust 1.65 Released", "GTK 4.8 Released"]
+    );
+
+    // All words have to match, each as a prefix.
+    assert_eq!(
+      titles(&index.search("rel gtk", 10).unwrap()),
+      ["GTK 4.8 Released"]
+    );
+    assert_eq!(
+      titles(&index.search("rust", 1).unwrap()),
+      ["Rust 1.65 Released"]
+    );
+
+    // The title search only considers the titles and sorts by date.
+    let terms = vec![String::from("released")];
+    let results = index.search_titles(&terms, 10).unwrap();
+    let results: Vec<FeedItem> = results.into_iter().map(|(_, item)| item).collect();
+    assert_eq!(titles(&results), ["GTK 4.8 Released", "Rust 1.65 Released"]);
+
+    let terms = vec![String::from("bindings")];
+    assert!(index.search_titles(&terms, 10).unwrap().is_empty());
+  }
+
+  #[test]
+  fn query_quoting() {
+    let index = create_index();
+
+    // Special characters of the FTS5 query syntax are neither errors nor operators.
+    assert!(index.search("\"", 10).unwrap().is_empty());
+    assert!(index.search("*", 10).unwrap().is_empty());
+    assert!(index.search("ru\"st", 10).unwrap().is_empty());
+    assert!(index.search("NEAR(", 10).unwrap().is_empty());
+    assert!(index.search("rust OR gtk", 10).unwrap().is_empty());
+    assert!(index.search("", 10).unwrap().is_empty());
+
+    assert_eq!(
+      titles(&index.search("\"hi\"", 10).unwrap()),
+      ["Say \"hi\" or bye"]
+    );
+    assert_eq!(
+      titles(&index.search("or", 10).unwrap()),
+      ["Say \"hi\" or bye"]
+    );
+  }
+
+  #[test]
+  fn read_state() {
+    let index = create_index();
+    let urls = vec![String::from("example.com/rust"), String::new()];
+
+    assert!(!index.is_read("example.com/rust").unwrap());
+    index.mark_read(&urls).unwrap();
+    index.mark_read(&urls).unwrap();
+    assert!(index.is_read("example.com/rust").unwrap());
+    assert!(!index.is_read("example.com/gtk").unwrap());
+    assert!(!index.is_read("").unwrap());
+  }
+}
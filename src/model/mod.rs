@@ -12,10 +12,63 @@
 // This module contains some objects which store the data which is shown in the user
 // interface.
 
+mod credentials;
+mod download;
+mod duplicates;
 mod feed;
 mod feed_item;
+mod feed_source;
+mod filter_preset;
+mod highlight_rule;
+mod import;
+mod item_index;
+mod mute_rule;
+mod network;
+mod page_scraper;
+mod parser;
+mod rule_matcher;
+mod thumbnail_cache;
+mod tls;
+mod websub;
 
+pub use self::credentials::open_credential_store;
+pub use self::credentials::Authentication;
+pub use self::credentials::CredentialStore;
+pub use self::credentials::Credentials;
+pub use self::download::Download;
+pub use self::download::DownloadState;
+pub use self::duplicates::find_duplicates;
+pub use self::duplicates::get_other_feed_titles;
+pub use self::duplicates::normalize_url;
 pub use self::feed::Feed;
+pub use self::feed::FeedOrder;
 pub use self::feed::FeedState;
+pub use self::feed::ItemOrder;
 pub use self::feed::StoredFeed;
+pub use self::feed_item::DateSource;
 pub use self::feed_item::FeedItem;
+pub use self::feed_item::StoredItem;
+pub use self::feed_source::FeedSource;
+pub use self::feed_source::FetchedContent;
+pub use self::filter_preset::FilterPreset;
+pub use self::highlight_rule::HighlightRule;
+pub use self::highlight_rule::HighlightStyle;
+pub use self::import::feed_uri_to_url;
+pub use self::import::get_self_link;
+pub use self::import::is_opml;
+pub use self::import::parse_opml;
+pub use self::item_index::ItemIndex;
+pub use self::mute_rule::MuteRule;
+pub use self::network::set_pause_on_metered;
+pub use self::network::NetworkState;
+pub use self::page_scraper::ScrapeConfig;
+pub use self::page_scraper::ScrapedItem;
+pub use self::rule_matcher::RuleKind;
+pub use self::rule_matcher::RuleMatcher;
+pub use self::thumbnail_cache::ThumbnailCache;
+pub use self::tls::http_client;
+pub use self::tls::set_ca_certificates;
+pub use self::tls::TlsConfig;
+pub use self::websub::WebSubEvent;
+pub use self::websub::WebSubLinks;
+pub use self::websub::WebSubServer;
@@ -0,0 +1,57 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{RuleKind, RuleMatcher};
+
+// ---------------------------------------------------------------------------------------
+// MuteRules are used to hide items in all feeds. An array of such structs is converted
+// from and to JSON using serde and stored under the GSettings key
+// /io/github/schneegans/BingeRSS/mute-rules. All comparisons are case-insensitive.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MuteRule {
+  // The part of the item the pattern is matched against.
+  pub kind: RuleKind,
+
+  // The keyword, author, or domain.
+  pub pattern: String,
+}
+
+impl MuteRule {
+  // The kinds of mute rules in the order in which they are shown in the user interface.
+  pub const KINDS: [RuleKind; 3] =
+    [RuleKind::Keyword, RuleKind::Author, RuleKind::Domain];
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns a RuleMatcher which decides whether an item should be hidden because of this
+  // rule.
+  pub fn get_matcher(&self) -> RuleMatcher {
+    RuleMatcher::new(self.kind, &self.pattern)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::FeedItem;
+
+  #[test]
+  fn stored_rule() {
+    let rule: MuteRule =
+      serde_json::from_str(r#"{"kind":"domain","pattern":"example.com"}"#).unwrap();
+    assert_eq!(rule.kind, RuleKind::Domain);
+
+    let item = FeedItem::new(&"Story".into(), &"https://example.com/1".into(), 0);
+    assert!(rule.get_matcher().matches(&item));
+  }
+}
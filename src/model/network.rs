@@ -0,0 +1,100 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{gio, prelude::*};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether automatic refreshes are paused on metered connections. This mirrors the
+// pause-on-metered key of the settings.
+static PAUSE_ON_METERED: AtomicBool = AtomicBool::new(false);
+
+// Sets whether automatic refreshes should be paused on metered connections. This is
+// considered by all subsequent calls to NetworkState::current().
+pub fn set_pause_on_metered(pause: bool) {
+  PAUSE_ON_METERED.store(pause, Ordering::Relaxed);
+}
+
+// ---------------------------------------------------------------------------------------
+// Web feeds can only be downloaded if the network is available. On metered connections,
+// automatic refreshes can be paused in the preferences; feeds can still be refreshed
+// manually then.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NetworkState {
+  Offline,
+  Paused,
+  Online,
+}
+
+impl NetworkState {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Determines the state from the availability and the kind of the network and from the
+  // pause-on-metered preference.
+  pub fn new(available: bool, metered: bool, pause_on_metered: bool) -> Self {
+    if !available {
+      NetworkState::Offline
+    } else if metered && pause_on_metered {
+      NetworkState::Paused
+    } else {
+      NetworkState::Online
+    }
+  }
+
+  // Returns the current state as reported by the default gio::NetworkMonitor.
+  pub fn current() -> Self {
+    let monitor = gio::NetworkMonitor::default();
+    Self::new(
+      monitor.is_network_available(),
+      monitor.is_network_metered(),
+      PAUSE_ON_METERED.load(Ordering::Relaxed),
+    )
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns true if web feeds may be downloaded. Automatic refreshes, for instance at
+  // startup or when the network returns, are paused on metered connections if this is
+  // configured. Downloads requested by the user only require the network.
+  pub fn allows_download(&self, automatic: bool) -> bool {
+    match self {
+      NetworkState::Offline => false,
+      NetworkState::Paused => !automatic,
+      NetworkState::Online => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn state() {
+    assert_eq!(
+      NetworkState::new(false, false, false),
+      NetworkState::Offline
+    );
+    assert_eq!(NetworkState::new(false, true, true), NetworkState::Offline);
+    assert_eq!(NetworkState::new(true, true, true), NetworkState::Paused);
+    assert_eq!(NetworkState::new(true, true, false), NetworkState::Online);
+    assert_eq!(NetworkState::new(true, false, true), NetworkState::Online);
+  }
+
+  #[test]
+  fn allows_download() {
+    assert!(!NetworkState::Offline.allows_download(false));
+    assert!(!NetworkState::Offline.allows_download(true));
+    assert!(NetworkState::Paused.allows_download(false));
+    assert!(!NetworkState::Paused.allows_download(true));
+    assert!(NetworkState::Online.allows_download(false));
+    assert!(NetworkState::Online.allows_download(true));
+  }
+}
@@ -0,0 +1,283 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::prelude::*;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{DateSource, FeedItem};
+
+// ---------------------------------------------------------------------------------------
+// A ScrapeConfig turns a web page without a feed into a list of items. It contains CSS
+// selectors which are evaluated against the HTML of the page. If a Feed has a
+// ScrapeConfig, it is stored together with the Feed in the settings.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScrapeConfig {
+  // Each element matching this selector becomes an item.
+  pub item: String,
+
+  // The text of the first element inside the item matching this selector is used as
+  // title. If this is empty, the text of the entire item element is used.
+  #[serde(default)]
+  pub title: String,
+
+  // The first element inside the item matching this selector provides the link. If this
+  // is empty, the first link inside the item is used.
+  #[serde(default)]
+  pub link: String,
+
+  // The first element inside the item matching this selector provides the date. Its
+  // datetime attribute is preferred over its text. If this is empty, items have no date.
+  #[serde(default)]
+  pub date: String,
+}
+
+// ---------------------------------------------------------------------------------------
+// A ScrapedItem is the result of applying a ScrapeConfig to a single item element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapedItem {
+  pub title: String,
+  pub url: String,
+  pub date: Option<i64>,
+}
+
+impl ScrapedItem {
+  // Creates a FeedItem for this item. Items without a date get the time at which they
+  // have been seen for the first time, like feed entries without a date.
+  pub fn to_item(&self, feed_title: &str) -> FeedItem {
+    let item = FeedItem::new(&self.title, &self.url, self.date.unwrap_or(0));
+    item.set_property("feed-title", feed_title);
+
+    if self.date.is_some() {
+      item.set_property("date-source", DateSource::Published);
+    } else {
+      item.set_property("date-source", DateSource::FirstSeen);
+    }
+
+    item
+  }
+}
+
+impl ScrapeConfig {
+  // ---------------------------------------------------------------------- public methods
+
+  // Applies the selectors to the given HTML document. Relative links are resolved against
+  // the given base URL, which usually is the URL of the page. Items without any text are
+  // skipped. An error message is returned if any of the selectors is invalid.
+  pub fn scrape(
+    &self,
+    html: &str,
+    base: Option<&url::Url>,
+  ) -> Result<Vec<ScrapedItem>, String> {
+    let item = Self::parse_selector(&self.item)?
+      .ok_or_else(|| String::from("The item selector must not be empty."))?;
+    let title = Self::parse_selector(&self.title)?;
+    let link = Self::parse_selector(&self.link)?;
+    let date = Self::parse_selector(&self.date)?;
+    let any_link = Selector::parse("a[href]").expect("valid selector");
+
+    let document = Html::parse_document(html);
+
+    let items = document
+      .select(&item)
+      .filter_map(|element| {
+        let title = match &title {
+          Some(title) => element.select(title).next().map(get_text)?,
+          None => get_text(element),
+        };
+
+        if title.is_empty() {
+          return None;
+        }
+
+        // The link may be the matched element itself or a link inside of it.
+        let link_element = match &link {
+          Some(link) => element.select(link).next(),
+          None if element.value().attr("href").is_some() => Some(element),
+          None => element.select(&any_link).next(),
+        };
+
+        let url = link_element
+          .and_then(|e| {
+            e.value()
+              .attr("href")
+              .or_else(|| e.select(&any_link).next()?.value().attr("href"))
+          })
+          .map(|href| resolve_link(href.trim(), base))
+          .unwrap_or_default();
+
+        let date = date
+          .as_ref()
+          .and_then(|date| element.select(date).next())
+          .and_then(|e| {
+            let text = e
+              .value()
+              .attr("datetime")
+              .map_or_else(|| get_text(e), |a| a.to_string());
+            parse_date(&text)
+          });
+
+        Some(ScrapedItem { title, url, date })
+      })
+      .collect();
+
+    Ok(items)
+  }
+
+  // Returns the content of the <title> element of the given HTML document.
+  pub fn get_page_title(html: &str) -> Option<String> {
+    let selector = Selector::parse("title").expect("valid selector");
+    let document = Html::parse_document(html);
+    let title = document.select(&selector).next().map(get_text)?;
+
+    if title.is_empty() {
+      None
+    } else {
+      Some(title)
+    }
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Parses the given CSS selector. Empty selectors are returned as None.
+  fn parse_selector(selector: &str) -> Result<Option<Selector>, String> {
+    let selector = selector.trim();
+
+    if selector.is_empty() {
+      return Ok(None);
+    }
+
+    Selector::parse(selector)
+      .map(Some)
+      .map_err(|_| format!("'{}' is not a valid CSS selector.", selector))
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the text content of the given element. Whitespace is collapsed so that the text
+// can be shown in a single line.
+fn get_text(element: ElementRef) -> String {
+  element
+    .text()
+    .flat_map(|text| text.split_whitespace())
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+// ---------------------------------------------------------------------------------------
+// Resolves a possibly relative link against the given base URL.
+fn resolve_link(href: &str, base: Option<&url::Url>) -> String {
+  match base.and_then(|base| base.join(href).ok()) {
+    Some(url) => url.to_string(),
+    None => href.to_string(),
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Tries to interpret the given text as date. Besides RFC 3339 and RFC 2822, some common
+// formats without time zone are supported; those are interpreted as UTC. This returns
+// the date as unix timestamp.
+fn parse_date(text: &str) -> Option<i64> {
+  let text = text.trim();
+
+  if let Ok(date) = chrono::DateTime::parse_from_rfc3339(text) {
+    return Some(date.timestamp());
+  }
+
+  if let Ok(date) = chrono::DateTime::parse_from_rfc2822(text) {
+    return Some(date.timestamp());
+  }
+
+  for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+    if let Ok(date) = chrono::NaiveDateTime::parse_from_str(text, format) {
+      return Some(date.timestamp());
+    }
+  }
+
+  for format in ["%Y-%m-%d", "%d.%m.%Y", "%B %d, %Y", "%b %d, %Y", "%d %B %Y"] {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(text, format) {
+      return Some(date.and_hms_opt(0, 0, 0)?.timestamp());
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PAGE: &str = r#"
+    <html>
+      <head><title> Release Notes </title></head>
+      <body>
+        <article class="post">
+          <h2><a href="/posts/1">Version 1.0</a></h2>
+          <time datetime="2022-11-20T10:00:00Z">Yesterday</time>
+        </article>
+        <article class="post">
+          <h2>Version   0.9</h2>
+          <a class="more" href="https://example.org/posts/0.9">Read more</a>
+          <span class="date">November 1, 2022</span>
+        </article>
+        <article class="post"></article>
+      </body>
+    </html>"#;
+
+  #[test]
+  fn scrape() {
+    let config = ScrapeConfig {
+      item: "article.post".into(),
+      title: "h2".into(),
+      link: "".into(),
+      date: "time, .date".into(),
+    };
+
+    let base = url::Url::parse("https://example.com/news/").unwrap();
+    let items = config.scrape(PAGE, Some(&base)).unwrap();
+
+    assert_eq!(
+      items,
+      vec![
+        ScrapedItem {
+          title: "Version 1.0".into(),
+          url: "https://example.com/posts/1".into(),
+          date: Some(1668938400),
+        },
+        ScrapedItem {
+          title: "Version 0.9".into(),
+          url: "https://example.org/posts/0.9".into(),
+          date: Some(1667260800),
+        },
+      ]
+    );
+
+    assert_eq!(
+      ScrapeConfig::get_page_title(PAGE),
+      Some("Release Notes".into())
+    );
+  }
+
+  #[test]
+  fn invalid_selectors() {
+    let config = ScrapeConfig {
+      item: "".into(),
+      ..Default::default()
+    };
+    assert!(config.scrape(PAGE, None).is_err());
+
+    let config = ScrapeConfig {
+      item: "article[".into(),
+      ..Default::default()
+    };
+    assert!(config.scrape(PAGE, None).is_err());
+  }
+}
@@ -0,0 +1,566 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+// This module contains the functions which turn downloaded bytes into FeedItems. They do
+// not depend on the network or on a display, so they can be tested in isolation.
+
+use gtk::prelude::*;
+use std::borrow::Cow;
+
+use crate::model::{DateSource, FeedItem};
+
+// ---------------------------------------------------------------------------------------
+// Parses the given bytes as RSS 0.9x, 1.0, 2.0, Atom, or JSON Feed. XML documents which
+// declare an encoding other than UTF-8 are converted to UTF-8 first.
+pub fn parse_feed(
+  bytes: &[u8],
+) -> Result<feed_rs::model::Feed, feed_rs::parser::ParseFeedError> {
+  let bytes = to_utf8(bytes);
+  feed_rs::parser::parse(&bytes[..])
+}
+
+// ---------------------------------------------------------------------------------------
+// Creates a FeedItem for the given entry of a parsed feed. The given feed title is stored
+// in the FeedItem so that it can be shown in lists containing items of multiple feeds.
+// Relative links are resolved against the given base URL, which usually is the URL of the
+// feed. If the entry has no usable link, the URL of the FeedItem will be empty. If the
+// entry has neither a publication nor an update date, the date of the FeedItem will be
+// zero and its date source will be DateSource::FirstSeen. It is up to the caller to
+// assign the actual time at which the item was seen for the first time. If the entry has
+// an enclosure, the enclosure-* properties of the FeedItem are set accordingly. If an
+// image representing the entry can be found, its URL is stored as thumbnail. The labels
+// of the entry's categories are stored as well; categories without a label use their
+// term instead.
+pub fn entry_to_item(
+  entry: &feed_rs::model::Entry,
+  feed_title: &str,
+  base: Option<&url::Url>,
+) -> FeedItem {
+  let title = match &entry.title {
+    Some(title) => title.content.clone(),
+    None => String::from("Unnamed Item"),
+  };
+
+  let url = select_link(entry, base).unwrap_or_default();
+  let (date, date_source) = match (entry.published, entry.updated) {
+    (Some(published), _) => (published.timestamp(), DateSource::Published),
+    (None, Some(updated)) => (updated.timestamp(), DateSource::Updated),
+    (None, None) => (0, DateSource::FirstSeen),
+  };
+
+  // Summary, content, and authors are optional.
+  let summary = entry.summary.as_ref().map(|s| s.content.clone());
+  let content = entry.content.as_ref().and_then(|c| c.body.clone());
+  let author: Vec<String> = entry.authors.iter().map(|a| a.name.clone()).collect();
+  let categories: Vec<String> = entry
+    .categories
+    .iter()
+    .map(|c| c.label.clone().unwrap_or_else(|| c.term.clone()))
+    .collect();
+
+  let item = FeedItem::new(&title, &url, date);
+  item.set_property("date-source", date_source);
+  item.set_property("feed-title", feed_title);
+  item.set_property("summary", summary.unwrap_or_default());
+  item.set_property("content", content.unwrap_or_default());
+  item.set_property("author", author.join(", "));
+  item.set_categories(categories);
+
+  if let Some(thumbnail) = select_thumbnail(entry, base) {
+    item.set_property("thumbnail", thumbnail);
+  }
+
+  if let Some(enclosure) = select_enclosure(entry, base) {
+    item.set_property("enclosure-url", enclosure.url);
+    item.set_property("enclosure-type", enclosure.mime_type);
+    item.set_property("enclosure-length", enclosure.length);
+    item.set_property("enclosure-duration", enclosure.duration);
+    item.set_property("enclosure-thumbnail", enclosure.thumbnail);
+  }
+
+  item
+}
+
+// ---------------------------------------------------------------------------------------
+// This is used to pass the information on an entry's enclosure from select_enclosure() to
+// entry_to_item(). Unknown lengths and durations are zero, unknown strings are empty.
+#[derive(Debug, Default)]
+struct Enclosure {
+  url: String,
+  mime_type: String,
+  length: u64,
+  duration: u64,
+  thumbnail: String,
+}
+
+// ---------------------------------------------------------------------------------------
+// Chooses the enclosure of the given entry. feed-rs maps RSS <enclosure> elements and
+// Media RSS elements to media objects. Audio and video contents are preferred over other
+// contents. If there are no media objects, Atom links with rel="enclosure" are used.
+// Relative URLs are resolved against the given base URL. If there is no enclosure, None
+// is returned.
+fn select_enclosure(
+  entry: &feed_rs::model::Entry,
+  base: Option<&url::Url>,
+) -> Option<Enclosure> {
+  let is_media = |content: &feed_rs::model::MediaContent| {
+    content.content_type.as_ref().map_or(false, |t| {
+      t.type_().as_str() == "audio" || t.type_().as_str() == "video"
+    })
+  };
+
+  let contents: Vec<_> = entry
+    .media
+    .iter()
+    .flat_map(|object| object.content.iter().map(move |content| (object, content)))
+    .filter(|(_, content)| content.url.is_some())
+    .collect();
+
+  let selected = contents
+    .iter()
+    .find(|(_, content)| is_media(content))
+    .or_else(|| contents.first());
+
+  if let Some((object, content)) = selected {
+    let duration = content.duration.or(object.duration).unwrap_or_default();
+    let thumbnail = object.thumbnails.first().map(|t| t.image.uri.as_str());
+
+    return Some(Enclosure {
+      url: content.url.as_ref().unwrap().to_string(),
+      mime_type: content
+        .content_type
+        .as_ref()
+        .map(|t| t.to_string())
+        .unwrap_or_default(),
+      length: content.size.unwrap_or_default(),
+      duration: duration.as_secs(),
+      thumbnail: thumbnail.and_then(|t| resolve(t, base)).unwrap_or_default(),
+    });
+  }
+
+  let link = entry
+    .links
+    .iter()
+    .find(|l| l.rel.as_deref() == Some("enclosure"))?;
+
+  Some(Enclosure {
+    url: resolve(&link.href, base)?,
+    mime_type: link.media_type.clone().unwrap_or_default(),
+    length: link.length.unwrap_or_default(),
+    ..Default::default()
+  })
+}
+
+// ---------------------------------------------------------------------------------------
+// Chooses an image which represents the given entry. Media RSS thumbnails are preferred,
+// then media contents which are images, and then the first <img> in the entry's content
+// or summary. Relative URLs are resolved against the given base URL. If there is no
+// image, None is returned.
+fn select_thumbnail(
+  entry: &feed_rs::model::Entry,
+  base: Option<&url::Url>,
+) -> Option<String> {
+  let thumbnail = entry
+    .media
+    .iter()
+    .flat_map(|object| object.thumbnails.iter())
+    .map(|thumbnail| thumbnail.image.uri.as_str())
+    .find(|uri| !uri.is_empty());
+
+  if let Some(thumbnail) = thumbnail {
+    return resolve(thumbnail, base);
+  }
+
+  let image = entry
+    .media
+    .iter()
+    .flat_map(|object| object.content.iter())
+    .filter(|content| {
+      content
+        .content_type
+        .as_ref()
+        .map_or(false, |t| t.type_().as_str() == "image")
+    })
+    .find_map(|content| content.url.as_ref());
+
+  if let Some(image) = image {
+    return Some(image.to_string());
+  }
+
+  let content = entry.content.as_ref().and_then(|c| c.body.as_deref());
+  let summary = entry.summary.as_ref().map(|s| s.content.as_str());
+
+  content
+    .and_then(find_image)
+    .or_else(|| summary.and_then(find_image))
+    .and_then(|src| resolve(&src, base))
+}
+
+// ---------------------------------------------------------------------------------------
+// Returns the value of the src attribute of the first <img> tag in the given HTML. HTML
+// entities in the attribute value are not decoded, except for &amp;. If there is no such
+// tag, None is returned.
+fn find_image(html: &str) -> Option<String> {
+  let lower = html.to_ascii_lowercase();
+  let mut offset = 0;
+
+  while let Some(start) = lower[offset..].find("<img") {
+    let tag_start = offset + start;
+    let tag_end = lower[tag_start..]
+      .find('>')
+      .map_or(lower.len(), |e| tag_start + e);
+    let tag = &lower[tag_start..tag_end];
+
+    if let Some(src) = tag.find("src=") {
+      let value_start = tag_start + src + 4;
+      let quote = html[value_start..].chars().next()?;
+
+      let value = if quote == '"' || quote == '\'' {
+        html[value_start + 1..tag_end].split(quote).next()
+      } else {
+        html[value_start..tag_end].split_whitespace().next()
+      };
+
+      if let Some(value) = value.filter(|v| !v.is_empty()) {
+        return Some(value.replace("&amp;", "&"));
+      }
+    }
+
+    offset = tag_end;
+  }
+
+  None
+}
+
+// ---------------------------------------------------------------------------------------
+// Resolves the given, potentially relative URL against the given base URL. If this is not
+// possible, None is returned.
+fn resolve(url: &str, base: Option<&url::Url>) -> Option<String> {
+  if url.is_empty() {
+    return None;
+  }
+
+  match base {
+    Some(base) => base.join(url).ok().map(String::from),
+    None => url::Url::parse(url).ok().map(String::from),
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// Chooses the link which should be opened if the given entry is activated. Links with
+// rel="alternate" are preferred, then links without any relation, and then any other
+// link. If there are no links at all, the entry's ID is used if it is an URL. Relative
+// links are resolved against the given base URL. If there is no usable link, None is
+// returned.
+fn select_link(entry: &feed_rs::model::Entry, base: Option<&url::Url>) -> Option<String> {
+  let link = entry
+    .links
+    .iter()
+    .find(|l| l.rel.as_deref() == Some("alternate"))
+    .or_else(|| entry.links.iter().find(|l| l.rel.is_none()))
+    .or_else(|| entry.links.first())
+    .map(|l| l.href.as_str())
+    .filter(|href| !href.is_empty());
+
+  if let Some(link) = link {
+    return resolve(link, base);
+  }
+
+  // Atom entries often use their permalink as ID.
+  match url::Url::parse(&entry.id) {
+    Ok(id) if id.scheme() == "http" || id.scheme() == "https" => Some(id.into()),
+    _ => None,
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// If the given bytes start with an XML declaration containing an encoding other than
+// UTF-8, the bytes are converted to UTF-8 and the declaration is updated accordingly.
+// Else, the bytes are returned unchanged.
+fn to_utf8(bytes: &[u8]) -> Cow<[u8]> {
+  if !bytes.starts_with(b"<?xml") {
+    return Cow::Borrowed(bytes);
+  }
+
+  // The XML declaration only contains ASCII characters, so we can safely search it.
+  let end = match bytes.iter().position(|b| *b == b'>') {
+    Some(end) => end,
+    None => return Cow::Borrowed(bytes),
+  };
+
+  let declaration = String::from_utf8_lossy(&bytes[..end]);
+
+  // The encoding has to be quoted. Anything else is not a valid declaration, so the
+  // bytes are passed to the parser unchanged.
+  let label = declaration.split("encoding=").nth(1).and_then(|rest| {
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    rest[1..].split(quote).next()
+  });
+
+  let encoding = label.and_then(|l| encoding_rs::Encoding::for_label(l.as_bytes()));
+  let encoding = match encoding {
+    Some(encoding) if encoding != encoding_rs::UTF_8 => encoding,
+    _ => return Cow::Borrowed(bytes),
+  };
+
+  let (text, _) = encoding.decode_without_bom_handling(&bytes[end + 1..]);
+  let declaration = declaration.replace(label.unwrap(), "UTF-8");
+
+  Cow::Owned(format!("{}>{}", declaration, text).into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Parses the fixture with the given name and converts all its entries to FeedItems.
+  fn load(name: &str) -> (feed_rs::model::Feed, Vec<FeedItem>) {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let bytes = std::fs::read(path).expect("fixture exists");
+    let feed = parse_feed(&bytes).expect("fixture can be parsed");
+    let base = url::Url::parse("https://example.com/feed.xml").unwrap();
+    let items = feed
+      .entries
+      .iter()
+      .map(|e| entry_to_item(e, "Fixture", Some(&base)))
+      .collect();
+    (feed, items)
+  }
+
+  #[test]
+  fn rss_10_rdf() {
+    let (feed, items) = load("rss10.rdf");
+
+    assert_eq!(feed.title.unwrap().content, "RSS 1.0 Fixture");
+    assert_eq!(items.len(), 2);
+    assert_eq!(*items[0].get_title(), "First RDF Item");
+    assert_eq!(*items[0].get_url(), "https://example.com/rdf/1");
+    assert_eq!(*items[0].get_feed_title(), "Fixture");
+  }
+
+  #[test]
+  fn rss_20_with_pub_date() {
+    let (_, items) = load("rss20.xml");
+
+    assert_eq!(*items[0].get_title(), "Dated Item");
+    assert_eq!(*items[0].get_url(), "https://example.com/rss/dated");
+    assert_eq!(items[0].get_date(), 1667304000);
+    assert_eq!(items[0].get_date_source(), DateSource::Published);
+    assert_eq!(*items[0].get_summary(), "A summary of the dated item.");
+  }
+
+  #[test]
+  fn rss_20_categories() {
+    let (_, items) = load("rss20.xml");
+
+    assert_eq!(*items[0].get_categories(), vec!["Rust", "GTK"]);
+    assert!(items[1].get_categories().is_empty());
+  }
+
+  #[test]
+  fn rss_20_without_pub_date() {
+    let (_, items) = load("rss20.xml");
+
+    assert_eq!(*items[1].get_title(), "Undated Item");
+    assert_eq!(items[1].get_date(), 0);
+    assert_eq!(items[1].get_date_source(), DateSource::FirstSeen);
+  }
+
+  #[test]
+  fn atom_with_updated_date_only() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(items[0].get_date(), 1667390400);
+    assert_eq!(items[0].get_date_source(), DateSource::Updated);
+  }
+
+  #[test]
+  fn rss_20_without_title() {
+    let (_, items) = load("rss20.xml");
+
+    assert_eq!(*items[2].get_title(), "Unnamed Item");
+  }
+
+  #[test]
+  fn atom_with_multiple_links() {
+    let (feed, items) = load("atom.xml");
+
+    assert_eq!(feed.title.unwrap().content, "Atom Fixture");
+    assert_eq!(items.len(), 1);
+    assert_eq!(*items[0].get_title(), "Atom Entry");
+    assert_eq!(*items[0].get_url(), "https://example.com/atom/entry");
+    assert_eq!(items[0].get_date(), 1667390400);
+    assert_eq!(*items[0].get_author(), "John Doe");
+    assert!(items[0].get_content().contains("full content"));
+  }
+
+  #[test]
+  fn entry_without_links() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(*items[0].get_title(), "Without Links");
+    assert!(items[0].get_url().is_empty());
+  }
+
+  #[test]
+  fn entry_with_url_as_id() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(*items[1].get_url(), "https://example.com/links/by-id");
+  }
+
+  #[test]
+  fn entry_with_relative_link() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(*items[2].get_url(), "https://example.com/links/relative");
+  }
+
+  #[test]
+  fn entry_with_link_without_relation() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(*items[3].get_url(), "https://example.com/links/no-rel");
+  }
+
+  #[test]
+  fn entry_with_only_enclosure() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(*items[4].get_url(), "https://example.com/links/audio.mp3");
+  }
+
+  #[test]
+  fn atom_with_xml_base() {
+    let (_, items) = load("xml_base.xml");
+
+    // Links are resolved against the xml:base of their entry or feed, not against the
+    // URL of the feed.
+    assert_eq!(
+      *items[0].get_url(),
+      "https://cdn.example.com/blog/posts/first.html"
+    );
+    assert_eq!(
+      *items[0].get_enclosure_url(),
+      "https://cdn.example.com/blog/media/first.mp3"
+    );
+    assert_eq!(
+      *items[1].get_url(),
+      "https://cdn.example.com/blog/second.html"
+    );
+    assert_eq!(
+      *items[1].get_enclosure_url(),
+      "https://cdn.example.com/blog/media/second.mp3"
+    );
+  }
+
+  #[test]
+  fn rss_20_podcast_enclosure() {
+    let (_, items) = load("podcast.xml");
+
+    assert_eq!(*items[0].get_url(), "https://example.com/podcast/episode-1");
+    assert_eq!(
+      *items[0].get_enclosure_url(),
+      "https://example.com/podcast/episode-1.mp3"
+    );
+    assert_eq!(*items[0].get_enclosure_type(), "audio/mpeg");
+    assert_eq!(items[0].get_enclosure_length(), 24986239);
+    assert!(!items[1].has_enclosure());
+  }
+
+  #[test]
+  fn atom_enclosure_link() {
+    let (_, items) = load("links.xml");
+
+    assert_eq!(
+      *items[4].get_enclosure_url(),
+      "https://example.com/links/audio.mp3"
+    );
+    assert_eq!(*items[4].get_enclosure_type(), "audio/mpeg");
+    assert!(!items[3].has_enclosure());
+  }
+
+  #[test]
+  fn media_thumbnail() {
+    let (_, items) = load("podcast.xml");
+
+    assert_eq!(
+      *items[0].get_thumbnail(),
+      "https://example.com/podcast/episode-1.jpg"
+    );
+  }
+
+  #[test]
+  fn image_in_content() {
+    let (_, items) = load("podcast.xml");
+
+    assert_eq!(
+      *items[1].get_thumbnail(),
+      "https://example.com/podcast/notes.png?size=small&format=png"
+    );
+  }
+
+  #[test]
+  fn no_thumbnail() {
+    let (_, items) = load("rss20.xml");
+
+    assert!(items[0].get_thumbnail().is_empty());
+  }
+
+  #[test]
+  fn json_feed_11() {
+    let (feed, items) = load("feed.json");
+
+    assert_eq!(feed.title.unwrap().content, "JSON Feed Fixture");
+    assert_eq!(items.len(), 2);
+    assert_eq!(*items[0].get_title(), "First JSON Item");
+    assert_eq!(*items[0].get_url(), "https://example.com/json/1");
+    assert_eq!(items[0].get_date(), 1667476800);
+  }
+
+  #[test]
+  fn malformed_xml() {
+    let path = format!(
+      "{}/tests/fixtures/malformed.xml",
+      env!("CARGO_MANIFEST_DIR")
+    );
+    let bytes = std::fs::read(path).expect("fixture exists");
+
+    assert!(parse_feed(&bytes).is_err());
+  }
+
+  #[test]
+  fn iso_8859_1_encoding() {
+    let (feed, items) = load("latin1.xml");
+
+    assert_eq!(feed.title.unwrap().content, "Grüße aus Köln");
+    assert_eq!(*items[0].get_title(), "Über Straßen und Brücken");
+  }
+
+  #[test]
+  fn invalid_encoding_declaration() {
+    let path = format!(
+      "{}/tests/fixtures/invalid_encoding.xml",
+      env!("CARGO_MANIFEST_DIR")
+    );
+    let bytes = std::fs::read(path).expect("fixture exists");
+
+    assert!(matches!(to_utf8(&bytes), Cow::Borrowed(_)));
+    let _ = parse_feed(&bytes);
+  }
+
+  #[test]
+  fn utf8_is_unchanged() {
+    let bytes = b"<?xml version=\"1.0\" encoding=\"utf-8\"?><rss></rss>";
+
+    assert!(matches!(to_utf8(bytes), Cow::Borrowed(_)));
+  }
+}
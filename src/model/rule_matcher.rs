@@ -0,0 +1,196 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::model::FeedItem;
+
+// ---------------------------------------------------------------------------------------
+// MuteRules and HighlightRules can match against different parts of a feed item. Each of
+// them supports a subset of these kinds.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleKind {
+  // The rule matches if the item's title contains the pattern.
+  Keyword,
+
+  // The rule matches if the pattern is a valid regular expression which matches the
+  // item's title.
+  Regex,
+
+  // The rule matches if the item's author contains the pattern.
+  Author,
+
+  // The rule matches if the item's URL points to the domain given as pattern or to one
+  // of its subdomains.
+  Domain,
+
+  // The rule matches if one of the item's categories equals the pattern.
+  Category,
+}
+
+impl RuleKind {
+  // Returns a human-readable name of the kind which can be shown in the user interface.
+  pub fn get_label(&self) -> &'static str {
+    match self {
+      RuleKind::Keyword => "Keyword",
+      RuleKind::Regex => "Regular Expression",
+      RuleKind::Author => "Author",
+      RuleKind::Domain => "Domain",
+      RuleKind::Category => "Category",
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// A RuleMatcher decides whether a feed item matches the pattern of a rule. The pattern is
+// prepared once, so that regular expressions do not have to be compiled again for each
+// item. All comparisons are case-insensitive. Rules with an empty pattern or an invalid
+// regular expression never match.
+#[derive(Debug, Clone)]
+pub struct RuleMatcher {
+  kind: RuleKind,
+  pattern: String,
+  regex: Option<Regex>,
+}
+
+impl RuleMatcher {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Prepares the given pattern for matching against the given part of the items.
+  pub fn new(kind: RuleKind, pattern: &str) -> Self {
+    let regex = match kind {
+      RuleKind::Regex if !pattern.trim().is_empty() => RegexBuilder::new(pattern.trim())
+        .case_insensitive(true)
+        .build()
+        .ok(),
+      _ => None,
+    };
+
+    Self {
+      kind,
+      pattern: pattern.trim().to_lowercase(),
+      regex,
+    }
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns true if the given item matches the pattern.
+  pub fn matches(&self, item: &FeedItem) -> bool {
+    let pattern = &self.pattern;
+
+    if pattern.is_empty() {
+      return false;
+    }
+
+    match self.kind {
+      RuleKind::Keyword => item.get_title().to_lowercase().contains(pattern),
+      RuleKind::Regex => self
+        .regex
+        .as_ref()
+        .map_or(false, |regex| regex.is_match(&item.get_title())),
+      RuleKind::Author => item.get_author().to_lowercase().contains(pattern),
+      RuleKind::Domain => {
+        let pattern = pattern.trim_start_matches("www.");
+        let host = url::Url::parse(&item.get_url())
+          .ok()
+          .and_then(|url| url.host_str().map(|host| host.to_lowercase()));
+
+        host.map_or(false, |host| {
+          let host = host.trim_start_matches("www.");
+          host == pattern || host.ends_with(&format!(".{}", pattern))
+        })
+      }
+      RuleKind::Category => item
+        .get_categories()
+        .iter()
+        .any(|category| category.trim().to_lowercase() == *pattern),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item(title: &str, url: &str, author: &str, categories: &[&str]) -> FeedItem {
+    let item = FeedItem::new(&title.into(), &url.into(), 0);
+    item.set_property("author", author);
+    item.set_categories(categories.iter().map(|c| c.to_string()).collect());
+    item
+  }
+
+  fn matches(kind: RuleKind, pattern: &str, item: &FeedItem) -> bool {
+    RuleMatcher::new(kind, pattern).matches(item)
+  }
+
+  #[test]
+  fn keyword() {
+    let item = item(
+      "Giveaway: Win a new Laptop",
+      "https://example.com/1",
+      "",
+      &[],
+    );
+
+    assert!(matches(RuleKind::Keyword, "giveaway", &item));
+    assert!(!matches(RuleKind::Keyword, "sponsored", &item));
+    assert!(!matches(RuleKind::Keyword, " ", &item));
+  }
+
+  #[test]
+  fn regex() {
+    let item = item("GTK 4.10 Released", "https://example.com/1", "", &[]);
+
+    assert!(matches(RuleKind::Regex, r"gtk \d+\.\d+", &item));
+    assert!(matches(RuleKind::Regex, r" GTK \d+ ", &item));
+    assert!(!matches(RuleKind::Regex, "^released", &item));
+    assert!(!matches(RuleKind::Regex, "gtk (", &item));
+    assert!(!matches(RuleKind::Regex, "", &item));
+  }
+
+  #[test]
+  fn author() {
+    let item = item(
+      "Some Story",
+      "https://example.com/1",
+      "Jane Doe, John Doe",
+      &[],
+    );
+
+    assert!(matches(RuleKind::Author, "john doe", &item));
+    assert!(!matches(RuleKind::Author, "Max", &item));
+  }
+
+  #[test]
+  fn domain() {
+    let item = item("Some Story", "https://www.news.example.com/1", "", &[]);
+
+    assert!(matches(RuleKind::Domain, "example.com", &item));
+    assert!(matches(RuleKind::Domain, "news.example.com", &item));
+    assert!(!matches(RuleKind::Domain, "ample.com", &item));
+  }
+
+  #[test]
+  fn category() {
+    let item = item(
+      "Some Story",
+      "https://example.com/1",
+      "",
+      &["Rust", "Programming Languages"],
+    );
+
+    assert!(matches(RuleKind::Category, "rust", &item));
+    assert!(!matches(RuleKind::Category, "programming", &item));
+  }
+}
@@ -0,0 +1,247 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::{gdk, gio, glib, prelude::*};
+use std::{
+  path::PathBuf,
+  time::{Duration, SystemTime},
+};
+
+// Thumbnails which have been cached longer than this are removed from the cache.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// If the cached thumbnails are larger than this in total, the oldest are removed.
+const MAX_CACHE_SIZE: u64 = 50 * 1024 * 1024;
+
+// Images larger than this are not downloaded.
+const MAX_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+
+// ---------------------------------------------------------------------------------------
+// The ThumbnailCache loads the thumbnail images of feed items. Images are downloaded only
+// when they are requested for the first time. They are then scaled down and stored as PNG
+// files in the user's cache directory, so that subsequent requests do not need to access
+// the network. Old thumbnails are removed by prune().
+pub struct ThumbnailCache {}
+
+impl ThumbnailCache {
+  // The thumbnails are scaled to fit into a square of this size.
+  pub const SIZE: i32 = 64;
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Loads the image at the given URL. If it is in the cache already, it is read from
+  // disk. Else, it is downloaded on the tokio runtime, scaled down, and stored in the
+  // cache. Reading, decoding, and writing the images happens asynchronously as well, so
+  // the main thread is never blocked. If anything fails, None is returned.
+  pub async fn load(url: String) -> Option<gdk::Texture> {
+    let file = gio::File::for_path(Self::get_path(&url));
+
+    if file.query_exists(gio::Cancellable::NONE) {
+      let result = match file.read_future(glib::PRIORITY_DEFAULT).await {
+        Ok(stream) => gdk::gdk_pixbuf::Pixbuf::from_stream_future(&stream).await,
+        Err(err) => Err(err),
+      };
+
+      return match result {
+        Ok(pixbuf) => Some(gdk::Texture::for_pixbuf(&pixbuf)),
+        Err(err) => {
+          println!("Failed to load thumbnail {}: {}", file.parse_name(), err);
+          None
+        }
+      };
+    }
+
+    let url_copy = url.clone();
+    let handle = crate::RUNTIME.spawn(async move {
+      let client = crate::model::http_client().map_err(|err| err.to_string())?;
+      let mut response = client
+        .get(&url_copy)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+
+      let too_large = || format!("Image is larger than {} bytes", MAX_IMAGE_SIZE);
+
+      if response.content_length().unwrap_or(0) > MAX_IMAGE_SIZE as u64 {
+        return Err(too_large());
+      }
+
+      let mut bytes = Vec::new();
+      while let Some(chunk) = response.chunk().await.map_err(|err| err.to_string())? {
+        bytes.extend_from_slice(&chunk);
+
+        if bytes.len() > MAX_IMAGE_SIZE {
+          return Err(too_large());
+        }
+      }
+
+      Ok(bytes)
+    });
+
+    let bytes = match handle.await.unwrap() {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        println!("Failed to download thumbnail {}: {}", url, err);
+        return None;
+      }
+    };
+
+    let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(bytes));
+    let pixbuf = gdk::gdk_pixbuf::Pixbuf::from_stream_at_scale_future(
+      &stream,
+      Self::SIZE,
+      Self::SIZE,
+      true,
+    )
+    .await
+    .ok()?;
+
+    // Failing to write the cache is not fatal, the image will be downloaded again next
+    // time.
+    let result = std::fs::create_dir_all(file.path().unwrap().parent().unwrap())
+      .map_err(|err| err.to_string())
+      .and_then(|_| {
+        pixbuf
+          .save_to_bufferv("png", &[])
+          .map_err(|err| err.to_string())
+      });
+
+    let result = match result {
+      Ok(png) => file
+        .replace_contents_future(
+          png,
+          None,
+          false,
+          gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|(_, err)| err.to_string()),
+      Err(err) => Err(err),
+    };
+
+    if let Err(err) = result {
+      println!("Failed to cache thumbnail {}: {}", file.parse_name(), err);
+    }
+
+    Some(gdk::Texture::for_pixbuf(&pixbuf))
+  }
+
+  // Removes all thumbnails which are older than MAX_AGE from the cache. If the remaining
+  // thumbnails are larger than MAX_CACHE_SIZE in total, the oldest of them are removed as
+  // well. This happens on a background thread.
+  pub fn prune() {
+    let mut dir = Self::get_path("");
+    dir.pop();
+
+    crate::RUNTIME.spawn_blocking(move || {
+      let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+      };
+
+      let files = entries
+        .filter_map(|entry| {
+          let entry = entry.ok()?;
+          let metadata = entry.metadata().ok()?;
+          Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect();
+
+      for path in Self::get_expired(files, SystemTime::now()) {
+        if let Err(err) = std::fs::remove_file(&path) {
+          println!("Failed to remove thumbnail {}: {}", path.display(), err);
+        }
+      }
+    });
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Returns the path at which the thumbnail for the given URL is cached. The file name is
+  // the SHA-256 checksum of the URL, so it is the same for all versions of BingeRSS.
+  fn get_path(url: &str) -> PathBuf {
+    let checksum =
+      glib::compute_checksum_for_data(glib::ChecksumType::Sha256, url.as_bytes())
+        .expect("SHA-256 is supported");
+
+    let mut path = glib::user_cache_dir();
+    path.push("binge-rss");
+    path.push("thumbnails");
+    path.push(format!("{}.png", checksum));
+    path
+  }
+
+  // Returns the paths of those of the given files which should be removed from the
+  // cache. Each file is given with its size and its modification time.
+  fn get_expired(
+    mut files: Vec<(PathBuf, u64, SystemTime)>,
+    now: SystemTime,
+  ) -> Vec<PathBuf> {
+    // Sort the files so that the newest file comes first.
+    files.sort_by(|a, b| b.2.cmp(&a.2));
+
+    let mut total_size = 0;
+
+    files
+      .into_iter()
+      .filter(|(_, size, time)| {
+        total_size += size;
+        let age = now.duration_since(*time).unwrap_or_default();
+        age > MAX_AGE || total_size > MAX_CACHE_SIZE
+      })
+      .map(|(path, _, _)| path)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn path() {
+    let path = ThumbnailCache::get_path("https://example.com/image.png");
+    let name = path.file_name().unwrap().to_str().unwrap();
+    assert_eq!(
+      name,
+      "99a19c215d3db74ae82c36fa43878f88c5e63830dc30799320b01a4f5aa341e4.png"
+    );
+  }
+
+  #[test]
+  fn expired() {
+    let now = SystemTime::UNIX_EPOCH + MAX_AGE * 10;
+    let day = Duration::from_secs(24 * 60 * 60);
+    let file =
+      |name: &str, size: u64, age: Duration| (PathBuf::from(name), size, now - age);
+
+    // Old files are removed.
+    let files = vec![file("new", 10, day), file("old", 10, MAX_AGE + day)];
+    assert_eq!(
+      ThumbnailCache::get_expired(files, now),
+      vec![PathBuf::from("old")]
+    );
+
+    // If the cache is too large, the oldest files are removed.
+    let half = MAX_CACHE_SIZE / 2;
+    let files = vec![
+      file("b", half, day * 2),
+      file("c", half, day * 3),
+      file("a", half, day),
+    ];
+    assert_eq!(
+      ThumbnailCache::get_expired(files, now),
+      vec![PathBuf::from("c")]
+    );
+  }
+}
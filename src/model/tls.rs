@@ -0,0 +1,333 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, sync::RwLock};
+
+// When invalid certificates are accepted for a feed or when custom headers are sent,
+// redirects are only followed if they stay on the same host. This is the maximum number
+// of those redirects.
+const MAX_REDIRECTS: usize = 10;
+
+// The additional CA certificates configured in the preferences. They are trusted for all
+// requests next to the certificates of the system.
+static CA_CERTIFICATES: Lazy<RwLock<Vec<reqwest::Certificate>>> =
+  Lazy::new(|| RwLock::new(Vec::new()));
+
+// Loads the PEM files at the given paths and trusts all certificates contained in them
+// for subsequent requests. Previously loaded certificates are replaced. Files which
+// cannot be loaded are skipped; for each of them, an error message is returned.
+pub fn set_ca_certificates(paths: &[String]) -> Vec<String> {
+  let mut certificates = Vec::new();
+  let mut errors = Vec::new();
+
+  for path in paths {
+    match load_certificates(path) {
+      Ok(mut loaded) => certificates.append(&mut loaded),
+      Err(err) => errors.push(format!(
+        "Failed to load certificates from {}: {}",
+        path, err
+      )),
+    }
+  }
+
+  *CA_CERTIFICATES.write().unwrap() = certificates;
+
+  errors
+}
+
+// Returns a ClientBuilder which trusts the additional CA certificates.
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+  CA_CERTIFICATES
+    .read()
+    .unwrap()
+    .iter()
+    .fold(reqwest::Client::builder(), |builder, certificate| {
+      builder.add_root_certificate(certificate.clone())
+    })
+}
+
+// Returns a Client which trusts the additional CA certificates. This should be used for
+// all requests which do not belong to a specific feed, like favicons and thumbnails.
+pub fn http_client() -> reqwest::Result<reqwest::Client> {
+  http_client_builder().build()
+}
+
+// Reads all certificates from the PEM bundle at the given path.
+fn load_certificates(path: &str) -> Result<Vec<reqwest::Certificate>, Box<dyn Error>> {
+  const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+  const END: &str = "-----END CERTIFICATE-----";
+
+  let pem = std::fs::read_to_string(path)?;
+  let mut certificates = Vec::new();
+  let mut rest = pem.as_str();
+
+  while let Some(start) = rest.find(BEGIN) {
+    let end = rest[start..]
+      .find(END)
+      .ok_or("The PEM file contains an incomplete certificate.")?
+      + start
+      + END.len();
+
+    certificates.push(reqwest::Certificate::from_pem(
+      &rest.as_bytes()[start..end],
+    )?);
+    rest = &rest[end..];
+  }
+
+  if certificates.is_empty() {
+    return Err("The file contains no PEM certificates.".into());
+  }
+
+  Ok(certificates)
+}
+
+// ---------------------------------------------------------------------------------------
+// The TlsConfig of a Feed contains the settings which are specific to the server of the
+// feed: A client certificate for servers which require mutual TLS and whether invalid
+// server certificates should be accepted.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+  // The path to a PEM file containing the client certificate. If client_key is empty,
+  // this file has to contain the private key as well.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub client_certificate: String,
+
+  // The path to a PEM file containing the PKCS#8 private key of the client certificate.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub client_key: String,
+
+  // If set, the certificate of the server is not verified at all. This is only meant
+  // for servers with self-signed certificates which cannot be trusted otherwise.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub accept_invalid_certificates: bool,
+}
+
+impl TlsConfig {
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns true if the default settings are used. In this case, the TlsConfig is not
+  // written to the settings.
+  pub fn is_default(&self) -> bool {
+    *self == TlsConfig::default()
+  }
+
+  // Returns a Client for requests to the given URL. If invalid certificates are
+  // accepted, redirects to other hosts are refused, so that the exception does not
+  // apply to any other server. The same is done if same_host_only is set; this is used
+  // for requests carrying secrets which reqwest would not strip on redirects.
+  pub fn build_client(
+    &self,
+    url: &str,
+    same_host_only: bool,
+  ) -> Result<reqwest::Client, Box<dyn Error + Send + Sync>> {
+    let mut builder = http_client_builder();
+
+    if !self.client_certificate.is_empty() {
+      let certificate = std::fs::read(&self.client_certificate)?;
+      let key = if self.client_key.is_empty() {
+        certificate.clone()
+      } else {
+        std::fs::read(&self.client_key)?
+      };
+
+      builder = builder.identity(reqwest::Identity::from_pkcs8_pem(&certificate, &key)?);
+    }
+
+    if self.accept_invalid_certificates {
+      builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    if self.accept_invalid_certificates || same_host_only {
+      let host = url::Url::parse(url)?.host_str().map(str::to_string);
+      let message = if self.accept_invalid_certificates {
+        "Invalid certificates are only accepted for the feed's host."
+      } else {
+        "Custom headers are only sent to the feed's host."
+      };
+
+      builder = builder.redirect(reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+          attempt.error("Too many redirects.")
+        } else if attempt.url().host_str().map(str::to_string) != host {
+          attempt.error(message)
+        } else {
+          attempt.follow()
+        }
+      }));
+    }
+
+    Ok(builder.build()?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+  use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+  };
+
+  fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+  }
+
+  // Starts a minimal HTTPS server which uses the certificate of the test CA and answers
+  // each request with "ok". Returns the URL of the server.
+  fn start_server(require_client_certificate: bool) -> String {
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor
+      .set_private_key_file(fixture("server.key"), SslFiletype::PEM)
+      .unwrap();
+    acceptor
+      .set_certificate_chain_file(fixture("server.pem"))
+      .unwrap();
+
+    if require_client_certificate {
+      acceptor.set_ca_file(fixture("ca.pem")).unwrap();
+      acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+
+    let acceptor = acceptor.build();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        if let Ok(mut stream) = acceptor.accept(stream) {
+          // Skip the request header, it ends with an empty line.
+          let mut reader = BufReader::new(&mut stream);
+          let mut line = String::new();
+          while reader.read_line(&mut line).map_or(false, |n| n > 2) {
+            line.clear();
+          }
+
+          stream
+            .write_all(
+              b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            )
+            .ok();
+          stream.shutdown().ok();
+        }
+      }
+    });
+
+    format!("https://localhost:{}/feed.xml", port)
+  }
+
+  fn get(config: &TlsConfig, url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    get_with(config, url, false)
+  }
+
+  fn get_with(
+    config: &TlsConfig,
+    url: &str,
+    same_host_only: bool,
+  ) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let client = config.build_client(url, same_host_only)?;
+    crate::RUNTIME.block_on(async move {
+      Ok(
+        client
+          .get(url)
+          .send()
+          .await?
+          .error_for_status()?
+          .text()
+          .await?,
+      )
+    })
+  }
+
+  // The CA certificates are global, therefore all cases are checked in a single test.
+  #[test]
+  fn custom_trust() {
+    let url = start_server(false);
+    let mutual_url = start_server(true);
+
+    // The test CA is not trusted by default.
+    assert!(set_ca_certificates(&[]).is_empty());
+    assert!(get(&TlsConfig::default(), &url).is_err());
+
+    // Unless invalid certificates are accepted explicitly.
+    let config = TlsConfig {
+      accept_invalid_certificates: true,
+      ..Default::default()
+    };
+    assert_eq!(get(&config, &url).unwrap(), "ok");
+
+    // Missing files are reported.
+    assert_eq!(set_ca_certificates(&[fixture("missing.pem")]).len(), 1);
+
+    // Once the test CA is trusted, the server can be reached.
+    assert!(set_ca_certificates(&[fixture("ca.pem")]).is_empty());
+    assert_eq!(get(&TlsConfig::default(), &url).unwrap(), "ok");
+
+    // The second server requires a client certificate.
+    assert!(get(&TlsConfig::default(), &mutual_url).is_err());
+
+    let config = TlsConfig {
+      client_certificate: fixture("client.pem"),
+      client_key: fixture("client.key"),
+      ..Default::default()
+    };
+    assert_eq!(get(&config, &mutual_url).unwrap(), "ok");
+
+    set_ca_certificates(&[]);
+  }
+
+  // Starts a plain HTTP server which redirects requests for /feed.xml to the same path
+  // on the given host and answers all other requests with "ok". Returns the URL of the
+  // server on 127.0.0.1.
+  fn start_redirecting_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+      for mut stream in listener.incoming().flatten() {
+        let mut reader = BufReader::new(&mut stream);
+        let mut request = String::new();
+        reader.read_line(&mut request).ok();
+
+        let mut line = String::new();
+        while reader.read_line(&mut line).map_or(false, |n| n > 2) {
+          line.clear();
+        }
+
+        let response = if request.starts_with("GET /feed.xml ") {
+          format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/other.xml\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+            port
+          )
+        } else {
+          "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+            .to_string()
+        };
+
+        stream.write_all(response.as_bytes()).ok();
+      }
+    });
+
+    format!("http://127.0.0.1:{}/feed.xml", port)
+  }
+
+  #[test]
+  fn same_host_redirects() {
+    let url = start_redirecting_server();
+
+    // Redirects to other hosts are followed by default, but not if secrets are sent.
+    assert_eq!(get_with(&TlsConfig::default(), &url, false).unwrap(), "ok");
+    assert!(get_with(&TlsConfig::default(), &url, true).is_err());
+  }
+}
@@ -0,0 +1,743 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use gtk::glib;
+use std::{
+  collections::{HashMap, HashSet},
+  net::{IpAddr, SocketAddr},
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tokio::{
+  io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+  net::{TcpSocket, TcpStream},
+  task::JoinHandle,
+};
+
+use crate::model::{http_client, parser};
+
+// The lease which is requested from the hubs. Hubs may grant a shorter one.
+const LEASE_SECONDS: u32 = 7 * 24 * 60 * 60;
+
+// Pushed content larger than this is rejected.
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+// Connections which do not send a complete request within this time are closed.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Requests with a longer request line or header line, or with more header lines, are
+// rejected.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADER_COUNT: usize = 100;
+
+// ---------------------------------------------------------------------------------------
+// Feeds which support WebSub contain a link to their hub and a self link. The self link
+// is the topic under which the feed is known to the hub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSubLinks {
+  pub hub: String,
+  pub topic: String,
+}
+
+impl WebSubLinks {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Returns the hub and the topic of the given feed, or None if it has no hub. If the
+  // feed has no self link, the given URL of the feed is used as topic.
+  pub fn from_feed(feed: &feed_rs::model::Feed, url: &str) -> Option<Self> {
+    let find = |rel: &str| {
+      feed
+        .links
+        .iter()
+        .find(|link| link.rel.as_deref() == Some(rel))
+        .map(|link| link.href.clone())
+    };
+
+    Some(Self {
+      hub: find("hub")?,
+      topic: find("self").unwrap_or_else(|| url.to_string()),
+    })
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// The WebSubServer sends these events to the main context. The feeds are identified by
+// the IDs which were given to WebSubServer::subscribe().
+#[derive(Debug)]
+pub enum WebSubEvent {
+  // The hub verified that the feed should be subscribed or unsubscribed. Subscriptions
+  // have to be renewed with the given token before the lease expires.
+  Verified {
+    feed_id: String,
+    token: String,
+    mode: String,
+    lease_seconds: Option<u32>,
+  },
+
+  // The hub refused the subscription.
+  Denied {
+    feed_id: String,
+    reason: String,
+  },
+
+  // The hub pushed new content of the feed.
+  Content {
+    feed_id: String,
+    content: Box<feed_rs::model::Feed>,
+  },
+}
+
+// A subscription is pending until the hub verifies it. Unsubscribed subscriptions are
+// kept until the hub verifies the unsubscription as well.
+#[derive(Debug, Clone)]
+struct Subscription {
+  feed_id: String,
+  links: WebSubLinks,
+  mode: &'static str,
+}
+
+// The subscriptions are shared with the thread of the listener. They are stored by a
+// random token which is the last segment of the callback URL. As the token cannot be
+// guessed, only the hub can push content for a subscription.
+type Subscriptions = Arc<Mutex<HashMap<String, Subscription>>>;
+
+// ---------------------------------------------------------------------------------------
+// When a WebSubServer is stopped, the cancellations of its subscriptions still have to be
+// verified by the hubs. They are handed over to the next WebSubServer.
+#[derive(Debug)]
+pub struct PendingCancellations {
+  callback: String,
+  subscriptions: Vec<(String, Subscription)>,
+}
+
+// ---------------------------------------------------------------------------------------
+// The WebSubServer is a small HTTP listener which runs on the tokio runtime. Each
+// connection is handled in a separate task. Hubs send verification requests and pushed
+// content to its callback URL. The callback URL has to be reachable from the internet,
+// usually via a port forwarding or a reverse proxy. The listener is closed when the
+// WebSubServer is dropped; stop() cancels its subscriptions before.
+#[derive(Debug)]
+pub struct WebSubServer {
+  callback: String,
+  subscriptions: Subscriptions,
+  listener: JoinHandle<()>,
+}
+
+impl WebSubServer {
+  // ----------------------------------------------------------------- constructor methods
+
+  // Starts listening on the given local address and port. The callback is the public URL
+  // under which the port is reachable. All events are sent to the given sender.
+  pub fn start(
+    callback: &str,
+    address: &str,
+    port: u16,
+    sender: glib::Sender<WebSubEvent>,
+  ) -> std::io::Result<Self> {
+    let address: IpAddr = address.parse().map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid address '{}'", address),
+      )
+    })?;
+    let address = SocketAddr::new(address, port);
+
+    // The listener has to be created inside the runtime. SO_REUSEADDR is set so that the
+    // port can be bound again right away when the listener is restarted, even if there
+    // are closed connections in the TIME_WAIT state.
+    let _guard = crate::RUNTIME.enter();
+    let socket = match address {
+      SocketAddr::V4(_) => TcpSocket::new_v4()?,
+      SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    socket.set_reuseaddr(true)?;
+    socket.bind(address)?;
+    let listener = socket.listen(128)?;
+
+    let subscriptions = Subscriptions::default();
+    let task_subscriptions = subscriptions.clone();
+
+    let listener = crate::RUNTIME.spawn(async move {
+      loop {
+        match listener.accept().await {
+          Ok((stream, _)) => {
+            crate::RUNTIME.spawn(handle_connection(
+              stream,
+              task_subscriptions.clone(),
+              sender.clone(),
+            ));
+          }
+          Err(err) => {
+            // Errors like running out of file descriptors would make this loop spin.
+            println!("Failed to accept WebSub connection: {}", err);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+          }
+        }
+      }
+    });
+
+    Ok(Self {
+      callback: callback.trim_end_matches('/').to_string(),
+      subscriptions,
+      listener,
+    })
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns true if the feed with the given ID is subscribed with the given links or if
+  // this subscription is pending.
+  pub fn is_subscribed(&self, feed_id: &str, links: &WebSubLinks) -> bool {
+    self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .values()
+      .any(|subscription| {
+        subscription.feed_id == feed_id
+          && subscription.links == *links
+          && subscription.mode == "subscribe"
+      })
+  }
+
+  // Asks the hub to subscribe the feed with the given ID. If the feed was subscribed with
+  // different links before, the old subscription is canceled.
+  pub fn subscribe(&self, feed_id: &str, links: &WebSubLinks) {
+    if self.is_subscribed(feed_id, links) {
+      return;
+    }
+
+    self.unsubscribe(feed_id);
+
+    let token = glib::uuid_string_random().to_string();
+    self.subscriptions.lock().unwrap().insert(
+      token.clone(),
+      Subscription {
+        feed_id: feed_id.to_string(),
+        links: links.clone(),
+        mode: "subscribe",
+      },
+    );
+
+    self.send_request(&token);
+  }
+
+  // Asks the hub to renew the subscription with the given token. This does nothing if
+  // the subscription has been canceled in the meantime.
+  pub fn renew(&self, token: &str) {
+    let subscribed = self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .get(token)
+      .map_or(false, |subscription| subscription.mode == "subscribe");
+
+    if subscribed {
+      self.send_request(token);
+    }
+  }
+
+  // Asks the hubs to cancel all subscriptions of the feed with the given ID.
+  pub fn unsubscribe(&self, feed_id: &str) {
+    let tokens: Vec<String> = self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .iter_mut()
+      .filter(|(_, s)| s.feed_id == feed_id && s.mode == "subscribe")
+      .map(|(token, subscription)| {
+        subscription.mode = "unsubscribe";
+        token.clone()
+      })
+      .collect();
+
+    for token in tokens {
+      self.send_request(&token);
+    }
+  }
+
+  // Asks the hubs to cancel all subscriptions and closes the listener. The hubs verify
+  // the cancellations asynchronously by calling the callback URL, so the pending
+  // cancellations are returned. They can be passed to take_over() of a new listener.
+  pub fn stop(self) -> PendingCancellations {
+    let feed_ids: HashSet<String> = self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .values()
+      .map(|subscription| subscription.feed_id.clone())
+      .collect();
+
+    for feed_id in feed_ids {
+      self.unsubscribe(&feed_id);
+    }
+
+    let subscriptions = self
+      .subscriptions
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|(_, subscription)| subscription.mode == "unsubscribe")
+      .map(|(token, subscription)| (token.clone(), subscription.clone()))
+      .collect();
+
+    PendingCancellations {
+      callback: self.callback.clone(),
+      subscriptions,
+    }
+  }
+
+  // Answers the verification requests for the given cancellations of a previous
+  // listener. This only works if both listeners use the same callback URL.
+  pub fn take_over(&self, pending: PendingCancellations) {
+    if pending.callback == self.callback {
+      self
+        .subscriptions
+        .lock()
+        .unwrap()
+        .extend(pending.subscriptions);
+    }
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Sends the subscription request for the given token to its hub. The hub answers
+  // asynchronously by calling the callback URL.
+  fn send_request(&self, token: &str) {
+    let subscription = match self.subscriptions.lock().unwrap().get(token) {
+      Some(subscription) => subscription.clone(),
+      None => return,
+    };
+
+    let callback = format!("{}/{}", self.callback, token);
+    let WebSubLinks { hub, topic } = subscription.links;
+    let mode = subscription.mode;
+
+    crate::RUNTIME.spawn(async move {
+      let params = [
+        ("hub.mode", mode.to_string()),
+        ("hub.topic", topic.clone()),
+        ("hub.callback", callback),
+        ("hub.lease_seconds", LEASE_SECONDS.to_string()),
+      ];
+
+      let result = async {
+        http_client()?
+          .post(&hub)
+          .form(&params)
+          .send()
+          .await?
+          .error_for_status()?;
+        Ok::<(), reqwest::Error>(())
+      }
+      .await;
+
+      if let Err(err) = result {
+        println!("Failed to {} {} at {}: {}", mode, topic, hub, err);
+      }
+    });
+  }
+}
+
+impl Drop for WebSubServer {
+  // The listener task is canceled and we wait until it has been dropped. This ensures
+  // that the port is free again once the WebSubServer is gone, so that a new listener
+  // can be started on the same port. Connections which are currently handled are not
+  // interrupted.
+  fn drop(&mut self) {
+    self.listener.abort();
+    crate::RUNTIME.block_on(&mut self.listener).ok();
+  }
+}
+
+// Reads a single HTTP request from the given stream and writes the response. The
+// connection is closed afterwards.
+async fn handle_connection(
+  mut stream: TcpStream,
+  subscriptions: Subscriptions,
+  sender: glib::Sender<WebSubEvent>,
+) {
+  let (reader, mut writer) = stream.split();
+  let mut reader = BufReader::new(reader);
+
+  let request = tokio::time::timeout(READ_TIMEOUT, read_request(&mut reader))
+    .await
+    .unwrap_or(Err("408 Request Timeout"));
+
+  let (status, body) = match request {
+    Ok((method, target, body)) => {
+      match url::Url::parse("http://localhost").and_then(|url| url.join(&target)) {
+        Ok(url) => {
+          let (status, response, event) =
+            handle_request(&subscriptions, &method, &url, body);
+
+          if let Some(event) = event {
+            sender.send(event).ok();
+          }
+
+          (status, response)
+        }
+        Err(_) => ("400 Bad Request", String::new()),
+      }
+    }
+    Err(status) => (status, String::new()),
+  };
+
+  let response = format!(
+    "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\
+     Connection: close\r\n\r\n{}",
+    status,
+    body.len(),
+    body
+  );
+
+  if let Err(err) = writer.write_all(response.as_bytes()).await {
+    println!("Failed to answer WebSub request: {}", err);
+  }
+}
+
+// Reads the request line, the header lines, and the body of an HTTP request. Only
+// requests with a Content-Length are supported, chunked bodies are rejected. Returns the
+// method, the target, and the body of the request, or the status of the error response.
+async fn read_request<R: AsyncBufRead + Unpin>(
+  reader: &mut R,
+) -> Result<(String, String, Vec<u8>), &'static str> {
+  const BAD_REQUEST: &str = "400 Bad Request";
+
+  let request_line = read_line(reader).await?;
+  let mut parts = request_line.split_whitespace();
+  let method = parts.next().unwrap_or_default().to_string();
+  let target = parts.next().unwrap_or_default().to_string();
+
+  let mut content_length = 0;
+  let mut chunked = false;
+  let mut header_count = 0;
+
+  loop {
+    let line = read_line(reader).await?;
+
+    if line.trim_end().is_empty() {
+      break;
+    }
+
+    header_count += 1;
+    if header_count > MAX_HEADER_COUNT {
+      return Err("431 Request Header Fields Too Large");
+    }
+
+    if let Some((name, value)) = line.split_once(':') {
+      let (name, value) = (name.trim(), value.trim());
+      if name.eq_ignore_ascii_case("content-length") {
+        content_length = value.parse().map_err(|_| BAD_REQUEST)?;
+      } else if name.eq_ignore_ascii_case("transfer-encoding") {
+        chunked = !value.eq_ignore_ascii_case("identity");
+      }
+    }
+  }
+
+  if chunked {
+    return Err("411 Length Required");
+  }
+
+  if content_length > MAX_BODY_SIZE {
+    return Err("413 Payload Too Large");
+  }
+
+  let mut body = vec![0; content_length];
+  reader
+    .read_exact(&mut body)
+    .await
+    .map_err(|_| BAD_REQUEST)?;
+
+  Ok((method, target, body))
+}
+
+// Reads a single line of at most MAX_LINE_LENGTH bytes, including the line break.
+// Longer lines and lines which end before the line break are rejected.
+async fn read_line<R: AsyncBufRead + Unpin>(
+  reader: &mut R,
+) -> Result<String, &'static str> {
+  let mut line = Vec::new();
+  (&mut *reader)
+    .take(MAX_LINE_LENGTH as u64)
+    .read_until(b'\n', &mut line)
+    .await
+    .map_err(|_| "400 Bad Request")?;
+
+  if !line.ends_with(b"\n") {
+    return Err(if line.len() == MAX_LINE_LENGTH {
+      "431 Request Header Fields Too Large"
+    } else {
+      "400 Bad Request"
+    });
+  }
+
+  String::from_utf8(line).map_err(|_| "400 Bad Request")
+}
+
+// Answers a request to the callback URL. Verification requests of the hubs are GET
+// requests, they are answered with the challenge if the topic matches the subscription.
+// Pushed content is sent with POST requests; it is rejected if it cannot be parsed as a
+// feed. Returns the status and the body of the response and the event which should be
+// sent to the main context, if any.
+fn handle_request(
+  subscriptions: &Subscriptions,
+  method: &str,
+  url: &url::Url,
+  body: Vec<u8>,
+) -> (&'static str, String, Option<WebSubEvent>) {
+  const NOT_FOUND: &str = "404 Not Found";
+
+  let token = url
+    .path_segments()
+    .and_then(|mut segments| segments.next_back())
+    .unwrap_or_default();
+
+  let mut subscriptions = subscriptions.lock().unwrap();
+  let subscription = match subscriptions.get(token) {
+    Some(subscription) => subscription.clone(),
+    None => return (NOT_FOUND, String::new(), None),
+  };
+
+  match method {
+    "GET" => {
+      let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+      let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+
+      if param("hub.topic") != subscription.links.topic {
+        return (NOT_FOUND, String::new(), None);
+      }
+
+      let mode = param("hub.mode");
+
+      if mode == "denied" {
+        subscriptions.remove(token);
+        let event = WebSubEvent::Denied {
+          feed_id: subscription.feed_id,
+          reason: param("hub.reason"),
+        };
+        return ("200 OK", String::new(), Some(event));
+      }
+
+      if mode != subscription.mode {
+        return (NOT_FOUND, String::new(), None);
+      }
+
+      if mode == "unsubscribe" {
+        subscriptions.remove(token);
+      }
+
+      let event = WebSubEvent::Verified {
+        feed_id: subscription.feed_id,
+        token: token.to_string(),
+        mode,
+        lease_seconds: param("hub.lease_seconds").parse().ok(),
+      };
+
+      ("200 OK", param("hub.challenge"), Some(event))
+    }
+    "POST" if subscription.mode == "subscribe" => match parser::parse_feed(&body) {
+      Ok(content) => {
+        let event = WebSubEvent::Content {
+          feed_id: subscription.feed_id,
+          content: Box::new(content),
+        };
+        ("200 OK", String::new(), Some(event))
+      }
+      Err(err) => ("400 Bad Request", err.to_string(), None),
+    },
+    "POST" => ("410 Gone", String::new(), None),
+    _ => ("405 Method Not Allowed", String::new(), None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn links() -> WebSubLinks {
+    WebSubLinks {
+      hub: "https://hub.example.com/".into(),
+      topic: "https://example.com/websub.xml".into(),
+    }
+  }
+
+  fn create_subscriptions(mode: &'static str) -> Subscriptions {
+    let subscription = Subscription {
+      feed_id: "7".into(),
+      links: links(),
+      mode,
+    };
+    Arc::new(Mutex::new(HashMap::from([("abc".into(), subscription)])))
+  }
+
+  fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(path).unwrap()
+  }
+
+  fn request(
+    subscriptions: &Subscriptions,
+    method: &str,
+    target: &str,
+    body: Vec<u8>,
+  ) -> (&'static str, String, Option<WebSubEvent>) {
+    let url = url::Url::parse("http://localhost")
+      .unwrap()
+      .join(target)
+      .unwrap();
+    handle_request(subscriptions, method, &url, body)
+  }
+
+  fn read(data: &[u8]) -> Result<(String, String, Vec<u8>), &'static str> {
+    let mut reader = data;
+    crate::RUNTIME.block_on(read_request(&mut reader))
+  }
+
+  #[test]
+  fn requests() {
+    let (method, target, body) =
+      read(b"POST /websub/abc HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody").unwrap();
+    assert_eq!(
+      (method.as_str(), target.as_str(), body.as_slice()),
+      ("POST", "/websub/abc", b"body".as_slice())
+    );
+
+    let chunked = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n";
+    assert_eq!(read(chunked), Err("411 Length Required"));
+
+    let large = format!(
+      "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+      MAX_BODY_SIZE + 1
+    );
+    assert_eq!(read(large.as_bytes()), Err("413 Payload Too Large"));
+
+    // Incomplete requests, overly long lines, and too many headers are rejected.
+    assert_eq!(
+      read(b"GET / HTTP/1.1\r\nHost: exam"),
+      Err("400 Bad Request")
+    );
+
+    let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_LENGTH));
+    assert_eq!(
+      read(long.as_bytes()),
+      Err("431 Request Header Fields Too Large")
+    );
+
+    let many = format!(
+      "GET / HTTP/1.1\r\n{}\r\n",
+      "X-Header: 1\r\n".repeat(MAX_HEADER_COUNT + 1)
+    );
+    assert_eq!(
+      read(many.as_bytes()),
+      Err("431 Request Header Fields Too Large")
+    );
+  }
+
+  #[test]
+  fn restart() {
+    let (sender, _) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+      .and_then(|listener| listener.local_addr())
+      .unwrap()
+      .port();
+
+    // The port can be bound again right after the server has been stopped, even if it
+    // has accepted a connection before.
+    let server = WebSubServer::start("", "127.0.0.1", port, sender.clone()).unwrap();
+    std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    drop(server);
+    WebSubServer::start("", "127.0.0.1", port, sender.clone()).unwrap();
+
+    // Host names are not supported as local addresses.
+    assert!(WebSubServer::start("", "localhost", port, sender).is_err());
+  }
+
+  #[test]
+  fn hub_links() {
+    let feed = parser::parse_feed(&fixture("websub.xml")).unwrap();
+    assert_eq!(WebSubLinks::from_feed(&feed, "ignored"), Some(links()));
+
+    let feed = parser::parse_feed(&fixture("atom.xml")).unwrap();
+    assert_eq!(WebSubLinks::from_feed(&feed, "ignored"), None);
+  }
+
+  #[test]
+  fn verification() {
+    let subscriptions = create_subscriptions("subscribe");
+    let topic = "hub.topic=https%3A%2F%2Fexample.com%2Fwebsub.xml";
+
+    // Unknown tokens and wrong topics are rejected.
+    let target = format!("/websub/xyz?hub.mode=subscribe&{}&hub.challenge=42", topic);
+    assert_eq!(
+      request(&subscriptions, "GET", &target, vec![]).0,
+      "404 Not Found"
+    );
+
+    let target = "/websub/abc?hub.mode=subscribe&hub.topic=other&hub.challenge=42";
+    assert_eq!(
+      request(&subscriptions, "GET", target, vec![]).0,
+      "404 Not Found"
+    );
+
+    // The challenge is echoed for the subscribed topic.
+    let target = format!(
+      "/websub/abc?hub.mode=subscribe&{}&hub.challenge=42&hub.lease_seconds=600",
+      topic
+    );
+    let (status, body, event) = request(&subscriptions, "GET", &target, vec![]);
+    assert_eq!((status, body.as_str()), ("200 OK", "42"));
+    assert!(matches!(
+      event,
+      Some(WebSubEvent::Verified { feed_id, lease_seconds: Some(600), .. })
+        if feed_id == "7"
+    ));
+
+    // Unsubscriptions are not verified for subscribed feeds.
+    let target = format!(
+      "/websub/abc?hub.mode=unsubscribe&{}&hub.challenge=42",
+      topic
+    );
+    assert_eq!(
+      request(&subscriptions, "GET", &target, vec![]).0,
+      "404 Not Found"
+    );
+
+    // Denied subscriptions are forgotten.
+    let target = format!("/websub/abc?hub.mode=denied&{}", topic);
+    let (_, _, event) = request(&subscriptions, "GET", &target, vec![]);
+    assert!(matches!(event, Some(WebSubEvent::Denied { .. })));
+    assert!(subscriptions.lock().unwrap().is_empty());
+  }
+
+  #[test]
+  fn pushed_content() {
+    let subscriptions = create_subscriptions("subscribe");
+    let (status, _, event) =
+      request(&subscriptions, "POST", "/websub/abc", fixture("websub.xml"));
+    assert_eq!(status, "200 OK");
+    assert!(matches!(
+      event,
+      Some(WebSubEvent::Content { feed_id, content })
+        if feed_id == "7" && content.entries.len() == 1
+    ));
+
+    let (status, _, event) = request(&subscriptions, "POST", "/websub/abc", vec![]);
+    assert_eq!(status, "400 Bad Request");
+    assert!(event.is_none());
+
+    let subscriptions = create_subscriptions("unsubscribe");
+    let (status, _, _) =
+      request(&subscriptions, "POST", "/websub/abc", fixture("websub.xml"));
+    assert_eq!(status, "410 Gone");
+  }
+}
@@ -0,0 +1,107 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::{gio, glib, CompositeTemplate};
+
+use crate::model::FeedItem;
+use crate::view::ItemRow;
+
+// ---------------------------------------------------------------------------------------
+// The AllItemsPage is derived from gtk::Box. It is shown on the right if the "All Items"
+// pseudo-feed is selected in the sidebar. It shows the items of all feeds, newest first.
+// Stories which are contained in multiple feeds are collapsed into a single row; the
+// model assigned with set_model() is expected to contain only one item per story.
+glib::wrapper! {
+  pub struct AllItemsPage(ObjectSubclass<imp::AllItemsPage>)
+      @extends gtk::Widget, gtk::Box,
+      @implements gtk::Accessible, gtk::Buildable, gtk::Orientable;
+}
+
+impl AllItemsPage {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    glib::Object::builder().build()
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // This assigns the list of FeedItems to the AllItemsPage. We show at most 100 rows,
+  // else the performance will degrade too much.
+  pub fn set_model(&self, model: &gio::ListStore) {
+    let slice_model = gtk::SliceListModel::new(Some(model), 0, 100);
+    self
+      .imp()
+      .item_list_box
+      .bind_model(Some(&slice_model), move |item| {
+        let row = ItemRow::new();
+        row.set_item(item.downcast_ref::<FeedItem>().unwrap());
+        row.set_show_feed_title(true);
+        row.upcast()
+      });
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+  // The structure of this custom widget is defined in the AllItemsPage.ui file.
+  #[derive(Debug, Default, CompositeTemplate)]
+  #[template(resource = "/io/github/schneegans/BingeRSS/ui/AllItemsPage.ui")]
+  pub struct AllItemsPage {
+    #[template_child]
+    pub item_list_box: TemplateChild<gtk::ListBox>,
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for AllItemsPage {
+    const NAME: &'static str = "AllItemsPage";
+    type Type = super::AllItemsPage;
+    type ParentType = gtk::Box;
+
+    fn class_init(klass: &mut Self::Class) {
+      klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+      obj.init_template();
+    }
+  }
+
+  impl ObjectImpl for AllItemsPage {
+    // The item list is set up here, whenever a new AllItemsPage is constructed.
+    fn constructed(&self) {
+      self.parent_constructed();
+
+      // The items are sorted by date, so we group them by day with section headers.
+      self
+        .item_list_box
+        .set_header_func(ItemRow::update_section_header);
+
+      // The rows show relative timestamps, so we have to update them periodically.
+      glib::timeout_add_seconds_local(
+        60,
+        glib::clone!(@weak self as this => @default-return glib::Continue(false),
+          move || {
+            ItemRow::update_dates(&this.item_list_box);
+            glib::Continue(true)
+          }
+        ),
+      );
+    }
+  }
+
+  impl WidgetImpl for AllItemsPage {}
+
+  impl BoxImpl for AllItemsPage {}
+}
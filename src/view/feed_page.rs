@@ -10,15 +10,35 @@
 // SPDX-License-Identifier: MIT
 
 use adw::{prelude::*, subclass::prelude::*};
-use gtk::{gdk, gio, glib, CompositeTemplate};
+use gtk::{gio, glib, CompositeTemplate};
+use std::cell::Cell;
 
-use crate::model::{Feed, FeedItem, FeedState};
+use crate::config;
+use crate::model::{
+  Authentication, Credentials, Download, DownloadState, Feed, FeedItem, FeedSource,
+  FeedState, FilterPreset, ItemOrder, ScrapeConfig, TlsConfig,
+};
+use crate::view::{ItemRow, PreferencesWindow};
+
+// The selector preview shows at most this many of the matching items.
+const PREVIEW_ITEMS: usize = 10;
+
+// Only this many rows are shown initially, else the performance will degrade too much.
+const MAX_ROWS: u32 = 50;
 
 // ---------------------------------------------------------------------------------------
 // The FeedPage is derived from gtk::Box. There is one FeedPage shown on the right for
 // each feed. It shows text entries for the feed's title, URL, and filter as well as the
 // actual feed items once downloaded. Depending on the Feed's state, it can also display
-// several info messages.
+// several info messages. Enclosures of feed items can be opened or downloaded with the
+// feed.open-enclosure(url) and feed.download-enclosure(url) actions; the progress of the
+// downloads is shown above the items. Next to the filter entry, a filter preset can be
+// chosen; editing the filter of a feed using a preset detaches the feed from the preset.
+// If scraping is enabled, the CSS selectors for extracting items from a web page can be
+// edited. While typing, a preview shows what the selectors match on the last fetched
+// page. Credentials and custom headers for feeds requiring authentication can be entered
+// as well, and so can a client certificate. Accepting invalid certificates requires a
+// confirmation.
 glib::wrapper! {
   pub struct FeedPage(ObjectSubclass<imp::FeedPage>)
       @extends gtk::Widget, gtk::Box,
@@ -55,12 +75,308 @@ impl FeedPage {
       .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
       .build();
 
-    // Make sure that the actual feed list is filtered whenever the filter value changes.
+    // Sync the Feed's thumbnail setting to the state of the switch. As the thumbnails are
+    // added when the rows are created, all rows are re-created if the setting changes.
     feed
-      .bind_property("filter", &self.imp().filter, "search")
+      .bind_property(
+        "show-thumbnails",
+        &self.imp().thumbnails_switch.get(),
+        "active",
+      )
+      .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
+      .build();
+
+    feed.connect_notify_local(
+      Some("show-thumbnails"),
+      glib::clone!(@weak self as this => move |_, _| {
+        let n = this.imp().model.n_items();
+        this.imp().model.items_changed(0, n, n);
+      }),
+    );
+
+    // Show the scrape config of the Feed. This has to happen before the signal handlers
+    // below are connected, else the config would be applied again right away.
+    if let Some(config) = feed.get_scrape_config().as_ref() {
+      self.imp().item_selector_entry.set_text(&config.item);
+      self.imp().title_selector_entry.set_text(&config.title);
+      self.imp().link_selector_entry.set_text(&config.link);
+      self.imp().date_selector_entry.set_text(&config.date);
+      self.imp().scrape_expander.set_enable_expansion(true);
+    }
+
+    // The selectors are applied to the Feed if the apply button of any of the entries is
+    // clicked or if scraping is enabled or disabled. While typing, only the preview is
+    // updated.
+    for entry in self.get_selector_entries() {
+      entry.connect_apply(glib::clone!(@weak self as this, @weak feed => move |_| {
+        this.apply_scrape_config(&feed);
+      }));
+
+      entry.connect_changed(glib::clone!(@weak self as this, @weak feed => move |_| {
+        this.update_scrape_preview(&feed);
+      }));
+    }
+
+    self.imp().scrape_expander.connect_enable_expansion_notify(
+      glib::clone!(@weak self as this, @weak feed => move |_| {
+        this.apply_scrape_config(&feed);
+        this.update_scrape_preview(&feed);
+      }),
+    );
+
+    self.update_scrape_preview(feed);
+
+    // Show the credentials of the Feed. They are shown again whenever they are changed,
+    // for instance by the dialog which is shown if the server requires authentication.
+    self.load_credentials(feed);
+    feed.connect_notify_local(
+      Some("authenticated"),
+      glib::clone!(@weak self as this => move |feed, _| {
+        this.load_credentials(feed);
+      }),
+    );
+
+    // The entered credentials are applied if the apply button is clicked or if
+    // authentication is enabled. Disabling authentication removes the credentials.
+    self.imp().auth_apply_button.connect_clicked(
+      glib::clone!(@weak self as this, @weak feed => move |_| {
+        this.apply_credentials(&feed);
+      }),
+    );
+
+    self.imp().auth_expander.connect_enable_expansion_notify(
+      glib::clone!(@weak self as this, @weak feed => move |expander| {
+        if this.imp().updating_credentials.get() {
+          return;
+        }
+
+        if expander.enables_expansion() {
+          this.apply_credentials(&feed);
+        } else {
+          feed.set_credentials(None);
+        }
+      }),
+    );
+
+    // Show the TLS config of the Feed. Changed paths are applied with the apply buttons
+    // of the entries.
+    let config = feed.get_tls_config().clone();
+    self
+      .imp()
+      .client_certificate_entry
+      .set_text(&config.client_certificate);
+    self.imp().client_key_entry.set_text(&config.client_key);
+    self
+      .imp()
+      .invalid_certificates_switch
+      .set_active(config.accept_invalid_certificates);
+
+    for entry in [
+      self.imp().client_certificate_entry.get(),
+      self.imp().client_key_entry.get(),
+    ] {
+      entry.connect_apply(glib::clone!(@weak self as this, @weak feed => move |_| {
+        let imp = this.imp();
+        let mut config = feed.get_tls_config().clone();
+        config.client_certificate = imp.client_certificate_entry.text().trim().into();
+        config.client_key = imp.client_key_entry.text().trim().into();
+        feed.set_tls_config(config);
+      }));
+    }
+
+    // Invalid certificates are only accepted after a confirmation. If the switch is
+    // reverted because the confirmation was canceled, its state matches the TlsConfig
+    // again and nothing happens.
+    self
+      .imp()
+      .invalid_certificates_switch
+      .connect_active_notify(
+        glib::clone!(@weak self as this, @weak feed => move |switch| {
+          let mut config = feed.get_tls_config().clone();
+
+          if switch.is_active() == config.accept_invalid_certificates {
+            return;
+          }
+
+          if switch.is_active() {
+            this.confirm_invalid_certificates(&feed);
+          } else {
+            config.accept_invalid_certificates = false;
+            feed.set_tls_config(config);
+          }
+        }),
+      );
+
+    // The Feed decides which items match its filter. Make sure that the actual feed list
+    // is filtered again whenever the filter value changes.
+    self.imp().filter.set_filter_func(
+      glib::clone!(@weak feed => @default-return true, move |item| {
+        feed.matches_filter(item.downcast_ref::<FeedItem>().unwrap())
+      }),
+    );
+
+    feed.connect_notify_local(
+      Some("filter"),
+      glib::clone!(@weak self as this => move |_, _| {
+        this.imp().filter.changed(gtk::FilterChange::Different);
+      }),
+    );
+
+    // The drop-down next to the filter entry allows choosing a filter preset. Choosing a
+    // preset copies its filter to the Feed. The list of presets is updated whenever the
+    // presets change.
+    self.update_presets(feed);
+    self.imp().settings.connect_changed(
+      Some("filter-presets"),
+      glib::clone!(@weak self as this, @weak feed => move |_, _| {
+        this.update_presets(&feed);
+      }),
+    );
+
+    self.imp().preset_drop_down.connect_selected_notify(
+      glib::clone!(@weak self as this, @weak feed => move |drop_down| {
+        if this.imp().updating_presets.get() {
+          return;
+        }
+
+        // The first entry stands for a manually entered filter.
+        let presets = PreferencesWindow::get_filter_presets(&this.imp().settings);
+        let index = (drop_down.selected() as usize).wrapping_sub(1);
+
+        match presets.get(index) {
+          Some(preset) => {
+            feed.set_property("preset", &preset.name);
+            feed.set_property("filter", &preset.filter);
+          }
+          None => feed.set_property("preset", ""),
+        }
+      }),
+    );
+
+    // If the filter of a Feed which uses a preset is edited, the Feed stops using the
+    // preset. Presets themselves can only be edited in the preferences. When a preset is
+    // chosen or changed, the filter is set to the preset's filter, so the Feed stays
+    // attached in these cases.
+    feed.connect_notify_local(
+      Some("filter"),
+      glib::clone!(@weak self as this => move |feed, _| {
+        let presets = PreferencesWindow::get_filter_presets(&this.imp().settings);
+        let filter = FilterPreset::get_filter(&presets, &feed.get_preset());
+
+        if filter.map_or(false, |filter| filter != *feed.get_filter()) {
+          feed.set_property("preset", "");
+          this.update_presets(feed);
+        }
+      }),
+    );
+
+    // Items matching the global mute rules are hidden. Whenever the rules change, the
+    // list has to be filtered again. The number of hidden items is shown in the
+    // description of the item list.
+    feed.connect_notify_local(
+      Some("muted"),
+      glib::clone!(@weak self as this => move |_, _| {
+        this.imp().mute_filter.changed(gtk::FilterChange::Different);
+      }),
+    );
+
+    feed
+      .bind_property("muted", &self.imp().items_group.get(), "description")
+      .transform_to(|_, n: i32| match n {
+        0 => Some(String::new()),
+        1 => Some(String::from("One item is hidden by the mute rules.")),
+        n => Some(format!("{} items are hidden by the mute rules.", n)),
+      })
       .flags(glib::BindingFlags::SYNC_CREATE)
       .build();
 
+    // Sort the feed items according to the Feed's order. Items which are pinned by a
+    // highlight rule are always shown first.
+    self.imp().sorter.set_sort_func(
+      glib::clone!(@weak feed => @default-return gtk::Ordering::Equal, move |a, b| {
+        let a = a.downcast_ref::<FeedItem>().unwrap();
+        let b = b.downcast_ref::<FeedItem>().unwrap();
+        b.is_pinned()
+          .cmp(&a.is_pinned())
+          .then(feed.get_order().compare(a, b, *feed.get_viewed()))
+          .into()
+      }),
+    );
+
+    // The highlight styles are applied when the rows are created, so all rows are
+    // re-created and re-sorted if the highlight rules change.
+    feed.connect_notify_local(
+      Some("highlighted"),
+      glib::clone!(@weak self as this => move |_, _| {
+        let n = this.imp().model.n_items();
+        this.imp().model.items_changed(0, n, n);
+      }),
+    );
+
+    // Re-sort the items whenever the order changes. As unread items may be shown first,
+    // we also have to do this when the feed is viewed.
+    for property in ["order", "viewed"] {
+      feed.connect_notify_local(
+        Some(property),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.imp().sorter.changed(gtk::SorterChange::Different);
+        }),
+      );
+    }
+
+    // If the items are sorted by date, we group them by day with section headers.
+    self.imp().feed_item_list_box.set_header_func(
+      glib::clone!(@weak feed => move |row, before| {
+        let order = *feed.get_order();
+        if order == ItemOrder::NewestFirst || order == ItemOrder::OldestFirst {
+          ItemRow::update_section_header(row, before);
+        } else {
+          row.set_header(gtk::Widget::NONE);
+        }
+      }),
+    );
+
+    // The order menu in the header of the item list changes the Feed's order property
+    // via this action.
+    let actions = gio::SimpleActionGroup::new();
+    actions.add_action(&gio::PropertyAction::new("order", feed, "order"));
+
+    // The media buttons of the ItemRows open the enclosure of an item in the default
+    // application for its type...
+    {
+      let action =
+        gio::SimpleAction::new("open-enclosure", Some(glib::VariantTy::STRING));
+      action.connect_activate(|_, url| {
+        let url = url.and_then(|url| url.get::<String>()).unwrap_or_default();
+        let result =
+          gio::AppInfo::launch_default_for_uri(&url, gio::AppLaunchContext::NONE);
+        if result.is_err() {
+          println!("Failed to open URL {}", url);
+        }
+      });
+      actions.add_action(&action);
+    }
+
+    // ... or download it to the configured download folder.
+    {
+      let action =
+        gio::SimpleAction::new("download-enclosure", Some(glib::VariantTy::STRING));
+      action.connect_activate(glib::clone!(@weak self as this => move |_, url| {
+        let url = url.and_then(|url| url.get::<String>()).unwrap_or_default();
+        let folder = PreferencesWindow::get_download_folder(&this.imp().settings);
+
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+          println!("Failed to create folder {}: {}", folder.display(), err);
+          return;
+        }
+
+        this.imp().downloads.append(&Download::new(&url, &folder));
+      }));
+      actions.add_action(&action);
+    }
+
+    self.insert_action_group("feed", Some(&actions));
+
     // Depending on the Feed's state, we show and hide several components of the FeedPage.
     feed.connect_notify_local(
       Some("state"),
@@ -73,15 +389,401 @@ impl FeedPage {
         } else if state == FeedState::DownloadStarted {
           this.imp().stack.set_visible_child_name("spinner");
         } else if state == FeedState::DownloadFailed {
+          this.update_error_page(feed);
           this.imp().stack.set_visible_child_name("connection_error_message");
+        } else if state == FeedState::FileMissing {
+          this.imp().stack.set_visible_child_name("file_missing_message");
+        } else if state == FeedState::AuthenticationRequired {
+          this.imp().stack.set_visible_child_name("auth_required_message");
         } else if state == FeedState::DownloadSucceeded {
           this.imp().stack.set_visible_child_name("feed_items");
           this.imp().model.remove_all();
           this.imp().model.extend_from_slice(&feed.get_items().as_ref());
+          this.imp().slice_model.set_size(MAX_ROWS);
+        }
+
+        // A new page may have been fetched.
+        if state == FeedState::DownloadSucceeded || state == FeedState::DownloadFailed {
+          this.update_scrape_preview(feed);
+        }
+
+      }),
+    );
+  }
+
+  // Scrolls to the row of the item with the given URL and focuses it. If the item is
+  // further down than the rows which are currently shown, more rows are shown. This
+  // returns false if the item is not shown, for instance because it does not match the
+  // filter.
+  pub fn show_item(&self, url: &str) -> bool {
+    let slice_model = &self.imp().slice_model;
+    let sorted = match slice_model.model() {
+      Some(sorted) => sorted,
+      None => return false,
+    };
+
+    let position = (0..sorted.n_items()).find(|i| {
+      sorted
+        .item(*i)
+        .and_then(|item| item.downcast::<FeedItem>().ok())
+        .map_or(false, |item| *item.get_url() == url)
+    });
+
+    match position {
+      Some(position) if position >= slice_model.size() => {
+        slice_model.set_size(position + 1);
+      }
+      Some(_) => {}
+      None => return false,
+    }
+
+    // The rows are created right away when the slice grows.
+    let list = &self.imp().feed_item_list_box;
+    let row = std::iter::successors(list.first_child(), |w| w.next_sibling())
+      .filter_map(|w| w.downcast::<ItemRow>().ok())
+      .find(|row| row.get_item().map_or(false, |item| *item.get_url() == url));
+
+    match row {
+      Some(row) => row.grab_focus(),
+      None => false,
+    }
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // If a command feed failed, the exit status and the error output of the command are
+  // shown on the error page. For other feeds, a generic hint is shown.
+  fn update_error_page(&self, feed: &Feed) {
+    let page = &self.imp().connection_error_page;
+
+    if let FeedSource::Command(_) = FeedSource::from_url(&feed.get_url()) {
+      page.set_title("Command Failed");
+      page.set_description(Some(&feed.get_error()));
+    } else if feed.get_scrape_config().is_some() {
+      page.set_title("Scraping Failed");
+      page.set_description(Some(&feed.get_error()));
+    } else {
+      page.set_title("Connection Error");
+      page.set_description(Some(
+        "Please check the URL or your internet connection and try again.",
+      ));
+    }
+  }
+
+  // Returns the entries for the CSS selectors of the scrape config.
+  fn get_selector_entries(&self) -> [adw::EntryRow; 4] {
+    let imp = self.imp();
+    [
+      imp.item_selector_entry.get(),
+      imp.title_selector_entry.get(),
+      imp.link_selector_entry.get(),
+      imp.date_selector_entry.get(),
+    ]
+  }
+
+  // Returns the scrape config currently entered by the user. If scraping is disabled,
+  // this returns None.
+  fn get_entered_scrape_config(&self) -> Option<ScrapeConfig> {
+    let imp = self.imp();
+
+    if !imp.scrape_expander.enables_expansion() {
+      return None;
+    }
+
+    Some(ScrapeConfig {
+      item: imp.item_selector_entry.text().trim().to_string(),
+      title: imp.title_selector_entry.text().trim().to_string(),
+      link: imp.link_selector_entry.text().trim().to_string(),
+      date: imp.date_selector_entry.text().trim().to_string(),
+    })
+  }
+
+  // Stores the entered scrape config in the Feed. This will download the feed again.
+  fn apply_scrape_config(&self, feed: &Feed) {
+    feed.set_scrape_config(self.get_entered_scrape_config());
+  }
+
+  // Applies the entered selectors to the page fetched by the last download and shows the
+  // first few matching items. Invalid selectors are reported in the preview as well. The
+  // preview is hidden if scraping is disabled.
+  fn update_scrape_preview(&self, feed: &Feed) {
+    let imp = self.imp();
+    let list_box = &imp.scrape_preview_list_box;
+
+    while let Some(row) = list_box.first_child() {
+      list_box.remove(&row);
+    }
+
+    let config = match self.get_entered_scrape_config() {
+      Some(config) => config,
+      None => {
+        imp.scrape_preview_group.set_visible(false);
+        return;
+      }
+    };
+
+    imp.scrape_preview_group.set_visible(true);
+
+    let page = feed.get_page();
+
+    if page.is_empty() {
+      imp
+        .scrape_preview_group
+        .set_description(Some("The page has not been fetched yet."));
+      return;
+    }
+
+    let base = url::Url::parse(&feed.get_url()).ok();
+
+    let items = match config.scrape(&page, base.as_ref()) {
+      Ok(items) => items,
+      Err(err) => {
+        imp.scrape_preview_group.set_description(Some(&err));
+        return;
+      }
+    };
+
+    let description = match items.len() {
+      0 => String::from("The selectors do not match any items."),
+      1 => String::from("The selectors match one item."),
+      n => format!("The selectors match {} items.", n),
+    };
+    imp.scrape_preview_group.set_description(Some(&description));
+
+    for item in items.iter().take(PREVIEW_ITEMS) {
+      let subtitle = if item.url.is_empty() {
+        "No link"
+      } else {
+        item.url.as_str()
+      };
+
+      // The scraped texts may contain characters like '&'.
+      let row = adw::ActionRow::builder()
+        .title(&item.title)
+        .subtitle(subtitle)
+        .use_markup(false)
+        .build();
+
+      let date = item
+        .date
+        .and_then(|date| chrono::NaiveDateTime::from_timestamp_opt(date, 0))
+        .map_or(String::from("No date"), |date| {
+          date.format("%e %B %Y").to_string().trim().to_string()
+        });
+
+      row.add_suffix(&gtk::Label::new(Some(&date)));
+      list_box.append(&row);
+    }
+  }
+
+  // Shows the credentials of the given Feed in the authentication settings. The settings
+  // are only enabled if the Feed has credentials.
+  fn load_credentials(&self, feed: &Feed) {
+    let imp = self.imp();
+    let enabled = feed.get_credentials().is_some();
+    let credentials = feed.get_credentials().clone().unwrap_or_default();
+
+    let (username, password, token) = match &credentials.auth {
+      Authentication::None => ("", "", ""),
+      Authentication::Basic { username, password } => {
+        (username.as_str(), password.as_str(), "")
+      }
+      Authentication::Bearer { token } => ("", "", token.as_str()),
+    };
+
+    imp.updating_credentials.set(true);
+    imp.username_entry.set_text(username);
+    imp.password_entry.set_text(password);
+    imp.token_entry.set_text(token);
+    imp
+      .headers_text_view
+      .buffer()
+      .set_text(&credentials.format_headers());
+    imp.auth_expander.set_enable_expansion(enabled);
+    imp.updating_credentials.set(false);
+  }
+
+  // Stores the entered credentials in the Feed. If a bearer token is entered, it is used
+  // instead of the user name and the password. If the custom headers are invalid, the
+  // error is shown and the credentials of the Feed are not changed.
+  fn apply_credentials(&self, feed: &Feed) {
+    let imp = self.imp();
+    let buffer = imp.headers_text_view.buffer();
+    let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+
+    let headers = match Credentials::parse_headers(&text) {
+      Ok(headers) => headers,
+      Err(err) => {
+        imp.auth_apply_row.set_subtitle(&err);
+        return;
+      }
+    };
+
+    imp
+      .auth_apply_row
+      .set_subtitle("They are stored in the keyring");
+
+    let username = imp.username_entry.text().trim().to_string();
+    let password = imp.password_entry.text().to_string();
+    let token = imp.token_entry.text().trim().to_string();
+
+    let auth = if !token.is_empty() {
+      Authentication::Bearer { token }
+    } else if !username.is_empty() {
+      Authentication::Basic { username, password }
+    } else {
+      Authentication::None
+    };
+
+    feed.set_credentials(Some(Credentials { auth, headers }));
+  }
+
+  // Asks the user whether invalid certificates should really be accepted for the host of
+  // the given Feed. If so, the TlsConfig of the Feed is updated, else the switch is
+  // turned off again.
+  fn confirm_invalid_certificates(&self, feed: &Feed) {
+    let host = url::Url::parse(&feed.get_url())
+      .ok()
+      .and_then(|url| url.host_str().map(str::to_string))
+      .unwrap_or_else(|| feed.get_url().clone());
+
+    let dialog = adw::MessageDialog::builder()
+      .modal(true)
+      .heading("Accept Invalid Certificates?")
+      .body(&format!(
+        "The certificate of {} will not be verified anymore. Anyone who can intercept \
+         the connection will be able to read and modify this feed. Only continue if you \
+         trust the network and the server.",
+        host
+      ))
+      .build();
+    dialog.set_transient_for(
+      self
+        .root()
+        .and_then(|root| root.downcast::<gtk::Window>().ok())
+        .as_ref(),
+    );
+    dialog.add_response("cancel", "_Cancel");
+    dialog.add_response("accept", "_Accept");
+    dialog.set_response_appearance("accept", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    dialog.connect_response(
+      None,
+      glib::clone!(@weak self as this, @weak feed => move |_, response| {
+        if response == "accept" {
+          feed.set_tls_config(TlsConfig {
+            accept_invalid_certificates: true,
+            ..feed.get_tls_config().clone()
+          });
+        } else {
+          this.imp().invalid_certificates_switch.set_active(false);
         }
+      }),
+    );
+
+    dialog.present();
+  }
 
+  // Shows all filter presets in the drop-down next to the filter entry and selects the
+  // preset used by the given Feed. The drop-down is hidden if there are no presets.
+  fn update_presets(&self, feed: &Feed) {
+    let imp = self.imp();
+    let presets = PreferencesWindow::get_filter_presets(&imp.settings);
+
+    let mut names = vec!["Custom"];
+    names.extend(presets.iter().map(|p| p.name.as_str()));
+
+    let selected = presets
+      .iter()
+      .position(|p| p.name == *feed.get_preset())
+      .map_or(0, |i| i + 1);
+
+    imp.updating_presets.set(true);
+    imp
+      .preset_drop_down
+      .set_model(Some(&gtk::StringList::new(&names)));
+    imp.preset_drop_down.set_selected(selected as u32);
+    imp.updating_presets.set(false);
+
+    imp.preset_drop_down.set_visible(!presets.is_empty());
+  }
+
+  // Creates a row for the list of downloads. It shows the file name and a progress bar.
+  // Once the download is finished, the row can be activated to open the file. Finished
+  // and failed downloads can be removed from the list.
+  fn create_download_row(&self, download: &Download) -> gtk::Widget {
+    let path = std::path::PathBuf::from(&*download.get_path());
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let row = adw::ActionRow::builder()
+      .title(&name)
+      .subtitle("Downloading…")
+      .build();
+
+    let progress_bar = gtk::ProgressBar::builder()
+      .valign(gtk::Align::Center)
+      .build();
+    row.add_suffix(&progress_bar);
+
+    let remove_button = gtk::Button::builder()
+      .icon_name("window-close-symbolic")
+      .tooltip_text("Remove from list")
+      .valign(gtk::Align::Center)
+      .visible(false)
+      .build();
+    remove_button.add_css_class("flat");
+    row.add_suffix(&remove_button);
+
+    download.connect_notify_local(
+      Some("progress"),
+      glib::clone!(@weak progress_bar => move |download, _| {
+        if download.get_progress() < 0.0 {
+          progress_bar.pulse();
+        } else {
+          progress_bar.set_fraction(download.get_progress());
+        }
       }),
     );
+
+    download.connect_notify_local(
+      Some("state"),
+      glib::clone!(@weak row, @weak progress_bar, @weak remove_button =>
+        move |download, _| {
+          let state = download.get_state();
+          progress_bar.set_visible(state == DownloadState::Running);
+          remove_button.set_visible(state != DownloadState::Running);
+
+          if state == DownloadState::Finished {
+            row.set_subtitle(&format!("Saved to {}", download.get_path()));
+            row.set_activatable(true);
+          } else if state == DownloadState::Failed {
+            row.set_subtitle("Download failed");
+          }
+        }
+      ),
+    );
+
+    // Open the downloaded file if the row is activated.
+    row.connect_activated(move |_| {
+      let uri = gio::File::for_path(&path).uri();
+      let result =
+        gio::AppInfo::launch_default_for_uri(&uri, gio::AppLaunchContext::NONE);
+      if result.is_err() {
+        println!("Failed to open file {}", path.display());
+      }
+    });
+
+    remove_button.connect_clicked(
+      glib::clone!(@weak self as this, @weak download => move |_| {
+        if let Some(i) = this.imp().downloads.find(&download) {
+          this.imp().downloads.remove(i);
+        }
+      }),
+    );
+
+    row.upcast()
   }
 }
 
@@ -100,12 +802,65 @@ mod imp {
     #[template_child]
     pub filter_entry: TemplateChild<adw::EntryRow>,
     #[template_child]
+    pub preset_drop_down: TemplateChild<gtk::DropDown>,
+    #[template_child]
+    pub thumbnails_switch: TemplateChild<gtk::Switch>,
+    #[template_child]
+    pub scrape_expander: TemplateChild<adw::ExpanderRow>,
+    #[template_child]
+    pub item_selector_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub title_selector_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub link_selector_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub date_selector_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub auth_expander: TemplateChild<adw::ExpanderRow>,
+    #[template_child]
+    pub username_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub password_entry: TemplateChild<adw::PasswordEntryRow>,
+    #[template_child]
+    pub token_entry: TemplateChild<adw::PasswordEntryRow>,
+    #[template_child]
+    pub headers_text_view: TemplateChild<gtk::TextView>,
+    #[template_child]
+    pub auth_apply_row: TemplateChild<adw::ActionRow>,
+    #[template_child]
+    pub auth_apply_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub client_certificate_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub client_key_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub invalid_certificates_switch: TemplateChild<gtk::Switch>,
+    #[template_child]
+    pub scrape_preview_group: TemplateChild<adw::PreferencesGroup>,
+    #[template_child]
+    pub scrape_preview_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub downloads_group: TemplateChild<adw::PreferencesGroup>,
+    #[template_child]
+    pub download_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
     pub stack: TemplateChild<gtk::Stack>,
     #[template_child]
+    pub connection_error_page: TemplateChild<adw::StatusPage>,
+    #[template_child]
+    pub items_group: TemplateChild<adw::PreferencesGroup>,
+    #[template_child]
     pub feed_item_list_box: TemplateChild<gtk::ListBox>,
 
     pub model: gio::ListStore,
-    pub filter: gtk::StringFilter,
+    pub filter: gtk::CustomFilter,
+    pub mute_filter: gtk::BoolFilter,
+    pub sorter: gtk::CustomSorter,
+    pub slice_model: gtk::SliceListModel,
+    pub downloads: gio::ListStore,
+    pub settings: gio::Settings,
+    pub updating_presets: Cell<bool>,
+    pub updating_credentials: Cell<bool>,
   }
 
   impl Default for FeedPage {
@@ -114,18 +869,47 @@ mod imp {
         title_entry: TemplateChild::default(),
         url_entry: TemplateChild::default(),
         filter_entry: TemplateChild::default(),
+        preset_drop_down: TemplateChild::default(),
+        thumbnails_switch: TemplateChild::default(),
+        scrape_expander: TemplateChild::default(),
+        item_selector_entry: TemplateChild::default(),
+        title_selector_entry: TemplateChild::default(),
+        link_selector_entry: TemplateChild::default(),
+        date_selector_entry: TemplateChild::default(),
+        auth_expander: TemplateChild::default(),
+        username_entry: TemplateChild::default(),
+        password_entry: TemplateChild::default(),
+        token_entry: TemplateChild::default(),
+        headers_text_view: TemplateChild::default(),
+        auth_apply_row: TemplateChild::default(),
+        auth_apply_button: TemplateChild::default(),
+        client_certificate_entry: TemplateChild::default(),
+        client_key_entry: TemplateChild::default(),
+        invalid_certificates_switch: TemplateChild::default(),
+        scrape_preview_group: TemplateChild::default(),
+        scrape_preview_list_box: TemplateChild::default(),
+        downloads_group: TemplateChild::default(),
+        download_list_box: TemplateChild::default(),
         stack: TemplateChild::default(),
+        connection_error_page: TemplateChild::default(),
+        items_group: TemplateChild::default(),
         feed_item_list_box: TemplateChild::default(),
         model: gio::ListStore::new(FeedItem::static_type()),
-        filter: gtk::StringFilter::builder()
-          .ignore_case(true)
-          .match_mode(gtk::StringFilterMatchMode::Substring)
+        filter: gtk::CustomFilter::new(|_| true),
+        mute_filter: gtk::BoolFilter::builder()
+          .invert(true)
           .expression(gtk::PropertyExpression::new(
             FeedItem::static_type(),
             gtk::Expression::NONE,
-            "title",
+            "muted",
           ))
           .build(),
+        sorter: gtk::CustomSorter::new(|_, _| gtk::Ordering::Equal),
+        slice_model: gtk::SliceListModel::new(gio::ListModel::NONE, 0, MAX_ROWS),
+        downloads: gio::ListStore::new(Download::static_type()),
+        settings: gio::Settings::new(config::APP_ID),
+        updating_presets: Cell::new(false),
+        updating_credentials: Cell::new(false),
       }
     }
   }
@@ -152,47 +936,58 @@ mod imp {
     fn constructed(&self) {
       self.parent_constructed();
 
-      // Wire up everything. We show at most 50 rows, else the performance will degrade
-      // too much. We could use a gtk::ListView, however this would require nesting
-      // directly inside a gtk::ScrolledWindow which in turn would require a redesign of
-      // the user interface.
-      let filter_model = gtk::FilterListModel::new(Some(&self.model), Some(&self.filter));
-      let slice_model = gtk::SliceListModel::new(Some(&filter_model), 0, 50);
-      self
-        .feed_item_list_box
-        .bind_model(Some(&slice_model), move |item| {
-          // The item's title is shown on each row.
-          let title: String = item.property("title");
-          let row = adw::ActionRow::builder()
-            .title(&title)
-            .title_lines(2)
-            .selectable(false)
-            .activatable(true)
-            .use_markup(false)
-            .build();
-
-          // Add an icon as suffix to each row.
-          let icon = gtk::Image::builder()
-            .icon_name("adw-external-link-symbolic")
-            .build();
-          row.add_suffix(&icon);
-
-          // Open the item's URL if the row is activated.
-          let url: String = item.property("url");
-          row.connect_activated(move |_| {
-            let result =
-              gio::AppInfo::launch_default_for_uri(&url, gio::AppLaunchContext::NONE);
-            if result.is_err() {
-              println!("Failed to open URL {}", url);
-            }
-          });
+      // Wire up everything. The items are first filtered, then sorted. We show at most
+      // MAX_ROWS rows, unless show_item() needs more. We could use a gtk::ListView,
+      // however this would require nesting directly inside a gtk::ScrolledWindow which
+      // in turn would require a redesign of the user interface. Only items which match
+      // the feed's filter and are not muted are shown.
+      let filter = gtk::EveryFilter::new();
+      filter.append(&self.filter);
+      filter.append(&self.mute_filter);
+      let filter_model = gtk::FilterListModel::new(Some(&self.model), Some(&filter));
+      let sort_model = gtk::SortListModel::new(Some(&filter_model), Some(&self.sorter));
+      self.slice_model.set_model(Some(&sort_model));
+      // The thumbnails are only loaded for the rows which are actually shown.
+      self.feed_item_list_box.bind_model(
+        Some(&self.slice_model),
+        glib::clone!(@weak self as this => @default-panic, move |item| {
+          let row = ItemRow::new();
+          row.set_item(item.downcast_ref::<FeedItem>().unwrap());
+          if this.thumbnails_switch.is_active() {
+            row.show_thumbnail();
+          }
+          row.upcast()
+        }),
+      );
 
-          // Make the cursor change to a pointer if hovering over the item list. This
-          // increases the affordance of clickable links.
-          row.set_cursor(Some(&gdk::Cursor::from_name("pointer", None).unwrap()));
+      // Show a row for each download of an enclosure. The list is hidden if there are no
+      // downloads.
+      self.download_list_box.bind_model(
+        Some(&self.downloads),
+        glib::clone!(@weak self as this => @default-panic, move |download| {
+          this
+            .obj()
+            .create_download_row(download.downcast_ref::<Download>().unwrap())
+        }),
+      );
 
-          row.ancestor(gtk::Widget::static_type()).unwrap()
-        });
+      self
+        .downloads
+        .bind_property("n-items", &self.downloads_group.get(), "visible")
+        .transform_to(|_, n: u32| Some(n > 0))
+        .flags(glib::BindingFlags::SYNC_CREATE)
+        .build();
+
+      // The rows show relative timestamps, so we have to update them periodically.
+      glib::timeout_add_seconds_local(
+        60,
+        glib::clone!(@weak self as this => @default-return glib::Continue(false),
+          move || {
+            ItemRow::update_dates(&this.feed_item_list_box);
+            glib::Continue(true)
+          }
+        ),
+      );
     }
   }
 
@@ -11,13 +11,16 @@
 
 use adw::{prelude::*, subclass::prelude::*};
 use gtk::{glib, CompositeTemplate};
+use std::cell::RefCell;
 
-use crate::model::{Feed, FeedState};
+use crate::model::{Feed, FeedSource, FeedState};
 
 // ---------------------------------------------------------------------------------------
 // The FeedRow is derived from adw::ActionRow. There is one FeedRow shown in the sidebar
 // on the left for each feed. It shows the feed's title and icon. Depending on the Feed's
-// state, it can show a spinner and several error messages.
+// state, it can show a spinner and several error messages. The number of unread items is
+// shown in a badge; unread items matching important highlight rules are counted in a
+// separate badge.
 glib::wrapper! {
   pub struct FeedRow(ObjectSubclass<imp::FeedRow>)
       @extends adw::ActionRow, adw::PreferencesRow, gtk::ListBoxRow, gtk::Widget,
@@ -36,6 +39,8 @@ impl FeedRow {
   // This assigns a Feed to the FeedRow. The method will bind some properties of the
   // FeedRow to the properties of the Feed.
   pub fn set_feed(&self, feed: &Feed) {
+    self.imp().feed.replace(Some(feed.clone()));
+
     // Show the Feed's title.
     feed
       .bind_property("title", self, "title")
@@ -62,9 +67,22 @@ impl FeedRow {
         this.imp().avatar.set_custom_image(feed.get_icon().as_ref());
         this.imp().avatar.set_icon_name(Some("network-no-route-symbolic"));
         this.set_subtitle("");
+        this.set_tooltip_text(None);
+
+        let is_command = matches!(
+          FeedSource::from_url(&feed.get_url()),
+          FeedSource::Command(_)
+        );
 
-        if state == FeedState::DownloadFailed {
+        if state == FeedState::DownloadFailed && is_command {
+          this.set_subtitle("Command failed");
+          this.set_tooltip_text(Some(&feed.get_error()));
+        } else if state == FeedState::DownloadFailed {
           this.set_subtitle("Connection failed");
+        } else if state == FeedState::FileMissing {
+          this.set_subtitle("File not found");
+        } else if state == FeedState::AuthenticationRequired {
+          this.set_subtitle("Authentication required");
         } else if state == FeedState::EmptyURL {
           this.set_subtitle("Empty URL");
         } else if state == FeedState::DownloadSucceeded {
@@ -73,16 +91,43 @@ impl FeedRow {
       }),
     );
 
-    // Show the number of unread feed items in a badge.
+    // Show the number of unread feed items matching the feed's filter in a badge. If
+    // there are unread items which do not match the filter, the total number is shown
+    // in the tooltip of the badge.
+    for property in ["unread", "total-unread"] {
+      feed.connect_notify_local(
+        Some(property),
+        glib::clone!(@weak self as this => move |feed, _| {
+          let count = feed.get_unread();
+          let total = feed.get_total_unread();
+          this.imp().badge.set_visible(count > 0);
+          this.imp().badge.set_label(&count.to_string());
+
+          if total > count {
+            let tooltip = format!("{} unread items, {} in total", count, total);
+            this.imp().badge.set_tooltip_text(Some(&tooltip));
+          } else {
+            this.imp().badge.set_tooltip_text(None);
+          }
+        }),
+      );
+    }
+
+    // Show the number of important unread feed items in a separate badge.
     feed.connect_notify_local(
-      Some("unread"),
+      Some("important"),
       glib::clone!(@weak self as this => move |feed, _| {
-        let count = feed.get_unread();
-        this.imp().badge.set_visible(count > 0);
-        this.imp().badge.set_label(&feed.get_unread().to_string());
+        let count = feed.get_important();
+        this.imp().important_badge.set_visible(count > 0);
+        this.imp().important_badge.set_label(&count.to_string());
       }),
     );
   }
+
+  // Returns the Feed which has been assigned with set_feed() before.
+  pub fn get_feed(&self) -> Option<Feed> {
+    self.imp().feed.borrow().clone()
+  }
 }
 
 mod imp {
@@ -98,7 +143,11 @@ mod imp {
     #[template_child]
     pub avatar: TemplateChild<adw::Avatar>,
     #[template_child]
+    pub important_badge: TemplateChild<gtk::Label>,
+    #[template_child]
     pub badge: TemplateChild<gtk::Label>,
+
+    pub feed: RefCell<Option<Feed>>,
   }
 
   impl Default for FeedRow {
@@ -106,7 +155,9 @@ mod imp {
       Self {
         spinner: TemplateChild::default(),
         avatar: TemplateChild::default(),
+        important_badge: TemplateChild::default(),
         badge: TemplateChild::default(),
+        feed: RefCell::new(None),
       }
     }
   }
@@ -0,0 +1,497 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use adw::{prelude::*, subclass::prelude::*};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
+use gtk::{gdk, gio, glib, CompositeTemplate};
+use std::cell::{Cell, RefCell};
+
+use crate::model::{DateSource, FeedItem, ThumbnailCache};
+
+// ---------------------------------------------------------------------------------------
+// The ItemRow is derived from adw::ActionRow. It is used to show a single FeedItem in the
+// item lists of the FeedPage and the StarredPage. It shows the item's title, a relative
+// timestamp of the item's publication date, and a button for starring the item. If the
+// row is activated, the item's URL is opened in the default browser. Items with an
+// enclosure additionally show a media badge, the duration of the enclosure, and buttons
+// which call the feed.open-enclosure() and feed.download-enclosure() actions. These
+// actions are provided by the FeedPage. Optionally, a thumbnail image of the item can be
+// shown at the start of the row. Activating the row marks the item and all its duplicates
+// in other feeds as read via the app.mark-read() action; read items are shown dimmed.
+// Items matching highlight rules get the CSS classes of the rules' styles.
+glib::wrapper! {
+  pub struct ItemRow(ObjectSubclass<imp::ItemRow>)
+      @extends adw::ActionRow, adw::PreferencesRow, gtk::ListBoxRow, gtk::Widget,
+      @implements gtk::Accessible, gtk::Buildable, gtk::Actionable;
+}
+
+impl ItemRow {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    glib::Object::builder().build()
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // This assigns a FeedItem to the ItemRow. The method will bind some properties of the
+  // ItemRow to the properties of the FeedItem.
+  pub fn set_item(&self, item: &FeedItem) {
+    self.imp().item.replace(Some(item.clone()));
+
+    // The item's title is shown on each row, the publication date as subtitle.
+    self.set_title(&item.get_title());
+    self.update_date();
+
+    // The star button calls the app.toggle-star() action. As the starred items are stored
+    // independently of their feeds, we pass all information on the item to the action.
+    let stored = serde_json::to_string(&item.to_stored()).unwrap();
+    self
+      .imp()
+      .star_button
+      .set_action_target_value(Some(&stored.to_variant()));
+
+    // Show a filled star if the item is starred.
+    item
+      .bind_property("starred", &self.imp().star_button.get(), "icon-name")
+      .transform_to(|_, starred: bool| {
+        Some(if starred {
+          "starred-symbolic"
+        } else {
+          "non-starred-symbolic"
+        })
+      })
+      .flags(glib::BindingFlags::SYNC_CREATE)
+      .build();
+
+    // Read items are shown dimmed.
+    let update_read_state = glib::clone!(@weak self as this => move |item: &FeedItem| {
+      if item.is_read() {
+        this.add_css_class("item-read");
+      } else {
+        this.remove_css_class("item-read");
+      }
+    });
+
+    // As the rows are re-created whenever the item list changes, the handler is
+    // disconnected again when the row is disposed.
+    update_read_state(item);
+    let handler =
+      item.connect_notify_local(Some("read"), move |item, _| update_read_state(item));
+    self.imp().read_handler.replace(Some(handler));
+
+    // Emphasize the item if it matches any highlight rules.
+    for style in item.get_highlights().iter() {
+      self.add_css_class(style.get_css_class());
+    }
+
+    // Show the media badge and buttons if the item has an enclosure.
+    if item.has_enclosure() {
+      self.setup_enclosure(item);
+    }
+
+    // Items without a usable link cannot be activated. As starred items are identified by
+    // their URL, they cannot be starred either.
+    let url = item.get_url().clone();
+    if url.is_empty() {
+      self.set_activatable(false);
+      self.imp().link_icon.set_visible(false);
+      self.imp().star_button.set_visible(false);
+      return;
+    }
+
+    // Open the item's URL if the row is activated and mark it as read.
+    self.connect_activated(move |this| {
+      this
+        .activate_action("app.mark-read", Some(&url.to_variant()))
+        .ok();
+
+      let result =
+        gio::AppInfo::launch_default_for_uri(&url, gio::AppLaunchContext::NONE);
+      if result.is_err() {
+        println!("Failed to open URL {}", url);
+      }
+    });
+
+    // Make the cursor change to a pointer if hovering over the item list. This increases
+    // the affordance of clickable links.
+    self.set_cursor(Some(&gdk::Cursor::from_name("pointer", None).unwrap()));
+  }
+
+  // Shows the thumbnail image of the assigned FeedItem at the start of the row. The image
+  // is loaded asynchronously via the ThumbnailCache. This does nothing if the item has no
+  // thumbnail.
+  pub fn show_thumbnail(&self) {
+    let url = self
+      .get_item()
+      .map(|item| item.get_thumbnail().clone())
+      .unwrap_or_default();
+
+    if url.is_empty() {
+      return;
+    }
+
+    let ctx = glib::MainContext::default();
+    ctx.spawn_local(glib::clone!(@weak self as this => async move {
+      if let Some(texture) = ThumbnailCache::load(url).await {
+        this.imp().thumbnail.set_paintable(Some(&texture));
+        this.imp().thumbnail.set_visible(true);
+      }
+    }));
+  }
+
+  // Returns the FeedItem which has been assigned with set_item() before.
+  pub fn get_item(&self) -> Option<FeedItem> {
+    self.imp().item.borrow().clone()
+  }
+
+  // If set to true, the title of the feed the item belongs to is shown in the subtitle
+  // next to the publication date. This is useful for lists containing items from
+  // multiple feeds.
+  pub fn set_show_feed_title(&self, show: bool) {
+    self.imp().show_feed_title.set(show);
+    self.update_date();
+  }
+
+  // Updates the relative timestamp and the list of feeds containing duplicates shown in
+  // the subtitle. As the timestamp is relative to the current time, this should be called
+  // periodically.
+  pub fn update_date(&self) {
+    let item = self.imp().item.borrow();
+    if item.is_none() {
+      return;
+    }
+
+    let item = item.as_ref().unwrap();
+    let mut parts = vec![];
+
+    if self.imp().show_feed_title.get() && !item.get_feed_title().is_empty() {
+      parts.push(item.get_feed_title().clone());
+    }
+
+    // Mention other feeds which contain the same story.
+    if !item.get_also_in().is_empty() {
+      parts.push(format!("Also in {}", item.get_also_in().join(", ")));
+    }
+
+    // Dates which are not given by the feed itself are marked as estimates.
+    if item.get_date() > 0 {
+      let date = format_date(item.get_date(), &Local::now());
+      match item.get_date_source() {
+        DateSource::Published => parts.push(date),
+        DateSource::Updated => parts.push(format!("Updated {}", date)),
+        DateSource::FirstSeen => parts.push(format!("First seen {}", date)),
+      }
+    }
+
+    self.set_subtitle(&parts.join(" · "));
+  }
+
+  // Calls update_date() on all ItemRows of the given gtk::ListBox and re-creates its
+  // section headers. This should be called periodically for all lists of ItemRows.
+  pub fn update_dates(list_box: &gtk::ListBox) {
+    let mut i = 0;
+    while let Some(row) = list_box.row_at_index(i) {
+      if let Ok(row) = row.downcast::<ItemRow>() {
+        row.update_date();
+      }
+      i += 1;
+    }
+
+    list_box.invalidate_headers();
+  }
+
+  // This can be used as a header function for a gtk::ListBox containing ItemRows which
+  // are sorted by date. It inserts section headers like "Today" or "Yesterday" whenever
+  // the day section of an item differs from the section of the item before.
+  pub fn update_section_header(row: &gtk::ListBoxRow, before: Option<&gtk::ListBoxRow>) {
+    let section = |row: &gtk::ListBoxRow| {
+      row
+        .downcast_ref::<ItemRow>()
+        .and_then(|r| r.get_item())
+        .map(|i| get_section(i.get_date(), &Local::now()))
+    };
+
+    let current = section(row);
+    let previous = before.and_then(section);
+
+    if current.is_none() || current == previous {
+      row.set_header(gtk::Widget::NONE);
+      return;
+    }
+
+    let label = gtk::Label::builder()
+      .label(current.unwrap())
+      .xalign(0.0)
+      .build();
+    label.add_css_class("heading");
+    label.add_css_class("item-section-header");
+    row.set_header(Some(&label));
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Shows the media badge, the duration, and the buttons for the enclosure of the given
+  // item. The buttons pass the URL of the enclosure to their actions.
+  fn setup_enclosure(&self, item: &FeedItem) {
+    let imp = self.imp();
+    imp.media_box.set_visible(true);
+
+    let mime_type = item.get_enclosure_type().clone();
+    imp
+      .media_icon
+      .set_icon_name(Some(if mime_type.starts_with("video/") {
+        "video-x-generic-symbolic"
+      } else if mime_type.starts_with("audio/") {
+        "audio-x-generic-symbolic"
+      } else {
+        "mail-attachment-symbolic"
+      }));
+
+    // The tooltip of the badge shows the MIME type and the size of the enclosure.
+    let mut tooltip = vec![];
+    if !mime_type.is_empty() {
+      tooltip.push(mime_type);
+    }
+    if item.get_enclosure_length() > 0 {
+      tooltip.push(glib::format_size(item.get_enclosure_length()).to_string());
+    }
+    imp.media_icon.set_tooltip_text(Some(&tooltip.join(", ")));
+
+    let duration = item.get_enclosure_duration();
+    imp.duration_label.set_visible(duration > 0);
+    imp.duration_label.set_label(&format_duration(duration));
+
+    let url = item.get_enclosure_url().to_variant();
+    imp.play_button.set_action_target_value(Some(&url));
+    imp.download_button.set_action_target_value(Some(&url));
+  }
+}
+
+// Formats the given unix timestamp relative to the given current time. For example, this
+// returns "5 min ago", "14:02", "Yesterday 14:02", or "Monday 14:02" for recent dates and
+// the full date for older dates.
+fn format_date(date: i64, now: &DateTime<Local>) -> String {
+  let date = match Local.timestamp_opt(date, 0).single() {
+    Some(date) => date,
+    None => return String::new(),
+  };
+
+  let age = now.signed_duration_since(date);
+  let days = (now.date_naive() - date.date_naive()).num_days();
+
+  if age < Duration::minutes(1) {
+    String::from("Just now")
+  } else if age < Duration::hours(1) {
+    format!("{} min ago", age.num_minutes())
+  } else if days == 0 {
+    date.format("%H:%M").to_string()
+  } else if days == 1 {
+    date.format("Yesterday %H:%M").to_string()
+  } else if days < 7 {
+    date.format("%A %H:%M").to_string()
+  } else if date.year() == now.year() {
+    date.format("%e %B").to_string().trim().to_string()
+  } else {
+    date.format("%e %B %Y").to_string().trim().to_string()
+  }
+}
+
+// Formats the given duration in seconds like "42:05" or "1:02:03".
+fn format_duration(seconds: u64) -> String {
+  let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+  if hours > 0 {
+    format!("{}:{:02}:{:02}", hours, minutes, seconds)
+  } else {
+    format!("{}:{:02}", minutes, seconds)
+  }
+}
+
+// Returns the name of the day section the given unix timestamp belongs to. Dates in the
+// future are considered to be published today. Items without a date have a timestamp of
+// zero and thus end up in the "Older" section.
+fn get_section(date: i64, now: &DateTime<Local>) -> &'static str {
+  let days = match Local.timestamp_opt(date, 0).single() {
+    Some(date) => (now.date_naive() - date.date_naive()).num_days(),
+    None => return "Older",
+  };
+
+  if days <= 0 {
+    "Today"
+  } else if days == 1 {
+    "Yesterday"
+  } else if days < 7 {
+    "This Week"
+  } else {
+    "Older"
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+  // The structure of this custom widget is defined in the ItemRow.ui file.
+  #[derive(Debug, CompositeTemplate)]
+  #[template(resource = "/io/github/schneegans/BingeRSS/ui/ItemRow.ui")]
+  pub struct ItemRow {
+    #[template_child]
+    pub thumbnail: TemplateChild<gtk::Image>,
+    #[template_child]
+    pub media_box: TemplateChild<gtk::Box>,
+    #[template_child]
+    pub media_icon: TemplateChild<gtk::Image>,
+    #[template_child]
+    pub duration_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    pub play_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub download_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub star_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub link_icon: TemplateChild<gtk::Image>,
+
+    pub item: RefCell<Option<FeedItem>>,
+    pub read_handler: RefCell<Option<glib::SignalHandlerId>>,
+    pub show_feed_title: Cell<bool>,
+  }
+
+  impl Default for ItemRow {
+    fn default() -> Self {
+      Self {
+        thumbnail: TemplateChild::default(),
+        media_box: TemplateChild::default(),
+        media_icon: TemplateChild::default(),
+        duration_label: TemplateChild::default(),
+        play_button: TemplateChild::default(),
+        download_button: TemplateChild::default(),
+        star_button: TemplateChild::default(),
+        link_icon: TemplateChild::default(),
+        item: RefCell::new(None),
+        read_handler: RefCell::new(None),
+        show_feed_title: Cell::new(false),
+      }
+    }
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for ItemRow {
+    const NAME: &'static str = "ItemRow";
+    type Type = super::ItemRow;
+    type ParentType = adw::ActionRow;
+
+    fn class_init(klass: &mut Self::Class) {
+      klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+      obj.init_template();
+    }
+  }
+
+  impl ObjectImpl for ItemRow {
+    // The FeedItem usually outlives the row. Hence, the handler which updates the row
+    // when the item is read has to be disconnected.
+    fn dispose(&self) {
+      if let (Some(item), Some(handler)) =
+        (self.item.borrow().as_ref(), self.read_handler.take())
+      {
+        item.disconnect(handler);
+      }
+    }
+  }
+  impl WidgetImpl for ItemRow {}
+  impl ListBoxRowImpl for ItemRow {}
+  impl PreferencesRowImpl for ItemRow {}
+  impl ActionRowImpl for ItemRow {}
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A Wednesday in November, so that the tests are not affected by daylight saving time.
+  fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2022, 11, 16, 12, 0, 0).unwrap()
+  }
+
+  fn timestamp(month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+    Local
+      .with_ymd_and_hms(2022, month, day, hour, minute, 0)
+      .unwrap()
+      .timestamp()
+  }
+
+  #[test]
+  fn dates() {
+    let now = now();
+    let cases = [
+      (now.timestamp() + 3600, "Just now"),
+      (now.timestamp() - 30, "Just now"),
+      (now.timestamp() - 300, "5 min ago"),
+      (now.timestamp() - 3599, "59 min ago"),
+      (timestamp(11, 16, 0, 0), "00:00"),
+      (timestamp(11, 16, 10, 59), "10:59"),
+      (timestamp(11, 15, 23, 59), "Yesterday 23:59"),
+      (timestamp(11, 15, 0, 0), "Yesterday 00:00"),
+      (timestamp(11, 14, 8, 30), "Monday 08:30"),
+      (timestamp(11, 10, 0, 0), "Thursday 00:00"),
+      (timestamp(11, 9, 23, 59), "9 November"),
+      (timestamp(1, 1, 0, 0), "1 January"),
+      (timestamp(1, 1, 0, 0) - 1, "31 December 2021"),
+      (i64::MAX, ""),
+    ];
+
+    for (date, expected) in cases {
+      assert_eq!(format_date(date, &now), expected, "date: {}", date);
+    }
+  }
+
+  #[test]
+  fn durations() {
+    let cases = [
+      (0, "0:00"),
+      (59, "0:59"),
+      (61, "1:01"),
+      (2525, "42:05"),
+      (3599, "59:59"),
+      (3600, "1:00:00"),
+      (3723, "1:02:03"),
+      (36000, "10:00:00"),
+    ];
+
+    for (seconds, expected) in cases {
+      assert_eq!(format_duration(seconds), expected);
+    }
+  }
+
+  #[test]
+  fn sections() {
+    let now = now();
+    let cases = [
+      (timestamp(11, 17, 9, 0), "Today"),
+      (timestamp(11, 16, 0, 0), "Today"),
+      (timestamp(11, 15, 23, 59), "Yesterday"),
+      (timestamp(11, 15, 0, 0), "Yesterday"),
+      (timestamp(11, 14, 23, 59), "This Week"),
+      (timestamp(11, 10, 0, 0), "This Week"),
+      (timestamp(11, 9, 23, 59), "Older"),
+      (0, "Older"),
+      (i64::MAX, "Older"),
+    ];
+
+    for (date, expected) in cases {
+      assert_eq!(get_section(date, &now), expected, "date: {}", date);
+    }
+  }
+}
@@ -11,10 +11,20 @@
 
 // This module contains some objects which are components of the user interface.
 
+mod all_items_page;
 mod feed_page;
 mod feed_row;
+mod item_row;
+mod preferences_window;
+mod search_page;
+mod starred_page;
 mod window;
 
+pub use self::all_items_page::AllItemsPage;
 pub use self::feed_page::FeedPage;
 pub use self::feed_row::FeedRow;
+pub use self::item_row::ItemRow;
+pub use self::preferences_window::PreferencesWindow;
+pub use self::search_page::SearchPage;
+pub use self::starred_page::StarredPage;
 pub use self::window::Window;
@@ -0,0 +1,653 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::{gio, glib, CompositeTemplate};
+use std::{cell::RefCell, path::PathBuf};
+
+use crate::config;
+use crate::model::{FilterPreset, HighlightRule, HighlightStyle, MuteRule};
+
+// ---------------------------------------------------------------------------------------
+// The PreferencesWindow is derived from adw::PreferencesWindow. It is shown when the
+// app.preferences() action is activated and allows changing the global settings of
+// BingeRSS, like the download folder, the global mute and highlight rules, the filter
+// presets, additional certificate authorities, the network behavior, and the WebSub
+// callback. All values are directly stored in the settings.
+glib::wrapper! {
+  pub struct PreferencesWindow(ObjectSubclass<imp::PreferencesWindow>)
+      @extends gtk::Widget, gtk::Window, adw::Window, adw::PreferencesWindow,
+      @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl PreferencesWindow {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    glib::Object::builder().build()
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // Returns the folder to which enclosures of feed items are downloaded. If none has been
+  // configured, the user's download folder is used. If this does not exist either, the
+  // home directory is used.
+  pub fn get_download_folder(settings: &gio::Settings) -> PathBuf {
+    let folder = settings.string("download-folder");
+
+    if !folder.is_empty() {
+      return PathBuf::from(folder.as_str());
+    }
+
+    glib::user_special_dir(glib::UserDirectory::Downloads).unwrap_or_else(glib::home_dir)
+  }
+
+  // Returns the global mute rules. They are stored in a JSON string under the settings
+  // key io.github.schneegans.bingerss.mute-rules.
+  pub fn get_mute_rules(settings: &gio::Settings) -> Vec<MuteRule> {
+    let data = settings.string("mute-rules");
+    serde_json::from_str(data.as_str()).expect("valid json")
+  }
+
+  // Returns all filter presets. They are stored in a JSON string under the settings key
+  // io.github.schneegans.bingerss.filter-presets.
+  pub fn get_filter_presets(settings: &gio::Settings) -> Vec<FilterPreset> {
+    let data = settings.string("filter-presets");
+    serde_json::from_str(data.as_str()).expect("valid json")
+  }
+
+  // Stores the given filter presets in the settings.
+  pub fn set_filter_presets(settings: &gio::Settings, presets: &[FilterPreset]) {
+    let json = serde_json::to_string(presets).unwrap();
+    settings
+      .set_string("filter-presets", &json)
+      .expect("Failed to write settings!");
+  }
+
+  // Returns the global highlight rules. They are stored in a JSON string under the
+  // settings key io.github.schneegans.bingerss.highlight-rules.
+  pub fn get_highlight_rules(settings: &gio::Settings) -> Vec<HighlightRule> {
+    let data = settings.string("highlight-rules");
+    serde_json::from_str(data.as_str()).expect("valid json")
+  }
+
+  // Returns the paths to the PEM files containing additional certificate authorities.
+  // They are stored in a JSON string under the settings key
+  // io.github.schneegans.bingerss.ca-certificates.
+  pub fn get_ca_certificates(settings: &gio::Settings) -> Vec<String> {
+    let data = settings.string("ca-certificates");
+    serde_json::from_str(data.as_str()).expect("valid json")
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Shows a file chooser dialog which allows selecting a new download folder. The chosen
+  // folder is directly written to the settings.
+  fn choose_download_folder(&self) {
+    let dialog = gtk::FileChooserNative::new(
+      Some("Select Download Folder"),
+      Some(self),
+      gtk::FileChooserAction::SelectFolder,
+      Some("Select"),
+      Some("Cancel"),
+    );
+
+    let folder = gio::File::for_path(Self::get_download_folder(&self.imp().settings));
+    dialog.set_current_folder(Some(&folder)).ok();
+
+    dialog.connect_response(glib::clone!(@weak self as this => move |dialog, response| {
+      if response == gtk::ResponseType::Accept {
+        if let Some(path) = dialog.file().and_then(|f| f.path()) {
+          this
+            .imp()
+            .settings
+            .set_string("download-folder", &path.to_string_lossy())
+            .expect("Failed to write settings!");
+        }
+      }
+
+      this.imp().file_chooser.replace(None);
+    }));
+
+    dialog.show();
+
+    // The native dialog is not a widget, so we have to keep a reference to it while it is
+    // shown.
+    self.imp().file_chooser.replace(Some(dialog));
+  }
+
+  // Shows a file chooser dialog which allows selecting a PEM file with additional
+  // certificate authorities. The chosen file is directly added to the settings.
+  fn add_ca_certificate(&self) {
+    let dialog = gtk::FileChooserNative::new(
+      Some("Select Certificates"),
+      Some(self),
+      gtk::FileChooserAction::Open,
+      Some("Add"),
+      Some("Cancel"),
+    );
+
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("PEM Certificates"));
+    filter.add_pattern("*.pem");
+    filter.add_pattern("*.crt");
+    dialog.add_filter(&filter);
+
+    dialog.connect_response(glib::clone!(@weak self as this => move |dialog, response| {
+      if response == gtk::ResponseType::Accept {
+        if let Some(path) = dialog.file().and_then(|f| f.path()) {
+          let settings = &this.imp().settings;
+          let path = path.to_string_lossy().to_string();
+          let mut paths = Self::get_ca_certificates(settings);
+
+          if !paths.contains(&path) {
+            paths.push(path);
+            let json = serde_json::to_string(&paths).unwrap();
+            settings
+              .set_string("ca-certificates", &json)
+              .expect("Failed to write settings!");
+          }
+        }
+      }
+
+      this.imp().file_chooser.replace(None);
+    }));
+
+    dialog.show();
+
+    // The native dialog is not a widget, so we have to keep a reference to it while it is
+    // shown.
+    self.imp().file_chooser.replace(Some(dialog));
+  }
+
+  // Re-creates the list of files with additional certificate authorities. The list is
+  // hidden if there are none.
+  fn update_ca_certificate_list(&self) {
+    let list_box = &self.imp().ca_certificate_list_box;
+
+    while let Some(row) = list_box.first_child() {
+      list_box.remove(&row);
+    }
+
+    let paths = Self::get_ca_certificates(&self.imp().settings);
+
+    for (i, path) in paths.iter().enumerate() {
+      let name = PathBuf::from(path)
+        .file_name()
+        .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+      list_box.append(&self.create_rule_row("ca-certificates", i, &name, path));
+    }
+
+    list_box.set_visible(!paths.is_empty());
+  }
+
+  // Stores the given mute rules in the settings.
+  fn set_mute_rules(&self, rules: &[MuteRule]) {
+    let json = serde_json::to_string(rules).unwrap();
+
+    self
+      .imp()
+      .settings
+      .set_string("mute-rules", &json)
+      .expect("Failed to write settings!");
+  }
+
+  // Adds a rule for the text of the entry row and the kind selected in the combo row.
+  // Empty patterns and rules which exist already are ignored.
+  fn add_mute_rule(&self) {
+    let pattern = self.imp().mute_pattern_row.text().trim().to_string();
+    let kind = MuteRule::KINDS[self.imp().mute_kind_row.selected() as usize];
+    let rule = MuteRule { kind, pattern };
+
+    let mut rules = Self::get_mute_rules(&self.imp().settings);
+
+    if !rule.pattern.is_empty() && !rules.contains(&rule) {
+      rules.push(rule);
+      self.set_mute_rules(&rules);
+    }
+
+    self.imp().mute_pattern_row.set_text("");
+  }
+
+  // Re-creates the list of mute rules. Each rule is shown in a row with a button which
+  // removes the rule. The list is hidden if there are no rules.
+  fn update_mute_rule_list(&self) {
+    let list_box = &self.imp().mute_rule_list_box;
+
+    while let Some(row) = list_box.first_child() {
+      list_box.remove(&row);
+    }
+
+    let rules = Self::get_mute_rules(&self.imp().settings);
+
+    for (i, rule) in rules.iter().enumerate() {
+      let row =
+        self.create_rule_row("mute-rules", i, &rule.pattern, rule.kind.get_label());
+      list_box.append(&row);
+    }
+
+    list_box.set_visible(!rules.is_empty());
+  }
+
+  // Stores the given highlight rules in the settings.
+  fn set_highlight_rules(&self, rules: &[HighlightRule]) {
+    let json = serde_json::to_string(rules).unwrap();
+
+    self
+      .imp()
+      .settings
+      .set_string("highlight-rules", &json)
+      .expect("Failed to write settings!");
+  }
+
+  // Adds a rule for the text of the entry row and the kind, style, and importance
+  // selected in the rows above. Empty patterns and rules which exist already are ignored.
+  fn add_highlight_rule(&self) {
+    let imp = self.imp();
+    let rule = HighlightRule {
+      kind: HighlightRule::KINDS[imp.highlight_kind_row.selected() as usize],
+      pattern: imp.highlight_pattern_row.text().trim().to_string(),
+      style: HighlightStyle::ALL[imp.highlight_style_row.selected() as usize],
+      important: imp.highlight_important_switch.is_active(),
+    };
+
+    let mut rules = Self::get_highlight_rules(&imp.settings);
+
+    if !rule.pattern.is_empty() && !rules.contains(&rule) {
+      rules.push(rule);
+      self.set_highlight_rules(&rules);
+    }
+
+    imp.highlight_pattern_row.set_text("");
+  }
+
+  // Re-creates the list of highlight rules. Each rule is shown in a row with a button
+  // which removes the rule. The list is hidden if there are no rules.
+  fn update_highlight_rule_list(&self) {
+    let list_box = &self.imp().highlight_rule_list_box;
+
+    while let Some(row) = list_box.first_child() {
+      list_box.remove(&row);
+    }
+
+    let rules = Self::get_highlight_rules(&self.imp().settings);
+
+    for (i, rule) in rules.iter().enumerate() {
+      let mut subtitle = format!("{}, {}", rule.kind.get_label(), rule.style.get_label());
+      if rule.important {
+        subtitle.push_str(", Important");
+      }
+
+      let row = self.create_rule_row("highlight-rules", i, &rule.pattern, &subtitle);
+      list_box.append(&row);
+    }
+
+    list_box.set_visible(!rules.is_empty());
+  }
+
+  // Adds a preset with an empty filter and the name of the entry row. Empty names and
+  // names which are used already are ignored.
+  fn add_filter_preset(&self) {
+    let name = self.imp().filter_preset_row.text().trim().to_string();
+    let mut presets = Self::get_filter_presets(&self.imp().settings);
+
+    if !name.is_empty() && !presets.iter().any(|p| p.name == name) {
+      presets.push(FilterPreset {
+        name,
+        filter: String::new(),
+      });
+      Self::set_filter_presets(&self.imp().settings, &presets);
+    }
+
+    self.imp().filter_preset_row.set_text("");
+  }
+
+  // Re-creates the list of filter presets. Each preset is shown in an entry row which
+  // allows changing its filter and a button which removes the preset. The list is hidden
+  // if there are no presets.
+  fn update_filter_preset_list(&self) {
+    let list_box = &self.imp().filter_preset_list_box;
+
+    while let Some(row) = list_box.first_child() {
+      list_box.remove(&row);
+    }
+
+    let presets = Self::get_filter_presets(&self.imp().settings);
+
+    for (i, preset) in presets.iter().enumerate() {
+      let row = adw::EntryRow::builder()
+        .title(&preset.name)
+        .text(&preset.filter)
+        .show_apply_button(true)
+        .build();
+
+      row.connect_apply(glib::clone!(@weak self as this => move |row| {
+        let mut presets = Self::get_filter_presets(&this.imp().settings);
+        if let Some(preset) = presets.get_mut(i) {
+          preset.filter = row.text().to_string();
+          Self::set_filter_presets(&this.imp().settings, &presets);
+        }
+      }));
+
+      row.add_suffix(&self.create_remove_button("filter-presets", i));
+      list_box.append(&row);
+    }
+
+    list_box.set_visible(!presets.is_empty());
+  }
+
+  // Creates a row for a list of rules. The rules are stored as JSON array under the given
+  // settings key; the row contains a button which removes the rule at the given index
+  // from this array.
+  fn create_rule_row(
+    &self,
+    key: &'static str,
+    index: usize,
+    title: &str,
+    subtitle: &str,
+  ) -> adw::ActionRow {
+    let row = adw::ActionRow::builder()
+      .title(title)
+      .subtitle(subtitle)
+      .build();
+
+    row.add_suffix(&self.create_remove_button(key, index));
+    row
+  }
+
+  // Creates a button which removes the element at the given index from the JSON array
+  // stored under the given settings key.
+  fn create_remove_button(&self, key: &'static str, index: usize) -> gtk::Button {
+    let button = gtk::Button::builder()
+      .icon_name("user-trash-symbolic")
+      .tooltip_text("Remove")
+      .valign(gtk::Align::Center)
+      .build();
+    button.add_css_class("flat");
+
+    button.connect_clicked(glib::clone!(@weak self as this => move |_| {
+      let settings = &this.imp().settings;
+      let mut rules: Vec<serde_json::Value> =
+        serde_json::from_str(settings.string(key).as_str()).expect("valid json");
+
+      if index < rules.len() {
+        rules.remove(index);
+        let json = serde_json::to_string(&rules).unwrap();
+        settings.set_string(key, &json).expect("Failed to write settings!");
+      }
+    }));
+
+    button
+  }
+
+  // Shows the current download folder as subtitle of the corresponding row.
+  fn update_download_folder_row(&self) {
+    let folder = Self::get_download_folder(&self.imp().settings);
+    self
+      .imp()
+      .download_folder_row
+      .set_subtitle(&folder.to_string_lossy());
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+  // The structure of this custom widget is defined in the PreferencesWindow.ui file.
+  #[derive(Debug, CompositeTemplate)]
+  #[template(resource = "/io/github/schneegans/BingeRSS/ui/PreferencesWindow.ui")]
+  pub struct PreferencesWindow {
+    #[template_child]
+    pub download_folder_row: TemplateChild<adw::ActionRow>,
+    #[template_child]
+    pub download_folder_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub mute_kind_row: TemplateChild<adw::ComboRow>,
+    #[template_child]
+    pub mute_pattern_row: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub mute_rule_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub highlight_kind_row: TemplateChild<adw::ComboRow>,
+    #[template_child]
+    pub highlight_style_row: TemplateChild<adw::ComboRow>,
+    #[template_child]
+    pub highlight_important_switch: TemplateChild<gtk::Switch>,
+    #[template_child]
+    pub highlight_pattern_row: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub highlight_rule_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub filter_preset_row: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub filter_preset_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub ca_certificate_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub ca_certificate_list_box: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub pause_on_metered_switch: TemplateChild<gtk::Switch>,
+    #[template_child]
+    pub websub_callback_row: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub websub_address_row: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub websub_port_row: TemplateChild<adw::EntryRow>,
+
+    pub settings: gio::Settings,
+    pub file_chooser: RefCell<Option<gtk::FileChooserNative>>,
+  }
+
+  impl Default for PreferencesWindow {
+    fn default() -> Self {
+      Self {
+        download_folder_row: TemplateChild::default(),
+        download_folder_button: TemplateChild::default(),
+        mute_kind_row: TemplateChild::default(),
+        mute_pattern_row: TemplateChild::default(),
+        mute_rule_list_box: TemplateChild::default(),
+        highlight_kind_row: TemplateChild::default(),
+        highlight_style_row: TemplateChild::default(),
+        highlight_important_switch: TemplateChild::default(),
+        highlight_pattern_row: TemplateChild::default(),
+        highlight_rule_list_box: TemplateChild::default(),
+        filter_preset_row: TemplateChild::default(),
+        filter_preset_list_box: TemplateChild::default(),
+        ca_certificate_button: TemplateChild::default(),
+        ca_certificate_list_box: TemplateChild::default(),
+        pause_on_metered_switch: TemplateChild::default(),
+        websub_callback_row: TemplateChild::default(),
+        websub_address_row: TemplateChild::default(),
+        websub_port_row: TemplateChild::default(),
+        settings: gio::Settings::new(config::APP_ID),
+        file_chooser: RefCell::new(None),
+      }
+    }
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for PreferencesWindow {
+    const NAME: &'static str = "PreferencesWindow";
+    type Type = super::PreferencesWindow;
+    type ParentType = adw::PreferencesWindow;
+
+    fn class_init(klass: &mut Self::Class) {
+      klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+      obj.init_template();
+    }
+  }
+
+  impl ObjectImpl for PreferencesWindow {
+    // Most components of this custom widget are defined in the UI file. However, the
+    // connections to the settings have to be set up in code. This is done here, whenever
+    // a new PreferencesWindow is constructed.
+    fn constructed(&self) {
+      self.parent_constructed();
+
+      // Show the current download folder and update it whenever it changes.
+      self.obj().update_download_folder_row();
+      self.settings.connect_changed(
+        Some("download-folder"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_download_folder_row();
+        }),
+      );
+
+      self.download_folder_button.connect_clicked(
+        glib::clone!(@weak self as this => move |_| {
+          this.obj().choose_download_folder();
+        }),
+      );
+
+      // Show the current mute rules and update them whenever they change. New rules are
+      // added when the apply button of the entry row is clicked.
+      let labels: Vec<&str> = MuteRule::KINDS.iter().map(|k| k.get_label()).collect();
+      self
+        .mute_kind_row
+        .set_model(Some(&gtk::StringList::new(&labels)));
+
+      self.obj().update_mute_rule_list();
+      self.settings.connect_changed(
+        Some("mute-rules"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_mute_rule_list();
+        }),
+      );
+
+      self
+        .mute_pattern_row
+        .connect_apply(glib::clone!(@weak self as this => move |_| {
+          this.obj().add_mute_rule();
+        }));
+
+      // The same is done for the highlight rules.
+      let labels: Vec<&str> =
+        HighlightRule::KINDS.iter().map(|k| k.get_label()).collect();
+      self
+        .highlight_kind_row
+        .set_model(Some(&gtk::StringList::new(&labels)));
+
+      let labels: Vec<&str> = HighlightStyle::ALL.iter().map(|s| s.get_label()).collect();
+      self
+        .highlight_style_row
+        .set_model(Some(&gtk::StringList::new(&labels)));
+
+      self.obj().update_highlight_rule_list();
+      self.settings.connect_changed(
+        Some("highlight-rules"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_highlight_rule_list();
+        }),
+      );
+
+      self.highlight_pattern_row.connect_apply(
+        glib::clone!(@weak self as this => move |_| {
+          this.obj().add_highlight_rule();
+        }),
+      );
+
+      // Show the current filter presets and update them whenever they change.
+      self.obj().update_filter_preset_list();
+      self.settings.connect_changed(
+        Some("filter-presets"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_filter_preset_list();
+        }),
+      );
+
+      self
+        .filter_preset_row
+        .connect_apply(glib::clone!(@weak self as this => move |_| {
+          this.obj().add_filter_preset();
+        }));
+
+      // Show the files with additional certificate authorities and update them whenever
+      // they change.
+      self.obj().update_ca_certificate_list();
+      self.settings.connect_changed(
+        Some("ca-certificates"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.obj().update_ca_certificate_list();
+        }),
+      );
+
+      self.ca_certificate_button.connect_clicked(
+        glib::clone!(@weak self as this => move |_| {
+          this.obj().add_ca_certificate();
+        }),
+      );
+
+      // The switch for metered connections is directly bound to the settings.
+      self
+        .settings
+        .bind(
+          "pause-on-metered",
+          &self.pause_on_metered_switch.get(),
+          "active",
+        )
+        .build();
+
+      // The WebSub callback, the local address, and the port are only stored when the
+      // apply button is clicked, as each change restarts the listener.
+      self
+        .websub_callback_row
+        .set_text(&self.settings.string("websub-callback"));
+      self.websub_callback_row.connect_apply(
+        glib::clone!(@weak self as this => move |row| {
+          this
+            .settings
+            .set_string("websub-callback", row.text().trim())
+            .expect("Failed to write settings!");
+        }),
+      );
+
+      self
+        .websub_address_row
+        .set_text(&self.settings.string("websub-address"));
+      self.websub_address_row.connect_apply(
+        glib::clone!(@weak self as this => move |row| {
+          this
+            .settings
+            .set_string("websub-address", row.text().trim())
+            .expect("Failed to write settings!");
+        }),
+      );
+
+      // Invalid ports are not stored; the row shows the stored port again instead.
+      self
+        .websub_port_row
+        .set_text(&self.settings.int("websub-port").to_string());
+      self
+        .websub_port_row
+        .connect_apply(glib::clone!(@weak self as this => move |row| {
+          match row.text().trim().parse::<u16>() {
+            Ok(port) if port > 0 => this
+              .settings
+              .set_int("websub-port", port as i32)
+              .expect("Failed to write settings!"),
+            _ => row.set_text(&this.settings.int("websub-port").to_string()),
+          }
+        }));
+    }
+  }
+
+  impl WidgetImpl for PreferencesWindow {}
+
+  impl WindowImpl for PreferencesWindow {}
+
+  impl AdwWindowImpl for PreferencesWindow {}
+
+  impl PreferencesWindowImpl for PreferencesWindow {}
+}
@@ -0,0 +1,165 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::{gio, glib, CompositeTemplate};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::model::{
+  find_duplicates, get_other_feed_titles, normalize_url, FeedItem, ItemIndex,
+};
+use crate::view::ItemRow;
+
+// ---------------------------------------------------------------------------------------
+// The SearchPage is derived from gtk::Box. It is shown on the right if the user starts a
+// global search. It contains a search entry and a list of all items from all feeds and
+// the stored item history which match the search query. The name of the feed each item
+// belongs to is shown in the subtitle of the rows. Stories which are contained in
+// multiple feeds are shown only once.
+glib::wrapper! {
+  pub struct SearchPage(ObjectSubclass<imp::SearchPage>)
+      @extends gtk::Widget, gtk::Box,
+      @implements gtk::Accessible, gtk::Buildable, gtk::Orientable;
+}
+
+impl SearchPage {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    glib::Object::builder().build()
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // This assigns the ItemIndex which is used for querying the items.
+  pub fn set_index(&self, index: Rc<ItemIndex>) {
+    self.imp().index.replace(Some(index));
+  }
+
+  // Moves the keyboard focus to the search entry and selects its content.
+  pub fn focus_search_entry(&self) {
+    self.imp().search_entry.grab_focus();
+    self.imp().search_entry.select_region(0, -1);
+  }
+
+  // Replaces the content of the search entry. The results are updated once the entry
+  // emits its search-changed signal.
+  pub fn set_query(&self, query: &str) {
+    self.imp().search_entry.set_text(query);
+  }
+
+  // --------------------------------------------------------------------- private methods
+
+  // Queries the ItemIndex for the current content of the search entry and shows the
+  // results. Duplicates are collapsed into the result with the highest rank.
+  fn update_results(&self) {
+    let query = self.imp().search_entry.text();
+    self.imp().model.remove_all();
+
+    if let Some(index) = self.imp().index.borrow().as_ref() {
+      let results = match index.search(&query, 100) {
+        Ok(results) => results,
+        Err(err) => {
+          println!("Failed to search for '{}': {}", query, err);
+          return;
+        }
+      };
+
+      for group in find_duplicates(&results) {
+        let item = &group[0];
+        item.set_also_in(get_other_feed_titles(&group, item));
+
+        let is_read = group
+          .iter()
+          .any(|i| index.is_read(&normalize_url(&i.get_url())).unwrap_or(false));
+        item.set_property("read", is_read);
+
+        self.imp().model.append(item);
+      }
+    }
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+  // The structure of this custom widget is defined in the SearchPage.ui file.
+  #[derive(Debug, CompositeTemplate)]
+  #[template(resource = "/io/github/schneegans/BingeRSS/ui/SearchPage.ui")]
+  pub struct SearchPage {
+    #[template_child]
+    pub search_entry: TemplateChild<gtk::SearchEntry>,
+    #[template_child]
+    pub result_list_box: TemplateChild<gtk::ListBox>,
+
+    pub model: gio::ListStore,
+    pub index: RefCell<Option<Rc<ItemIndex>>>,
+  }
+
+  impl Default for SearchPage {
+    fn default() -> Self {
+      Self {
+        search_entry: TemplateChild::default(),
+        result_list_box: TemplateChild::default(),
+        model: gio::ListStore::new(FeedItem::static_type()),
+        index: RefCell::new(None),
+      }
+    }
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for SearchPage {
+    const NAME: &'static str = "SearchPage";
+    type Type = super::SearchPage;
+    type ParentType = gtk::Box;
+
+    fn class_init(klass: &mut Self::Class) {
+      klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+      obj.init_template();
+    }
+  }
+
+  impl ObjectImpl for SearchPage {
+    // Most components of this custom widget are defined in the UI file. However, some
+    // things have to be set up in code. This is done here, whenever a new SearchPage is
+    // constructed.
+    fn constructed(&self) {
+      self.parent_constructed();
+
+      // Each result shows the title of the feed it belongs to as subtitle.
+      self
+        .result_list_box
+        .bind_model(Some(&self.model), move |item| {
+          let item = item.downcast_ref::<FeedItem>().unwrap();
+          let row = ItemRow::new();
+          row.set_item(item);
+          row.set_show_feed_title(true);
+          row.upcast()
+        });
+
+      // Update the results whenever the search query changes. The search entry already
+      // delays this signal a bit while the user is typing.
+      self.search_entry.connect_search_changed(
+        glib::clone!(@weak self as this => move |_| {
+          this.obj().update_results();
+        }),
+      );
+    }
+  }
+
+  impl WidgetImpl for SearchPage {}
+
+  impl BoxImpl for SearchPage {}
+}
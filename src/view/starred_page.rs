@@ -0,0 +1,141 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+use adw::{prelude::*, subclass::prelude::*};
+use gtk::{gio, glib, CompositeTemplate};
+
+use crate::config;
+use crate::model::FeedItem;
+use crate::view::ItemRow;
+
+// ---------------------------------------------------------------------------------------
+// The StarredPage is derived from gtk::Box. It is shown on the right if the "Starred"
+// pseudo-feed is selected in the sidebar. It shows all items which have been starred by
+// the user, regardless of whether their source feed still exists. Like the FeedPage, it
+// has a filter entry; the filter is stored directly in the settings.
+glib::wrapper! {
+  pub struct StarredPage(ObjectSubclass<imp::StarredPage>)
+      @extends gtk::Widget, gtk::Box,
+      @implements gtk::Accessible, gtk::Buildable, gtk::Orientable;
+}
+
+impl StarredPage {
+  // ----------------------------------------------------------------- constructor methods
+
+  pub fn new() -> Self {
+    glib::Object::builder().build()
+  }
+
+  // ---------------------------------------------------------------------- public methods
+
+  // This assigns the list of starred FeedItems to the StarredPage.
+  pub fn set_model(&self, model: &gio::ListStore) {
+    let filter_model = gtk::FilterListModel::new(Some(model), Some(&self.imp().filter));
+    self
+      .imp()
+      .item_list_box
+      .bind_model(Some(&filter_model), move |item| {
+        let row = ItemRow::new();
+        row.set_item(item.downcast_ref::<FeedItem>().unwrap());
+        row.set_show_feed_title(true);
+        row.upcast()
+      });
+  }
+}
+
+mod imp {
+  use super::*;
+
+  // -------------------------------------------------------------------------------------
+  // The structure of this custom widget is defined in the StarredPage.ui file.
+  #[derive(Debug, CompositeTemplate)]
+  #[template(resource = "/io/github/schneegans/BingeRSS/ui/StarredPage.ui")]
+  pub struct StarredPage {
+    #[template_child]
+    pub filter_entry: TemplateChild<adw::EntryRow>,
+    #[template_child]
+    pub item_list_box: TemplateChild<gtk::ListBox>,
+
+    pub filter: gtk::StringFilter,
+    pub settings: gio::Settings,
+  }
+
+  impl Default for StarredPage {
+    fn default() -> Self {
+      Self {
+        filter_entry: TemplateChild::default(),
+        item_list_box: TemplateChild::default(),
+        filter: gtk::StringFilter::builder()
+          .ignore_case(true)
+          .match_mode(gtk::StringFilterMatchMode::Substring)
+          .expression(gtk::PropertyExpression::new(
+            FeedItem::static_type(),
+            gtk::Expression::NONE,
+            "title",
+          ))
+          .build(),
+        settings: gio::Settings::new(config::APP_ID),
+      }
+    }
+  }
+
+  #[glib::object_subclass]
+  impl ObjectSubclass for StarredPage {
+    const NAME: &'static str = "StarredPage";
+    type Type = super::StarredPage;
+    type ParentType = gtk::Box;
+
+    fn class_init(klass: &mut Self::Class) {
+      klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+      obj.init_template();
+    }
+  }
+
+  impl ObjectImpl for StarredPage {
+    // Most components of this custom widget are defined in the UI file. However, the
+    // filter has to be set up in code. This is done here, whenever a new StarredPage is
+    // constructed.
+    fn constructed(&self) {
+      self.parent_constructed();
+
+      // The filter of the starred items is stored in the settings.
+      self
+        .settings
+        .bind("starred-filter", &self.filter_entry.get(), "text")
+        .build();
+
+      // Make sure that the item list is filtered whenever the filter value changes.
+      self
+        .filter_entry
+        .bind_property("text", &self.filter, "search")
+        .flags(glib::BindingFlags::SYNC_CREATE)
+        .build();
+
+      // The rows show relative timestamps, so we have to update them periodically.
+      glib::timeout_add_seconds_local(
+        60,
+        glib::clone!(@weak self as this => @default-return glib::Continue(false),
+          move || {
+            ItemRow::update_dates(&this.item_list_box);
+            glib::Continue(true)
+          }
+        ),
+      );
+    }
+  }
+
+  impl WidgetImpl for StarredPage {}
+
+  impl BoxImpl for StarredPage {}
+}
@@ -10,18 +10,23 @@
 // SPDX-License-Identifier: MIT
 
 use adw::{prelude::*, subclass::prelude::*};
-use gtk::{gio, glib, CompositeTemplate};
+use gtk::{gdk, gio, glib, CompositeTemplate};
+use std::rc::Rc;
 
 use crate::config;
-use crate::model::Feed;
-use crate::view::{FeedPage, FeedRow};
+use crate::model::{Feed, FeedOrder, ItemIndex};
+use crate::view::{AllItemsPage, FeedPage, FeedRow, SearchPage, StarredPage};
 
 // ---------------------------------------------------------------------------------------
 // The Window is derived from adw::Window. It primarily contains an adw::Leaflet with two
 // panes: On the left, there is a sidebar with a list of all configured feeds, on the
 // right there are details for the currently selected feed. The sidebar is realized as a
 // gtk::ListBox full of custom FeedRows, the feed details page is a gtk::Stack containing
-// a custom FeedPage for each feed.
+// a custom FeedPage for each feed. On top of the sidebar, there is an additional row for
+// the "Starred" pseudo-feed which shows a StarredPage when activated. Above, there is
+// a row for the "All Items" pseudo-feed which shows the items of all feeds in an
+// AllItemsPage. The global search is shown in a SearchPage in the same gtk::Stack. Below
+// the panes, a banner explains why the feeds are currently not refreshed.
 glib::wrapper! {
   pub struct Window(ObjectSubclass<imp::Window>)
       @extends gtk::Widget, gtk::Window, adw::Window,
@@ -53,13 +58,52 @@ impl Window {
     feed_row.set_widget_name(&feed.get_id());
     self.imp().feed_list.append(&feed_row);
 
-    // Re-sort the FeedRows if the title of the Feed changed.
-    feed.connect_notify_local(
-      Some("title"),
-      glib::clone!(@weak self as this => move |_, _| {
-        this.imp().feed_list.invalidate_sort();
-      }),
+    // Re-sort the FeedRows if any property of the Feed changed which is considered by
+    // one of the available feed orders.
+    for property in ["title", "unread", "state", "position"] {
+      feed.connect_notify_local(
+        Some(property),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.imp().feed_list.invalidate_sort();
+        }),
+      );
+    }
+
+    // If the feeds are ordered manually, the FeedRows can be reordered via drag and drop.
+    // The ID of the dragged feed is used as content.
+    let drag_source = gtk::DragSource::new();
+    drag_source.set_actions(gdk::DragAction::MOVE);
+    drag_source.connect_prepare(
+      glib::clone!(@weak self as this, @weak feed => @default-return None,
+        move |_, _, _| {
+          if this.get_feed_order() != FeedOrder::Manual {
+            return None;
+          }
+          Some(gdk::ContentProvider::for_value(&feed.get_id().to_value()))
+        }
+      ),
     );
+    drag_source.connect_drag_begin(glib::clone!(@weak feed_row => move |source, _| {
+      let paintable = gtk::WidgetPaintable::new(Some(&feed_row));
+      source.set_icon(Some(&paintable), 0, 0);
+    }));
+    feed_row.add_controller(&drag_source);
+
+    // If a FeedRow is dropped onto another FeedRow, it is moved to its position.
+    let drop_target = gtk::DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+    drop_target.connect_drop(
+      glib::clone!(@weak self as this, @weak feed_row => @default-return false,
+        move |_, value, _, _| {
+          let id = value.get::<String>();
+          if id.is_err() {
+            return false;
+          }
+          this.move_feed(&id.unwrap(), &feed_row);
+          true
+        }
+      ),
+    );
+    feed_row.add_controller(&drop_target);
 
     // Also update the title of the headerbar if the title of the Feed changed.
     feed
@@ -93,14 +137,201 @@ impl Window {
     feed.notify("state");
   }
 
+  // This adds the "Starred" pseudo-feed to the Window. It consists of a row at the top of
+  // the sidebar and a StarredPage which shows the given list of starred FeedItems. This
+  // should be called only once.
+  pub fn set_starred_items(&self, model: &gio::ListStore) {
+    // Add a new row to the list on the left. This uses the same style as the FeedRows.
+    let starred_row = adw::ActionRow::builder()
+      .title("Starred")
+      .activatable(true)
+      .selectable(true)
+      .name("starred")
+      .build();
+
+    let icon = gtk::Image::builder()
+      .icon_name("starred-symbolic")
+      .width_request(24)
+      .build();
+    starred_row.add_prefix(&icon);
+
+    // Show the number of starred items in a badge.
+    let badge = gtk::Label::builder().valign(gtk::Align::Center).build();
+    badge.add_css_class("item-count-badge");
+    starred_row.add_suffix(&badge);
+
+    model
+      .bind_property("n-items", &badge, "label")
+      .transform_to(|_, n: u32| Some(n.to_string()))
+      .flags(glib::BindingFlags::SYNC_CREATE)
+      .build();
+    model
+      .bind_property("n-items", &badge, "visible")
+      .transform_to(|_, n: u32| Some(n > 0))
+      .flags(glib::BindingFlags::SYNC_CREATE)
+      .build();
+
+    self.imp().feed_list.append(&starred_row);
+
+    // Now add the StarredPage to show the starred items.
+    let starred_page = StarredPage::new();
+    starred_page.set_model(model);
+    self
+      .imp()
+      .feed_details
+      .add_named(&starred_page, Some("starred"));
+
+    // Show the StarredPage if the row is activated.
+    starred_row.connect_activated(
+      glib::clone!(@weak self as this, @weak starred_page => move |starred_row| {
+        this.show_feed_pages();
+        this.imp().feed_details.set_visible_child(&starred_page);
+        this.imp().header_label.set_label(&starred_row.title());
+      }),
+    );
+
+    // If there are starred items, we show them even if there are no feeds configured.
+    if model.n_items() > 0 {
+      self.imp().main_stack.set_visible_child_name("main_leaflet");
+      self.imp().feed_list.select_row(Some(&starred_row));
+      self.imp().feed_details.set_visible_child(&starred_page);
+      self.imp().header_label.set_label(&starred_row.title());
+    }
+  }
+
+  // This adds the "All Items" pseudo-feed to the Window. It consists of a row at the top
+  // of the sidebar and an AllItemsPage which shows the given list of FeedItems. This
+  // should be called only once.
+  pub fn set_all_items(&self, model: &gio::ListStore) {
+    // Add a new row to the list on the left. This uses the same style as the FeedRows.
+    let all_items_row = adw::ActionRow::builder()
+      .title("All Items")
+      .activatable(true)
+      .selectable(true)
+      .name("all-items")
+      .build();
+
+    let icon = gtk::Image::builder()
+      .icon_name("view-list-symbolic")
+      .width_request(24)
+      .build();
+    all_items_row.add_prefix(&icon);
+
+    self.imp().feed_list.append(&all_items_row);
+
+    // Now add the AllItemsPage to show the items.
+    let all_items_page = AllItemsPage::new();
+    all_items_page.set_model(model);
+    self
+      .imp()
+      .feed_details
+      .add_named(&all_items_page, Some("all-items"));
+
+    // Show the AllItemsPage if the row is activated.
+    all_items_row.connect_activated(
+      glib::clone!(@weak self as this, @weak all_items_page => move |all_items_row| {
+        this.show_feed_pages();
+        this.imp().feed_details.set_visible_child(&all_items_page);
+        this.imp().header_label.set_label(&all_items_row.title());
+      }),
+    );
+  }
+
+  // This assigns the ItemIndex which is used by the global search. The SearchPage is
+  // added to the details stack; it can be shown with show_search().
+  pub fn set_item_index(&self, index: Rc<ItemIndex>) {
+    let search_page = SearchPage::new();
+    search_page.set_index(index);
+    self
+      .imp()
+      .feed_details
+      .add_named(&search_page, Some("search"));
+  }
+
+  // Shows the SearchPage of the global search and searches for the given query. This
+  // does nothing if set_item_index() has not been called before.
+  pub fn show_search_for(&self, query: &str) {
+    let page = self.imp().feed_details.child_by_name("search");
+
+    if let Some(search_page) = page.and_then(|p| p.downcast::<SearchPage>().ok()) {
+      search_page.set_query(query);
+      self.show_search();
+    }
+  }
+
+  // Selects the feed with the given ID in the sidebar, shows its FeedPage, and scrolls to
+  // the item with the given URL. This returns false if there is no such feed. If the URL
+  // is empty, only the feed is shown. If the item is not shown on the FeedPage, for
+  // instance because it does not match the filter, the global search for the given
+  // title is shown instead.
+  pub fn show_item(&self, id: &String, url: &str, title: &str) -> bool {
+    let (row, page) = match (self.get_feed_row(id), self.get_feed_page(id)) {
+      (Some(row), Some(page)) => (row, page),
+      _ => return false,
+    };
+
+    self.imp().main_stack.set_visible_child_name("main_leaflet");
+    self.imp().feed_list.select_row(Some(&row));
+    row.activate();
+
+    if !url.is_empty() && !page.show_item(url) {
+      self.show_search_for(title);
+    }
+
+    true
+  }
+
+  // Shows the SearchPage of the global search and focuses its search entry. This does
+  // nothing if set_item_index() has not been called before.
+  pub fn show_search(&self) {
+    let page = self.imp().feed_details.child_by_name("search");
+    if page.is_none() {
+      return;
+    }
+
+    let search_page = page.unwrap().downcast::<SearchPage>().unwrap();
+
+    self.imp().main_stack.set_visible_child_name("main_leaflet");
+    self.imp().feed_list.unselect_all();
+    self.imp().feed_details.set_visible_child(&search_page);
+    self.imp().header_label.set_label("Search");
+    self.show_feed_pages();
+
+    search_page.focus_search_entry();
+  }
+
   // This method removes the currently selected feed from the user interface and returns
   // its ID. The next feed in the sidebar will become selected thereafter. If there is no
-  // feed left, nothing will happen and the method will return None.
+  // feed left or the "Starred" or "All Items" pseudo-feeds are selected, nothing will
+  // happen and the method will return None.
   pub fn remove_selected_feed(&self) -> Option<String> {
-    let list = &self.imp().feed_list;
-    let row = list.selected_row()?;
+    let row = self.imp().feed_list.selected_row()?;
     let id = row.property::<String>("name");
 
+    if self.remove_feed(&id) {
+      Some(id)
+    } else {
+      None
+    }
+  }
+
+  // This method removes the feed with the given ID from the user interface. If the feed
+  // is currently selected, the next feed in the sidebar will become selected thereafter.
+  // If there is no such feed, nothing will happen and the method will return false. The
+  // "Starred" and "All Items" pseudo-feeds cannot be removed.
+  pub fn remove_feed(&self, id: &String) -> bool {
+    if id == "starred" || id == "all-items" {
+      return false;
+    }
+
+    let list = &self.imp().feed_list;
+    let row = match self.get_feed_row(id) {
+      Some(row) => row,
+      None => return false,
+    };
+
+    let was_selected = row.is_selected();
+
     // Choose the item which will be selected after this operation. Usually, it will be
     // the feed below the currently selected feed. However, if the last feed is to be
     // deleted, we have to select the one above it.
@@ -126,8 +357,14 @@ impl Window {
     list.remove(&row);
 
     // Remove the FeedPage from the details stack.
-    let page = self.get_feed_page(&id)?;
-    self.imp().feed_details.remove(&page);
+    if let Some(page) = self.get_feed_page(id) {
+      self.imp().feed_details.remove(&page);
+    }
+
+    // Feeds which are not selected can be removed without changing the selection.
+    if !was_selected {
+      return true;
+    }
 
     // Clear the headerbar label.
     self.imp().header_label.set_label("");
@@ -148,7 +385,7 @@ impl Window {
       self.show_feed_rows();
     }
 
-    Some(id)
+    true
   }
 
   // If the leaflet is folded, this will show the left sidebar area with the FeedRows.
@@ -167,6 +404,11 @@ impl Window {
       .set_visible_child(&self.imp().feed_details_page.get());
   }
 
+  // Shows a toast without any button at the bottom of the screen.
+  pub fn show_message(&self, title: &str) {
+    self.imp().toast_overlay.add_toast(&adw::Toast::new(title));
+  }
+
   // Shows a toast with the given message at the bottom of the screen.
   pub fn show_toast(
     &self,
@@ -184,8 +426,69 @@ impl Window {
     self.imp().toast_overlay.add_toast(&toast);
   }
 
+  // Shows the given message in the banner at the bottom of the window. This is used
+  // instead of showing an error for each feed if the feeds cannot be refreshed. The
+  // banner contains a button which refreshes all feeds if show_refresh is set. If the
+  // message is None, the banner is hidden.
+  pub fn show_network_banner(&self, message: Option<&str>, show_refresh: bool) {
+    self
+      .imp()
+      .network_label
+      .set_label(message.unwrap_or_default());
+    self.imp().network_refresh_button.set_visible(show_refresh);
+    self.imp().network_banner.set_revealed(message.is_some());
+  }
+
   // --------------------------------------------------------------------- private methods
 
+  // Returns the order of the FeedRows as currently configured in the settings.
+  fn get_feed_order(&self) -> FeedOrder {
+    FeedOrder::from_nick(self.imp().settings.string("feed-order").as_str())
+  }
+
+  // Moves the FeedRow of the feed with the given ID to the position of the given target
+  // FeedRow. This updates the position property of all feeds accordingly.
+  fn move_feed(&self, id: &String, target: &FeedRow) {
+    let list = &self.imp().feed_list;
+
+    // Collect all FeedRows in their current order.
+    let mut rows: Vec<FeedRow> = vec![];
+    let mut i = 0;
+    while let Some(row) = list.row_at_index(i) {
+      if let Ok(row) = row.downcast::<FeedRow>() {
+        rows.push(row);
+      }
+      i += 1;
+    }
+
+    // Move the dragged row to the position of the target row.
+    let source = rows.iter().position(|r| r.widget_name() == id.as_str());
+    let destination = rows.iter().position(|r| r == target);
+
+    if source.is_none() || destination.is_none() {
+      return;
+    }
+
+    let row = rows.remove(source.unwrap());
+    rows.insert(destination.unwrap(), row);
+
+    // Store the new positions in the feeds. This will re-sort the list.
+    for (i, row) in rows.iter().enumerate() {
+      if let Some(feed) = row.get_feed() {
+        feed.set_property("position", i as u32);
+      }
+    }
+  }
+
+  // Searches the sidebar for the row corresponding to the feed with the given ID. This
+  // will return None if no such row is found.
+  fn get_feed_row(&self, id: &String) -> Option<gtk::ListBoxRow> {
+    let list = &self.imp().feed_list;
+    std::iter::successors(list.first_child(), |w| w.next_sibling())
+      .find(|w| w.widget_name() == id.as_str())
+      .and_then(|w| w.downcast::<gtk::ListBoxRow>().ok())
+  }
+
   // Searches the gtk::Stack containing all FeedPages for the page corresponding to the
   // feed with the given ID. This will return None if no such page is found.
   fn get_feed_page(&self, id: &String) -> Option<FeedPage> {
@@ -251,6 +554,12 @@ mod imp {
     pub header_label: TemplateChild<gtk::Label>,
     #[template_child]
     pub feed_details: TemplateChild<gtk::Stack>,
+    #[template_child]
+    pub network_banner: TemplateChild<gtk::InfoBar>,
+    #[template_child]
+    pub network_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    pub network_refresh_button: TemplateChild<gtk::Button>,
     pub settings: gio::Settings,
   }
 
@@ -265,6 +574,9 @@ mod imp {
         feed_list: TemplateChild::default(),
         header_label: TemplateChild::default(),
         feed_details: TemplateChild::default(),
+        network_banner: TemplateChild::default(),
+        network_label: TemplateChild::default(),
+        network_refresh_button: TemplateChild::default(),
         settings: gio::Settings::new(config::APP_ID),
       }
     }
@@ -295,27 +607,47 @@ mod imp {
       // Restore the window size from the previous session.
       self.obj().load_window_size();
 
-      // Make sure that the FeedRows are sorted alphabetically.
-      self.feed_list.set_sort_func(|a, b| -> gtk::Ordering {
-        let a = a
-          .downcast_ref::<adw::ActionRow>()
-          .unwrap()
-          .title()
-          .to_lowercase();
-        let b = b
-          .downcast_ref::<adw::ActionRow>()
-          .unwrap()
-          .title()
-          .to_lowercase();
-
-        if a < b {
-          gtk::Ordering::Smaller
-        } else if a > b {
-          gtk::Ordering::Larger
-        } else {
-          gtk::Ordering::Equal
-        }
-      });
+      // Make sure that the FeedRows are sorted according to the order configured in the
+      // settings. The rows of the "All Items" and "Starred" pseudo-feeds are always shown
+      // at the top.
+      self.feed_list.set_sort_func(
+        glib::clone!(@weak self as this => @default-return gtk::Ordering::Equal,
+          move |a, b| {
+            let pseudo_feeds = ["all-items", "starred"];
+            let a_pseudo = pseudo_feeds.iter().position(|n| a.widget_name() == *n);
+            let b_pseudo = pseudo_feeds.iter().position(|n| b.widget_name() == *n);
+
+            match (a_pseudo, b_pseudo) {
+              (Some(a), Some(b)) => return a.cmp(&b).into(),
+              (Some(_), None) => return gtk::Ordering::Smaller,
+              (None, Some(_)) => return gtk::Ordering::Larger,
+              (None, None) => {}
+            }
+
+            let a = a.downcast_ref::<FeedRow>().unwrap().get_feed();
+            let b = b.downcast_ref::<FeedRow>().unwrap().get_feed();
+
+            if a.is_none() || b.is_none() {
+              return gtk::Ordering::Equal;
+            }
+
+            this.obj().get_feed_order().compare(&a.unwrap(), &b.unwrap()).into()
+          }
+        ),
+      );
+
+      // Re-sort the FeedRows whenever the order is changed. The order can be changed via
+      // the win.feed-order action which is used in the main menu.
+      self.settings.connect_changed(
+        Some("feed-order"),
+        glib::clone!(@weak self as this => move |_, _| {
+          this.feed_list.invalidate_sort();
+        }),
+      );
+
+      self
+        .obj()
+        .add_action(&self.settings.create_action("feed-order"));
     }
   }
 
@@ -0,0 +1,456 @@
+// ------------------------------------------------------------------------------------ //
+//                           ___ _               ___  ___ ___                           //
+//                          | _ |_)_ _  __ _ ___| _ \/ __/ __|                          //
+//                          | _ \ | ' \/ _` / -_)   /\__ \__ \                          //
+//                          |___/_|_||_\__, \___|_|_\|___/___/                          //
+//                                     |___/                                            //
+// ------------------------------------------------------------------------------------ //
+
+// SPDX-FileCopyrightText: Simon Schneegans <code@simonschneegans.de>
+// SPDX-License-Identifier: MIT
+
+// These tests start BingeRSS on a private session bus and use its D-Bus interfaces. Each
+// test executes itself again inside dbus-run-session, which stops the bus daemon once the
+// test has finished. As BingeRSS requires a display, the tests are ignored by default;
+// run them with `cargo test -- --ignored`.
+
+use gtk::{gio, glib, prelude::*};
+use std::{
+  cell::RefCell,
+  path::{Path, PathBuf},
+  process::{Child, Command},
+  rc::Rc,
+  time::{Duration, Instant},
+};
+
+// The application ID is generated by meson.
+#[path = "../src/config.rs"]
+#[allow(dead_code)]
+mod config;
+
+const INTERFACE_NAME: &str = "io.github.schneegans.BingeRSS";
+
+// This environment variable is set when a test is executed inside dbus-run-session.
+const PRIVATE_BUS: &str = "BINGE_RSS_TEST_PRIVATE_BUS";
+
+// The received D-Bus signals with their name and parameters.
+type Signals = Rc<RefCell<Vec<(String, glib::Variant)>>>;
+
+// ---------------------------------------------------------------------------------------
+// A temporary directory for the given test. It contains the compiled settings schema, the
+// settings of BingeRSS in a keyfile, and its data directory, so the tests do not modify
+// the data of the user. The directory is removed when this is dropped.
+struct TestDir {
+  path: PathBuf,
+}
+
+impl TestDir {
+  // Creates the directory and compiles the settings schema into it.
+  fn new(test: &str) -> Self {
+    let path =
+      std::env::temp_dir().join(format!("binge-rss-{}-{}", test, std::process::id()));
+    let schema_dir = path.join("schemas");
+    std::fs::create_dir_all(path.join("data")).expect("Failed to create data directory!");
+    std::fs::create_dir_all(path.join("config/glib-2.0/settings"))
+      .expect("Failed to create config directory!");
+    std::fs::create_dir_all(&schema_dir).expect("Failed to create schema directory!");
+
+    let schema = std::fs::read_to_string(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/data/BingeRSS.gschema.xml.in"
+    ))
+    .expect("Failed to read settings schema!");
+
+    std::fs::write(
+      schema_dir.join(format!("{}.gschema.xml", config::APP_ID)),
+      schema.replace("@app-id@", config::APP_ID),
+    )
+    .expect("Failed to write settings schema!");
+
+    let status = Command::new("glib-compile-schemas")
+      .arg(&schema_dir)
+      .status()
+      .expect("Failed to run glib-compile-schemas!");
+    assert!(status.success(), "Failed to compile settings schema!");
+
+    Self { path }
+  }
+
+  // Stores the given JSON string under the feeds settings key. This has to be called
+  // before BingeRSS is started.
+  fn set_feeds(&self, json: &str) {
+    std::fs::write(
+      self.path.join("config/glib-2.0/settings/keyfile"),
+      format!(
+        "[io/github/schneegans/BingeRSS]\nfeeds={}\n",
+        json.to_variant().print(false)
+      ),
+    )
+    .expect("Failed to write settings!");
+  }
+}
+
+impl Drop for TestDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.path);
+  }
+}
+
+// ---------------------------------------------------------------------------------------
+// A running instance of BingeRSS which uses the given test directory. Its credentials are
+// kept in memory. The process is killed when this is dropped.
+struct App {
+  process: Child,
+}
+
+impl App {
+  // Starts BingeRSS with the given arguments.
+  fn start(dir: &TestDir, args: &[&str]) -> Self {
+    let process = Command::new(env!("CARGO_BIN_EXE_binge-rss"))
+      .args(args)
+      .env("GSETTINGS_SCHEMA_DIR", dir.path.join("schemas"))
+      .env("GSETTINGS_BACKEND", "keyfile")
+      .env("BINGE_RSS_CREDENTIAL_BACKEND", "memory")
+      .env("XDG_CONFIG_HOME", dir.path.join("config"))
+      .env("XDG_DATA_HOME", dir.path.join("data"))
+      .spawn()
+      .expect("Failed to start BingeRSS!");
+
+    Self { process }
+  }
+
+  // Kills the process and waits until its bus name has been released.
+  fn stop(mut self, ctx: &glib::MainContext, connection: &gio::DBusConnection) {
+    let _ = self.process.kill();
+    let _ = self.process.wait();
+    assert!(
+      wait_for(ctx, || !has_owner(connection)),
+      "BingeRSS did not leave the bus!"
+    );
+  }
+}
+
+impl Drop for App {
+  fn drop(&mut self) {
+    let _ = self.process.kill();
+    let _ = self.process.wait();
+  }
+}
+
+// Executes the test with the given name again inside dbus-run-session and checks that it
+// succeeded. This returns false if the test is already running on the private bus; in
+// this case, the caller has to execute the actual test.
+fn run_on_private_bus(test: &str) -> bool {
+  if std::env::var_os(PRIVATE_BUS).is_some() {
+    return false;
+  }
+
+  let status = Command::new("dbus-run-session")
+    .arg("--")
+    .arg(std::env::current_exe().expect("Failed to get test executable!"))
+    .args([test, "--exact", "--ignored", "--nocapture"])
+    .env(PRIVATE_BUS, "1")
+    .status()
+    .expect("Failed to start dbus-run-session!");
+
+  assert!(status.success(), "{} failed on the private bus!", test);
+
+  true
+}
+
+// Runs the given function with a new thread-default main context. Replies and signals
+// are dispatched to this context while wait_for() iterates it.
+fn with_context<F: FnOnce(&glib::MainContext)>(f: F) {
+  let ctx = glib::MainContext::new();
+  ctx
+    .with_thread_default(|| f(&ctx))
+    .expect("context can be acquired");
+}
+
+// Iterates the given main context until the given condition is met or ten seconds have
+// passed. Returns true if the condition has been met.
+fn wait_for<F: Fn() -> bool>(ctx: &glib::MainContext, condition: F) -> bool {
+  let start = Instant::now();
+
+  while start.elapsed() < Duration::from_secs(10) {
+    while ctx.iteration(false) {}
+
+    if condition() {
+      return true;
+    }
+
+    std::thread::sleep(Duration::from_millis(50));
+  }
+
+  false
+}
+
+// Calls the given method of the given interface and returns the reply.
+fn call(
+  connection: &gio::DBusConnection,
+  path: &str,
+  interface: &str,
+  method: &str,
+  parameters: Option<&glib::Variant>,
+) -> Result<glib::Variant, glib::Error> {
+  connection.call_sync(
+    Some(config::APP_ID),
+    path,
+    interface,
+    method,
+    parameters,
+    None,
+    gio::DBusCallFlags::NONE,
+    5000,
+    gio::Cancellable::NONE,
+  )
+}
+
+// Collects all signals of the BingeRSS interface.
+fn subscribe(connection: &gio::DBusConnection) -> Signals {
+  let signals: Signals = Rc::new(RefCell::new(vec![]));
+
+  connection.signal_subscribe(
+    None,
+    Some(INTERFACE_NAME),
+    None,
+    None,
+    None,
+    gio::DBusSignalFlags::NONE,
+    glib::clone!(@strong signals => move |_, _, _, _, signal, parameters| {
+      signals
+        .borrow_mut()
+        .push((signal.to_string(), parameters.clone()));
+    }),
+  );
+
+  signals
+}
+
+// Returns true if the given application ID is owned by a process on the bus.
+fn has_owner(connection: &gio::DBusConnection) -> bool {
+  connection
+    .call_sync(
+      Some("org.freedesktop.DBus"),
+      "/org/freedesktop/DBus",
+      "org.freedesktop.DBus",
+      "NameHasOwner",
+      Some(&(config::APP_ID,).to_variant()),
+      None,
+      gio::DBusCallFlags::NONE,
+      5000,
+      gio::Cancellable::NONE,
+    )
+    .ok()
+    .and_then(|reply| reply.get::<(bool,)>())
+    .map_or(false, |(owned,)| owned)
+}
+
+// Returns the object path at which BingeRSS exports its interfaces.
+fn object_path() -> String {
+  format!("/{}", config::APP_ID.replace('.', "/"))
+}
+
+// Writes the RSS 2.0 fixture to the given path. If an extra item is given, it is added
+// to the feed.
+fn write_feed(path: &Path, extra_item: Option<(&str, &str)>) {
+  let feed = std::fs::read_to_string(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/rss20.xml"
+  ))
+  .expect("fixture exists");
+
+  let feed = match extra_item {
+    Some((title, url)) => feed.replace(
+      "</channel>",
+      &format!(
+        "<item><title>{}</title><link>{}</link></item></channel>",
+        title, url
+      ),
+    ),
+    None => feed,
+  };
+
+  std::fs::write(path, feed).expect("Failed to write feed!");
+}
+
+#[test]
+#[ignore = "requires a display and dbus-run-session"]
+fn dbus_interface() {
+  if run_on_private_bus("dbus_interface") {
+    return;
+  }
+
+  with_context(|ctx| {
+    let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE)
+      .expect("Failed to connect to the private bus!");
+    let signals = subscribe(&connection);
+
+    let dir = TestDir::new("dbus_interface");
+    let _app = App::start(&dir, &[]);
+    assert!(
+      wait_for(ctx, || has_owner(&connection)),
+      "BingeRSS did not appear on the bus!"
+    );
+
+    let path = object_path();
+    let invoke = |method: &str, parameters: Option<&glib::Variant>| {
+      call(&connection, &path, INTERFACE_NAME, method, parameters)
+    };
+
+    // Add a local feed and make sure that it is listed.
+    let file = dir.path.join("feed.xml");
+    write_feed(&file, None);
+    let url = glib::filename_to_uri(&file, None).unwrap().to_string();
+
+    let reply = invoke("AddFeed", Some(&(url.as_str(), "Example", "").to_variant()))
+      .expect("AddFeed failed!");
+    let (id,) = reply.get::<(String,)>().unwrap();
+
+    let reply = invoke("ListFeeds", None).unwrap();
+    let (feeds,) = reply
+      .get::<(Vec<(String, String, String, String)>,)>()
+      .unwrap();
+    assert!(feeds.contains(&(id.clone(), "Example".into(), url.clone(), "".into())));
+
+    // Command feeds cannot be added via D-Bus.
+    assert!(invoke("AddFeed", Some(&("exec:true", "", "").to_variant())).is_err());
+
+    // Once the feed has been loaded, the FeedUpdated signal is emitted.
+    let updated = |signals: &Signals| {
+      signals
+        .borrow()
+        .iter()
+        .filter(|(signal, parameters)| {
+          signal == "FeedUpdated"
+            && parameters.get::<(String, i32)>().map(|(i, _)| i) == Some(id.clone())
+        })
+        .count()
+    };
+    assert!(
+      wait_for(ctx, || updated(&signals) > 0),
+      "FeedUpdated was not emitted!"
+    );
+
+    let reply = invoke("GetUnreadCounts", None).unwrap();
+    let (counts,) = reply
+      .get::<(std::collections::HashMap<String, i32>,)>()
+      .unwrap();
+    assert!(counts.contains_key(&id));
+
+    // If the feed contains a new item, the NewItems signal announces it.
+    let new_item = ("New Item", "https://example.com/rss/new");
+    write_feed(&file, Some(new_item));
+    invoke("Refresh", Some(&(id.as_str(),).to_variant())).expect("Refresh failed!");
+
+    let expected: (String, Vec<(String, String)>) =
+      (id.clone(), vec![(new_item.0.into(), new_item.1.into())]);
+    assert!(
+      wait_for(ctx, || signals.borrow().iter().any(
+        |(signal, parameters)| {
+          signal == "NewItems"
+            && parameters.get::<(String, Vec<(String, String)>)>()
+              == Some(expected.clone())
+        }
+      )),
+      "NewItems was not emitted!"
+    );
+
+    // The other methods should work with the new feed but fail for unknown IDs.
+    for method in ["MarkRead", "Refresh"] {
+      assert!(invoke(method, Some(&(id.as_str(),).to_variant())).is_ok());
+      assert!(invoke(method, Some(&("foo",).to_variant())).is_err());
+    }
+
+    // Finally, remove the feed again.
+    invoke("RemoveFeed", Some(&(id.as_str(),).to_variant())).expect("RemoveFeed failed!");
+
+    let reply = invoke("ListFeeds", None).unwrap();
+    let (feeds,) = reply
+      .get::<(Vec<(String, String, String, String)>,)>()
+      .unwrap();
+    assert!(feeds.iter().all(|feed| feed.0 != id));
+  });
+}
+
+#[test]
+#[ignore = "requires a display and dbus-run-session"]
+fn search_provider_service() {
+  if run_on_private_bus("search_provider_service") {
+    return;
+  }
+
+  with_context(|ctx| {
+    let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE)
+      .expect("Failed to connect to the private bus!");
+
+    // Configure a local feed and start BingeRSS once so that its items are indexed.
+    let dir = TestDir::new("search_provider_service");
+    let file = dir.path.join("feed.xml");
+    write_feed(&file, None);
+    let url = glib::filename_to_uri(&file, None).unwrap().to_string();
+    dir.set_feeds(&format!(
+      r#"[{{"title":"Example","url":"{}","viewed":0}}]"#,
+      url
+    ));
+
+    let path = format!("{}/SearchProvider", object_path());
+    let invoke = |method: &str, parameters: &glib::Variant| {
+      call(
+        &connection,
+        &path,
+        "org.gnome.Shell.SearchProvider2",
+        method,
+        Some(parameters),
+      )
+    };
+
+    let dated = String::from("https://example.com/rss/dated");
+    let initial_results = || {
+      invoke("GetInitialResultSet", &(vec!["dated"],).to_variant())
+        .ok()
+        .and_then(|reply| reply.get::<(Vec<String>,)>())
+    };
+
+    // The items are indexed once the feed has been loaded.
+    let app = App::start(&dir, &[]);
+    assert!(
+      wait_for(ctx, || initial_results() == Some((vec![dated.clone()],))),
+      "The items were not indexed!"
+    );
+    app.stop(ctx, &connection);
+
+    // GNOME Shell starts BingeRSS as a D-Bus service. In this case, no window is opened
+    // and no feed is loaded, but the search provider should still find the items.
+    let _app = App::start(&dir, &["--gapplication-service"]);
+    assert!(
+      wait_for(ctx, || has_owner(&connection)),
+      "BingeRSS did not appear on the bus!"
+    );
+
+    assert_eq!(initial_results(), Some((vec![dated.clone()],)));
+
+    let reply = invoke(
+      "GetSubsearchResultSet",
+      &(vec![dated.as_str()], vec!["dated", "item"]).to_variant(),
+    )
+    .expect("GetSubsearchResultSet failed!");
+    assert_eq!(reply.get::<(Vec<String>,)>(), Some((vec![dated.clone()],)));
+
+    let reply = invoke(
+      "GetSubsearchResultSet",
+      &(vec![dated.as_str()], vec!["undated"]).to_variant(),
+    )
+    .expect("GetSubsearchResultSet failed!");
+    assert_eq!(reply.get::<(Vec<String>,)>(), Some((Vec::<String>::new(),)));
+
+    let reply = invoke("GetResultMetas", &(vec![dated.as_str()],).to_variant())
+      .expect("GetResultMetas failed!");
+    let (metas,) = reply
+      .get::<(Vec<std::collections::HashMap<String, glib::Variant>>,)>()
+      .unwrap();
+    assert_eq!(metas.len(), 1);
+    assert_eq!(metas[0]["id"].get::<String>(), Some(dated.clone()));
+    assert_eq!(metas[0]["name"].get::<String>(), Some("Dated Item".into()));
+  });
+}